@@ -0,0 +1,56 @@
+//! A dispatcher with 200 clauses, written with `case` and with `cond`. Both
+//! are lowered into tables when the procedure is defined, so the time to
+//! dispatch should not depend on the position of the matching clause.
+
+#![feature(test)]
+
+extern crate test;
+
+#[cfg(test)]
+mod tests {
+    use parsley::prelude::*;
+    use test::{black_box, Bencher};
+
+    const CLAUSES: usize = 200;
+
+    fn case_dispatcher() -> String {
+        let clauses = (0..CLAUSES)
+            .map(|i| format!("(({i}) {i})"))
+            .collect::<String>();
+        format!("(define (dispatch n) (case n {clauses} (else -1)))")
+    }
+
+    fn cond_dispatcher() -> String {
+        let clauses = (0..CLAUSES)
+            .map(|i| format!("((= n {i}) {i})"))
+            .collect::<String>();
+        format!("(define (dispatch n) (cond {clauses} (else -1)))")
+    }
+
+    fn bench_dispatch(b: &mut Bencher, dispatcher: &str, n: usize) {
+        let mut ctx = Context::base();
+        ctx.run(dispatcher).unwrap();
+        let call = format!("(dispatch {n})");
+        b.iter(|| black_box(ctx.run(&call).unwrap()));
+    }
+
+    #[bench]
+    fn case_first(b: &mut Bencher) {
+        bench_dispatch(b, &case_dispatcher(), 0);
+    }
+
+    #[bench]
+    fn case_last(b: &mut Bencher) {
+        bench_dispatch(b, &case_dispatcher(), CLAUSES - 1);
+    }
+
+    #[bench]
+    fn cond_first(b: &mut Bencher) {
+        bench_dispatch(b, &cond_dispatcher(), 0);
+    }
+
+    #[bench]
+    fn cond_last(b: &mut Bencher) {
+        bench_dispatch(b, &cond_dispatcher(), CLAUSES - 1);
+    }
+}
@@ -0,0 +1,20 @@
+//! `Context::base()` registers every language-level definition the first time
+//! it is called on a thread. Later calls copy that namespace instead, which
+//! is what a page (or a server making a context per request) pays for after
+//! the first. The namespace is not precomputed at build time, so the first
+//! call is not measured here.
+
+#![feature(test)]
+
+extern crate test;
+
+#[cfg(test)]
+mod tests {
+    use parsley::prelude::*;
+    use test::{black_box, Bencher};
+
+    #[bench]
+    fn base_context(b: &mut Bencher) {
+        b.iter(|| black_box(Context::base()));
+    }
+}
@@ -0,0 +1,28 @@
+//! Globals (like `+` and `=` below) are looked up on every iteration of a
+//! loop, after a scan of the environment stack, so this shows how much that
+//! scan costs as scopes nest.
+
+#![feature(test)]
+
+extern crate test;
+
+#[cfg(test)]
+mod tests {
+    use parsley::prelude::*;
+    use test::{black_box, Bencher};
+
+    const LOOP: &str = "(do ((i 0 (+ i 1)) (acc 0 (+ acc i))) ((= i 1000) acc))";
+
+    #[bench]
+    fn arithmetic_loop(b: &mut Bencher) {
+        let mut ctx = Context::base();
+        b.iter(|| black_box(ctx.run(LOOP).unwrap()));
+    }
+
+    #[bench]
+    fn arithmetic_loop_nested(b: &mut Bencher) {
+        let mut ctx = Context::base();
+        let code = format!("(let ((a 1)) (let ((b 2)) (let ((c 3)) (let ((d 4)) {LOOP}))))");
+        b.iter(|| black_box(ctx.run(&code).unwrap()));
+    }
+}
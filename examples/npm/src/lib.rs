@@ -1,24 +1,64 @@
 use std::fmt::Write;
 use wasm_bindgen::prelude::*;
 
+use parsley::ReplControl;
+
+/// The most output one call to `run` may produce, so that a runaway loop
+/// fails instead of freezing the page.
+const OUTPUT_LIMIT: usize = 1 << 20;
+
+fn fresh_context() -> parsley::Context {
+    let mut ctx = parsley::Context::base().capturing();
+    ctx.set_output_limit(Some(OUTPUT_LIMIT));
+    ctx
+}
+
 #[wasm_bindgen]
-pub struct Context(parsley::Context);
+pub struct Context {
+    ctx: parsley::Context,
+    on_exit: Option<js_sys::Function>,
+}
 
 #[allow(clippy::new_without_default)]
 #[wasm_bindgen]
 impl Context {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        Self(parsley::Context::base().capturing())
+        Self {
+            ctx: fresh_context(),
+            on_exit: None,
+        }
+    }
+
+    /// Set a function to call with the exit code when code run in this
+    /// context calls `(exit [code])`.
+    #[wasm_bindgen(js_name = onExit)]
+    pub fn on_exit(&mut self, callback: js_sys::Function) {
+        self.on_exit = Some(callback);
     }
 
     pub fn run(&mut self, code: &str) -> String {
         // do it
-        let evaled = self.0.run(code);
+        let evaled = self.ctx.run(code);
 
         // get the output
-        let mut buf = self.0.get_output().unwrap_or_default();
-        self.0.capture();
+        let mut buf = self.ctx.get_output().unwrap_or_default();
+        self.ctx.capture();
+
+        // exiting and restarting are left to the page
+        match ReplControl::of(&evaled) {
+            ReplControl::Exit(code) => {
+                if let Some(callback) = &self.on_exit {
+                    let _ = callback.call1(&JsValue::NULL, &JsValue::from(code));
+                }
+                return buf;
+            }
+            ReplControl::Restart => {
+                self.ctx = fresh_context();
+                return buf;
+            }
+            ReplControl::Continue => (),
+        }
 
         // put the results in the string
         let _ = match evaled {
@@ -3,6 +3,16 @@ use std::mem::take;
 
 use yew::prelude::*;
 
+/// The most output one input may produce, so that a runaway loop fails
+/// instead of freezing the tab.
+const OUTPUT_LIMIT: usize = 1 << 20;
+
+fn fresh_context() -> parsley::Context {
+    let mut ctx = parsley::Context::base().capturing();
+    ctx.set_output_limit(Some(OUTPUT_LIMIT));
+    ctx
+}
+
 pub struct Terminal {
     cmd_history: Vec<String>,
     cmd_idx: usize,
@@ -28,7 +38,7 @@ impl Component for Terminal {
             cmd_history: Vec::new(),
             cmd_idx: 0,
             cmd_tmp: None,
-            context: parsley::Context::base().capturing(),
+            context: fresh_context(),
             history: String::with_capacity(99999),
             value: String::new(),
             input_ref: Default::default(),
@@ -79,8 +89,21 @@ impl Component for Terminal {
                     self.history.push_str(&side_effects);
                 }
                 self.context.capture();
+                // there is no leaving the page, so exiting starts over too
+                match parsley::ReplControl::of(&evaled) {
+                    parsley::ReplControl::Exit(code) => {
+                        writeln!(self.history, "Exited with code {}", code).unwrap();
+                        self.context = fresh_context();
+                    }
+                    parsley::ReplControl::Restart => {
+                        self.history.clear();
+                        self.context = fresh_context();
+                    }
+                    parsley::ReplControl::Continue => (),
+                }
                 // show actual output
                 match evaled {
+                    Err(parsley::Error::Control(_)) => (),
                     Ok(result) => {
                         // print result, if it's not empty
                         let res = format!("{}", result);
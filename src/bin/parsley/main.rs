@@ -1,10 +1,12 @@
 use std::fs;
 use std::io::{self, Read, Result};
 use std::path::PathBuf;
+use std::process;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 use parsley::prelude::*;
+use parsley::ReplControl;
 mod repl;
 
 #[derive(Debug, Parser)]
@@ -19,6 +21,14 @@ struct Cli {
     /// Read and evaluate code from file
     #[clap(parse(from_os_str))]
     file: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print a markdown reference of the bindings in the base context
+    Doc,
 }
 
 fn main() -> Result<()> {
@@ -26,6 +36,11 @@ fn main() -> Result<()> {
 
     let mut base_context = Context::base();
 
+    if let Some(Command::Doc) = args.command {
+        print!("{}", base_context.bindings_markdown());
+        return Ok(());
+    }
+
     let code = if let Some(f_name) = args.file {
         fs::read_to_string(&f_name)?
     } else if args.read_stdin {
@@ -37,17 +52,27 @@ fn main() -> Result<()> {
     };
 
     if !code.is_empty() {
-        match base_context.run(&code) {
-            Ok(tree) => {
-                println!("{}", tree);
-            }
-            Err(error) => eprintln!("{}", error),
-        };
+        let result = base_context.run(&code);
+        match ReplControl::of(&result) {
+            ReplControl::Exit(code) => process::exit(code),
+            ReplControl::Restart => base_context = Context::base(),
+            ReplControl::Continue => match result {
+                Ok(tree) => {
+                    println!("{}", tree);
+                }
+                Err(error) => eprintln!("{}", error),
+            },
+        }
     }
 
     if code.is_empty() || args.force_interactive {
         match repl::repl(&mut base_context) {
-            Ok(res) => println!("{}", res),
+            Ok((res, code)) => {
+                println!("{}", res);
+                if code != 0 {
+                    process::exit(code);
+                }
+            }
             Err(err) => eprintln!("{}", err),
         }
     }
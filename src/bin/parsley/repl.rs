@@ -1,14 +1,86 @@
+use std::io::{self, IsTerminal};
+use std::ops::RangeInclusive;
+
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-use parsley::Context;
+use parsley::{Context, ReplControl, Snapshot};
 
 const NULL: &str = "'()";
 const REPL_PROMPT: &str = "> ";
 const REPL_WELCOME_MSG: &str = concat!("Welcome to PARSLEY v", env!("CARGO_PKG_VERSION"), ".");
 const REPL_EXIT_MSG: &str = "\nLeaving PARSLEY.\n";
 
-pub fn repl(ctx: &mut Context) -> Result<String, ReadlineError> {
+/// Split a special command (`.name arg` or `,name arg`) into its name and
+/// argument.
+fn command(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix('.').or_else(|| line.strip_prefix(','))?;
+    let (name, arg) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some((name, arg.trim()))
+}
+
+/// Parse a 1-based, inclusive range of history entries: `N`, `N..M` or `N..`.
+fn history_range(arg: &str, len: usize) -> Option<RangeInclusive<usize>> {
+    let (start, end) = match arg.split_once("..") {
+        Some((start, "")) => (start.parse().ok()?, len),
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let n = arg.parse().ok()?;
+            (n, n)
+        }
+    };
+
+    if start == 0 || start > end || end > len {
+        return None;
+    }
+
+    Some(start - 1..=end - 1)
+}
+
+/// Print what is known about a binding: its kind, its signature or arity and
+/// its documentation.
+fn describe(ctx: &Context, name: &str) {
+    match ctx.describe(name) {
+        Some(text) => print!("{}", text),
+        None => println!("`{}` is not defined", name),
+    }
+}
+
+fn eval_and_print(ctx: &mut Context, code: &str) -> ReplControl {
+    let result = ctx.run(code);
+    for warning in ctx.take_warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let control = ReplControl::of(&result);
+    match result {
+        Err(_) if control != ReplControl::Continue => (),
+        Ok(result) => {
+            let res = format!("{}", result);
+            if !res.is_empty() {
+                println!("{}", res);
+            }
+        }
+        Err(error) => {
+            let mut message = error.to_string();
+            // highlight any names suggested in place of an undefined symbol
+            if io::stdout().is_terminal() {
+                for name in error.suggestions() {
+                    message = message.replace(
+                        &format!("`{}`", name),
+                        &format!("\x1b[1;32m{}\x1b[0m", name),
+                    );
+                }
+            }
+            println!("{}", message);
+        }
+    }
+    control
+}
+
+/// Run the REPL until it is left, returning a farewell message and the exit
+/// code to leave the process with.
+pub fn repl(ctx: &mut Context) -> Result<(String, i32), ReadlineError> {
     print!(
         "\n{border}\n{side}{line_1:^72}{side}\n{side}{line_2:^72}{side}\n{border}\n\n",
         border = NULL.repeat(26),
@@ -18,37 +90,100 @@ pub fn repl(ctx: &mut Context) -> Result<String, ReadlineError> {
     );
 
     let mut rl = Editor::<()>::new()?;
+    // code entered this session, numbered from 1 for `.replay`
+    let mut history: Vec<String> = Vec::new();
+    // the environment before each input, and the input itself
+    let mut undo: Vec<(Snapshot, String)> = Vec::new();
+    let mut redo: Vec<(Snapshot, String)> = Vec::new();
+    // what the last input asked the REPL to do next
+    let mut control = ReplControl::Continue;
 
     loop {
         match rl.readline(REPL_PROMPT) {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
+                let line = line.trim();
+
                 // check for empty line/special commands
-                match line.trim() {
-                    "" => continue,
-                    ".exit" => break Ok(REPL_EXIT_MSG.to_string()),
-                    ".clear" => {
+                match command(line) {
+                    _ if line.is_empty() => continue,
+                    Some(("exit", _)) => break Ok((REPL_EXIT_MSG.to_string(), 0)),
+                    Some(("undo", _)) => match undo.pop() {
+                        Some((snapshot, input)) => {
+                            redo.push((ctx.snapshot(), input.clone()));
+                            ctx.restore(&snapshot);
+                            println!("Undid `{}`", input);
+                        }
+                        None => println!("Nothing to undo"),
+                    },
+                    Some(("redo", _)) => match redo.pop() {
+                        Some((snapshot, input)) => {
+                            undo.push((ctx.snapshot(), input.clone()));
+                            ctx.restore(&snapshot);
+                            println!("Redid `{}`", input);
+                        }
+                        None => println!("Nothing to redo"),
+                    },
+                    Some(("clear", _)) => {
+                        undo.push((ctx.snapshot(), line.to_string()));
+                        redo.clear();
                         rl.clear_history();
+                        history.clear();
                         ctx.pop();
                     }
-                    ".help" => {
+                    Some(("help", "")) => {
                         print!("\n{}\n", include_str!("help.txt"));
                     }
-                    other => match ctx.run(other) {
-                        Ok(result) => {
-                            let res = format!("{}", result);
-                            if !res.is_empty() {
-                                println!("{}", res);
+                    Some(("help", name)) => describe(ctx, name),
+                    Some(("history", _)) => {
+                        for (i, code) in history.iter().enumerate() {
+                            println!("{:>4}  {}", i + 1, code);
+                        }
+                    }
+                    Some(("replay", arg)) => match history_range(arg, history.len()) {
+                        Some(range) => {
+                            undo.push((ctx.snapshot(), line.to_string()));
+                            redo.clear();
+                            for i in range {
+                                let code = history[i].clone();
+                                println!("{}{}", REPL_PROMPT, code);
+                                control = eval_and_print(ctx, &code);
+                                if control != ReplControl::Continue {
+                                    break;
+                                }
                             }
                         }
-                        Err(error) => println!("{}", error),
+                        None => println!(
+                            "Invalid history range `{}`: expected N, N..M or N.. within 1..{}",
+                            arg,
+                            history.len()
+                        ),
                     },
+                    _ => {
+                        undo.push((ctx.snapshot(), line.to_string()));
+                        redo.clear();
+                        history.push(line.to_string());
+                        control = eval_and_print(ctx, line);
+                    }
                 }
             }
             Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => {
-                break Ok(REPL_EXIT_MSG.to_string());
+                break Ok((REPL_EXIT_MSG.to_string(), 0));
             }
             Err(error) => break Err(error),
         }
+
+        match std::mem::replace(&mut control, ReplControl::Continue) {
+            ReplControl::Continue => (),
+            ReplControl::Exit(code) => break Ok((REPL_EXIT_MSG.to_string(), code)),
+            ReplControl::Restart => {
+                *ctx = Context::base();
+                undo.clear();
+                redo.clear();
+                rl.clear_history();
+                history.clear();
+                println!("Restarted with a fresh context");
+            }
+        }
     }
 }
@@ -0,0 +1,77 @@
+use super::super::Error;
+use super::{Context, SExp};
+
+impl Context {
+    /// Make a [language-level definition](#structfield.lang) available under
+    /// another name as well, e.g. while moving to the name used by a standard.
+    ///
+    /// # Errors
+    /// Returns `Err` if `name` is not a language-level definition.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.alias("foldl", "reduce-left").unwrap();
+    /// assert_eq!(
+    ///     ctx.run("(reduce-left + 0 '(1 2 3))").unwrap(),
+    ///     SExp::from(6)
+    /// );
+    /// assert!(ctx.alias("potato", "tomato").is_err());
+    /// ```
+    pub fn alias(&mut self, name: &str, alias: &str) -> Result<(), Error> {
+        let value = self
+            .lang
+            .get(name)
+            .cloned()
+            .ok_or_else(|| self.undefined(name))?;
+        self.lang.insert(alias.to_string(), value);
+        Ok(())
+    }
+
+    /// Mark a language-level definition as deprecated, optionally in favor
+    /// of another name. The first time it is used, a warning is added to
+    /// those returned by [`take_warnings`](#method.take_warnings).
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.deprecate("foldl", Some("fold-left"));
+    /// ctx.run("(foldl + 0 '(1 2)) (foldl + 0 '(3 4))").unwrap();
+    /// assert_eq!(
+    ///     ctx.take_warnings(),
+    ///     vec!["`foldl` is deprecated, use `fold-left` instead"]
+    /// );
+    /// assert!(ctx.take_warnings().is_empty());
+    /// ```
+    pub fn deprecate(&mut self, name: &str, replacement: Option<&str>) {
+        self.deprecated
+            .insert(name.to_string(), replacement.map(String::from));
+    }
+
+    /// Take the warnings produced since the last call, oldest first.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Warn about the use of a deprecated name, unless it has been warned
+    /// about already or refers to something other than the deprecated
+    /// definition.
+    pub(super) fn check_deprecated(&mut self, name: &str, value: &SExp) {
+        if self.lang.get(name) != Some(value) {
+            return;
+        }
+
+        if let Some(replacement) = self.deprecated.remove(name) {
+            self.warnings.push(match replacement {
+                Some(replacement) => {
+                    format!("`{name}` is deprecated, use `{replacement}` instead")
+                }
+                None => format!("`{name}` is deprecated"),
+            });
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Debug-only checks that native procedures are registered with the arity
+//! their implementations actually expect.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use super::super::super::proc::PureFn;
+use super::super::super::Primitive::{
+    Boolean, Character, Number, Procedure, String as LispString, Symbol, Undefined,
+};
+use super::super::super::SExp::{self, Atom, Null};
+use super::super::super::{Func, Proc};
+use super::Context;
+
+/// Arguments to probe with. They have different types, so an implementation
+/// that looks at an argument at all should react to at least one of them.
+fn dummies() -> [SExp; 7] {
+    [
+        Null,
+        Atom(Undefined),
+        Atom(Boolean(false)),
+        Atom(Number(0.into())),
+        Atom(LispString(String::new())),
+        Atom(Symbol("x".to_string())),
+        Atom(Character('x')),
+    ]
+}
+
+/// Call a procedure's implementation directly (bypassing the arity check)
+/// with `n` copies of `arg`, followed by `last` (if any). Returns `None` if it
+/// panicked.
+fn probe(f: &PureFn, n: usize, arg: &SExp, last: Option<&SExp>) -> Option<String> {
+    let args = (0..n).map(|_| arg).chain(last).cloned().collect::<SExp>();
+
+    panic::catch_unwind(AssertUnwindSafe(|| format!("{:?}", f(args)))).ok()
+}
+
+/// Check one procedure, returning a description of the problem (if any).
+fn check(name: &str, proc: &Proc) -> Option<String> {
+    // procedures that need a context may have side effects, so leave them be
+    let f = match &proc.func {
+        Func::Pure(f) => f.as_ref(),
+        _ => return None,
+    };
+    let (min, max) = proc.arity_bounds();
+    let dummies = dummies();
+
+    // it must be able to handle as many arguments as it declares
+    let most = max.unwrap_or(min);
+    if dummies.iter().any(|d| probe(f, most, d, None).is_none()) {
+        return Some(format!(
+            "{name}: panicked when called with {most} argument(s)"
+        ));
+    }
+
+    // and it should notice when the last required argument is missing
+    if min > 0
+        && dummies
+            .iter()
+            .zip(dummies.iter().cycle().skip(1))
+            .all(|(d, last)| {
+                let fewer = probe(f, min - 1, d, None);
+                fewer.is_some() && fewer == probe(f, min - 1, d, Some(last))
+            })
+    {
+        return Some(format!(
+            "{name}: declared minimum of {min} argument(s), but never uses argument {min}"
+        ));
+    }
+
+    None
+}
+
+impl Context {
+    /// Verify the declared arity of every native, context-free procedure in
+    /// the language namespace.
+    ///
+    /// # Panics
+    /// If any procedure's implementation disagrees with its declared arity.
+    pub(super) fn assert_arities(&self) {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| ()));
+
+        let mut problems = self
+            .lang
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Atom(Procedure(p)) => check(name, p),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        panic::set_hook(hook);
+
+        problems.sort();
+        assert!(
+            problems.is_empty(),
+            "Native procedures with mismatched arity:\n{}",
+            problems.join("\n")
+        );
+    }
+}
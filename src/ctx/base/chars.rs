@@ -0,0 +1,134 @@
+use std::convert::TryFrom;
+
+use super::super::super::proc::utils::{make_chained_comparison, make_unary_expr};
+use super::super::super::Primitive::{Character, Number};
+use super::super::super::SExp::{self, Atom};
+use super::super::super::{Error, Num};
+use super::super::Context;
+
+macro_rules! define_with {
+    ( $ctx:ident, $name:expr, $proc:expr, $tform:expr ) => {
+        $ctx.lang
+            .insert($name.to_string(), $tform($proc, Some($name)))
+    };
+}
+
+fn char_arg(e: SExp) -> Result<char, Error> {
+    match e {
+        Atom(Character(c)) => Ok(c),
+        other => Err(Error::Type {
+            expected: "char",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// A procedure which tests a character.
+fn char_where(f: fn(char) -> bool) -> impl Fn(SExp) -> Result<SExp, Error> {
+    move |e| Ok(f(char_arg(e)?).into())
+}
+
+/// Change the case of a character, unless the result would be more than one
+/// character (as `ß` is `SS` in upper case).
+fn change_case<I: Iterator<Item = char>>(c: char, f: fn(char) -> I) -> char {
+    let mut changed = f(c);
+    match (changed.next(), changed.next()) {
+        (Some(one), None) => one,
+        _ => c,
+    }
+}
+
+fn integer_to_char(e: SExp) -> Result<SExp, Error> {
+    match e {
+        Atom(Number(Num::Int(i))) => u32::try_from(i)
+            .ok()
+            .and_then(char::from_u32)
+            .map(SExp::from)
+            .ok_or_else(|| Error::Type {
+                expected: "Unicode scalar value",
+                given: i.to_string(),
+            }),
+        other => Err(Error::Type {
+            expected: "exact integer",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+impl Context {
+    pub(super) fn chars(&mut self) {
+        define_with!(
+            self,
+            "char?",
+            |e| Ok(matches!(e, Atom(Character(_))).into()),
+            make_unary_expr
+        );
+
+        // classification
+        define_with!(
+            self,
+            "char-alphabetic?",
+            char_where(char::is_alphabetic),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "char-numeric?",
+            char_where(char::is_numeric),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "char-whitespace?",
+            char_where(char::is_whitespace),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "char-upper-case?",
+            char_where(char::is_uppercase),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "char-lower-case?",
+            char_where(char::is_lowercase),
+            make_unary_expr
+        );
+
+        // conversion
+        define_with!(
+            self,
+            "char-upcase",
+            |e| Ok(change_case(char_arg(e)?, char::to_uppercase).into()),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "char-downcase",
+            |e| Ok(change_case(char_arg(e)?, char::to_lowercase).into()),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "char->integer",
+            |e| Ok((u32::from(char_arg(e)?) as usize).into()),
+            make_unary_expr
+        );
+        define_with!(self, "integer->char", integer_to_char, make_unary_expr);
+
+        // comparison
+        for (name, f) in [
+            ("char=?", PartialEq::eq as fn(&char, &char) -> bool),
+            ("char<?", PartialOrd::lt),
+            ("char>?", PartialOrd::gt),
+            ("char<=?", PartialOrd::le),
+            ("char>=?", PartialOrd::ge),
+        ] {
+            self.lang.insert(
+                name.to_string(),
+                make_chained_comparison(char_arg, f, Some(name)),
+            );
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use super::super::super::primitives::{Deque, Heap, Set};
+use super::super::super::Error;
+use super::super::super::Primitive::{
+    Deque as DequePrim, Heap as HeapPrim, Number, Set as SetPrim, Void,
+};
+use super::super::super::SExp::{self, Atom};
+use super::super::{quoted, Context};
+
+macro_rules! define {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Pure(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+macro_rules! define_ctx {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Ctx(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+type CollectionResult = Result<SExp, Error>;
+
+fn heap_arg(e: SExp) -> Result<Heap, Error> {
+    match e {
+        Atom(HeapPrim(h)) => Ok(h),
+        other => Err(Error::Type {
+            expected: "heap",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+fn deque_arg(e: SExp) -> Result<Deque, Error> {
+    match e {
+        Atom(DequePrim(d)) => Ok(d),
+        other => Err(Error::Type {
+            expected: "deque",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+fn set_arg(e: SExp) -> Result<Set, Error> {
+    match e {
+        Atom(SetPrim(s)) => Ok(s),
+        other => Err(Error::Type {
+            expected: "set",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// Combine one or more sets into a new one, pairwise from the left.
+fn combine_sets(e: SExp, op: fn(&Set, &Set) -> Set) -> CollectionResult {
+    let (first, rest) = e.split_car()?;
+    // copy the first set so a single argument still gives a new set
+    let mut result = set_arg(first)?.to_vec().into_iter().collect::<Set>();
+    for other in rest {
+        result = op(&result, &set_arg(other)?);
+    }
+    Ok(result.into())
+}
+
+/// The value taken from a queue, or an error if it was empty.
+fn non_empty(value: Option<SExp>, kind: &str) -> CollectionResult {
+    value.ok_or_else(|| Error::Type {
+        expected: "non-empty queue",
+        given: format!("empty {kind}"),
+    })
+}
+
+/// `(heap-insert! heap priority [value])`: add a value, which is the priority
+/// itself if it is not given.
+fn heap_insert(e: SExp) -> CollectionResult {
+    let (heap, rest) = e.split_car()?;
+    let (priority, rest) = rest.split_car()?;
+
+    let priority = match priority {
+        Atom(Number(n)) => n,
+        other => {
+            return Err(Error::Type {
+                expected: "number",
+                given: other.type_of().to_string(),
+            })
+        }
+    };
+    let value = rest.car().unwrap_or_else(|_| priority.clone().into());
+
+    heap_arg(heap)?.insert(priority, value);
+    Ok(Atom(Void))
+}
+
+impl Context {
+    /// Call a procedure with each of the values a collection held when
+    /// iteration started. The procedure may not change the collection, since
+    /// the rest of the values would no longer be the ones it holds: an
+    /// `Error::Modified` is returned after the call which changes it.
+    fn for_each_member(
+        &mut self,
+        kind: &'static str,
+        proc: &SExp,
+        values: Vec<SExp>,
+        changes: impl Fn() -> u64,
+    ) -> CollectionResult {
+        let before = changes();
+        for value in values {
+            self.eval(sexp![proc.clone(), quoted(value)])?;
+            if changes() != before {
+                return Err(Error::Modified { kind });
+            }
+        }
+        Ok(Atom(Void))
+    }
+
+    /// `(deque-for-each proc deque)`: call `proc` with each value, from front
+    /// to back.
+    fn deque_for_each(&mut self, expr: SExp) -> CollectionResult {
+        let (proc, rest) = self.eval_args(expr)?.split_car()?;
+        let deque = deque_arg(rest.car()?)?;
+        self.for_each_member("deque", &proc, deque.to_vec(), || deque.changes())
+    }
+
+    /// `(set-for-each proc set)`: call `proc` with each member, in the order
+    /// they were added.
+    fn set_for_each(&mut self, expr: SExp) -> CollectionResult {
+        let (proc, rest) = self.eval_args(expr)?.split_car()?;
+        let set = set_arg(rest.car()?)?;
+        self.for_each_member("set", &proc, set.to_vec(), || set.changes())
+    }
+
+    pub(super) fn heap(&mut self) {
+        define!(self, "make-heap", |_| Ok(Heap::default().into()), 0);
+        define!(
+            self,
+            "heap?",
+            |e| Ok(matches!(e.car()?, Atom(HeapPrim(_))).into()),
+            1
+        );
+        define!(self, "heap-insert!", heap_insert, (2, 3));
+        define!(
+            self,
+            "heap-pop!",
+            |e| non_empty(heap_arg(e.car()?)?.pop(), "heap"),
+            1
+        );
+        define!(
+            self,
+            "heap-peek",
+            |e| non_empty(heap_arg(e.car()?)?.peek(), "heap"),
+            1
+        );
+        define!(
+            self,
+            "heap-size",
+            |e| Ok(heap_arg(e.car()?)?.len().into()),
+            1
+        );
+        define!(
+            self,
+            "heap-empty?",
+            |e| Ok(heap_arg(e.car()?)?.is_empty().into()),
+            1
+        );
+        define!(
+            self,
+            "heap->list",
+            |e| Ok(heap_arg(e.car()?)?.to_vec().into_iter().collect()),
+            1
+        );
+    }
+
+    pub(super) fn deque(&mut self) {
+        define!(
+            self,
+            "make-deque",
+            |e| Ok(e
+                .car()
+                .map_or_else(|_| Deque::default(), |list| list.into_iter().collect())
+                .into()),
+            (0, 1)
+        );
+        define!(
+            self,
+            "deque?",
+            |e| Ok(matches!(e.car()?, Atom(DequePrim(_))).into()),
+            1
+        );
+        define!(
+            self,
+            "deque-push-front!",
+            |e| {
+                let (deque, rest) = e.split_car()?;
+                let value = rest.car()?;
+                deque_arg(deque)?.push_front(value);
+                Ok(Atom(Void))
+            },
+            2
+        );
+        define!(
+            self,
+            "deque-push-back!",
+            |e| {
+                let (deque, rest) = e.split_car()?;
+                let value = rest.car()?;
+                deque_arg(deque)?.push_back(value);
+                Ok(Atom(Void))
+            },
+            2
+        );
+        define!(
+            self,
+            "deque-pop-front!",
+            |e| non_empty(deque_arg(e.car()?)?.pop_front(), "deque"),
+            1
+        );
+        define!(
+            self,
+            "deque-pop-back!",
+            |e| non_empty(deque_arg(e.car()?)?.pop_back(), "deque"),
+            1
+        );
+        define!(
+            self,
+            "deque-front",
+            |e| non_empty(deque_arg(e.car()?)?.front(), "deque"),
+            1
+        );
+        define!(
+            self,
+            "deque-back",
+            |e| non_empty(deque_arg(e.car()?)?.back(), "deque"),
+            1
+        );
+        define!(
+            self,
+            "deque-length",
+            |e| Ok(deque_arg(e.car()?)?.len().into()),
+            1
+        );
+        define!(
+            self,
+            "deque-empty?",
+            |e| Ok(deque_arg(e.car()?)?.is_empty().into()),
+            1
+        );
+        define!(
+            self,
+            "deque->list",
+            |e| Ok(deque_arg(e.car()?)?.to_vec().into_iter().collect()),
+            1
+        );
+        define_ctx!(self, "deque-for-each", Self::deque_for_each, 2);
+    }
+
+    pub(super) fn sets(&mut self) {
+        define!(
+            self,
+            "set",
+            |e| Ok(e.into_iter().collect::<Set>().into()),
+            (0,)
+        );
+        define!(
+            self,
+            "list->set",
+            |e| Ok(e.car()?.into_iter().collect::<Set>().into()),
+            1
+        );
+        define!(
+            self,
+            "set->list",
+            |e| Ok(set_arg(e.car()?)?.to_vec().into_iter().collect()),
+            1
+        );
+        define_ctx!(self, "set-for-each", Self::set_for_each, 2);
+        define!(
+            self,
+            "set?",
+            |e| Ok(matches!(e.car()?, Atom(SetPrim(_))).into()),
+            1
+        );
+        define!(
+            self,
+            "set-add!",
+            |e| {
+                let (set, rest) = e.split_car()?;
+                let value = rest.car()?;
+                set_arg(set)?.add(value);
+                Ok(Atom(Void))
+            },
+            2
+        );
+        define!(
+            self,
+            "set-remove!",
+            |e| {
+                let (set, rest) = e.split_car()?;
+                let value = rest.car()?;
+                set_arg(set)?.remove(&value);
+                Ok(Atom(Void))
+            },
+            2
+        );
+        define!(
+            self,
+            "set-member?",
+            |e| {
+                let (set, rest) = e.split_car()?;
+                let value = rest.car()?;
+                Ok(set_arg(set)?.contains(&value).into())
+            },
+            2
+        );
+        define!(self, "set-size", |e| Ok(set_arg(e.car()?)?.len().into()), 1);
+        define!(
+            self,
+            "set-empty?",
+            |e| Ok(set_arg(e.car()?)?.is_empty().into()),
+            1
+        );
+        define!(self, "set-union", |e| combine_sets(e, Set::union), (1,));
+        define!(
+            self,
+            "set-intersection",
+            |e| combine_sets(e, Set::intersection),
+            (1,)
+        );
+        define!(
+            self,
+            "set-difference",
+            |e| combine_sets(e, Set::difference),
+            (1,)
+        );
+        define!(
+            self,
+            "set-subset?",
+            |e| {
+                let (a, rest) = e.split_car()?;
+                let b = rest.car()?;
+                Ok(set_arg(a)?.is_subset(&set_arg(b)?).into())
+            },
+            2
+        );
+        define!(
+            self,
+            "set=?",
+            |e| {
+                let (a, rest) = e.split_car()?;
+                let b = rest.car()?;
+                let (a, b) = (set_arg(a)?, set_arg(b)?);
+                Ok((a.is_subset(&b) && b.is_subset(&a)).into())
+            },
+            2
+        );
+    }
+}
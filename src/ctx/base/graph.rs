@@ -0,0 +1,207 @@
+//! Graph algorithms over adjacency lists, enabled by the `graph` feature.
+//!
+//! A graph is written as a list of entries `(node neighbor...)`, with an edge
+//! from each node to each of its neighbors. Nodes may be any values, and are
+//! told apart by their written form; a node which only appears as a neighbor
+//! is still part of the graph.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::super::super::Error;
+use super::super::super::SExp;
+use super::super::Context;
+
+macro_rules! define {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Pure(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+/// A graph read from an adjacency list, with its nodes numbered in the order
+/// they first appear.
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<SExp>,
+    index: HashMap<String, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn from_alist(alist: SExp) -> Result<Self, Error> {
+        let mut graph = Self::default();
+        for entry in alist {
+            let (node, neighbors) = entry.split_car()?;
+            let from = graph.node(node);
+            for neighbor in neighbors {
+                let to = graph.node(neighbor);
+                graph.edges[from].push(to);
+            }
+        }
+        Ok(graph)
+    }
+
+    /// The number of a node, adding it if it is new.
+    fn node(&mut self, node: SExp) -> usize {
+        let key = format!("{node:?}");
+        if let Some(&i) = self.index.get(&key) {
+            return i;
+        }
+        self.index.insert(key, self.nodes.len());
+        self.nodes.push(node);
+        self.edges.push(Vec::new());
+        self.nodes.len() - 1
+    }
+
+    fn lookup(&self, node: &SExp) -> Option<usize> {
+        self.index.get(&format!("{node:?}")).copied()
+    }
+
+    fn list(&self, nodes: impl IntoIterator<Item = usize>) -> SExp {
+        nodes.into_iter().map(|i| self.nodes[i].clone()).collect()
+    }
+
+    /// Kahn's algorithm, taking ready nodes in the order they first appear.
+    /// `None` if the graph has a cycle.
+    fn topological_sort(&self) -> Option<Vec<usize>> {
+        let mut in_degree = vec![0; self.nodes.len()];
+        for &to in self.edges.iter().flatten() {
+            in_degree[to] += 1;
+        }
+
+        let mut ready = (0..self.nodes.len())
+            .filter(|&i| in_degree[i] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(from) = ready.pop_front() {
+            order.push(from);
+            for &to in &self.edges[from] {
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    ready.push_back(to);
+                }
+            }
+        }
+
+        if order.len() == self.nodes.len() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+
+    /// A path with the fewest edges, found breadth-first.
+    fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut previous = vec![None; self.nodes.len()];
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::from(vec![from]);
+        seen[from] = true;
+
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(p) = previous[current] {
+                    path.push(p);
+                    current = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &next in &self.edges[node] {
+                if !seen[next] {
+                    seen[next] = true;
+                    previous[next] = Some(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The components of the graph with the direction of its edges ignored.
+    fn connected_components(&self) -> Vec<Vec<usize>> {
+        let mut undirected = vec![Vec::new(); self.nodes.len()];
+        for (from, neighbors) in self.edges.iter().enumerate() {
+            for &to in neighbors {
+                undirected[from].push(to);
+                undirected[to].push(from);
+            }
+        }
+
+        let mut component = vec![None; self.nodes.len()];
+        let mut components = Vec::new();
+        for start in 0..self.nodes.len() {
+            if component[start].is_some() {
+                continue;
+            }
+            let mut members = Vec::new();
+            let mut stack = vec![start];
+            component[start] = Some(components.len());
+            while let Some(node) = stack.pop() {
+                members.push(node);
+                for &next in &undirected[node] {
+                    if component[next].is_none() {
+                        component[next] = Some(components.len());
+                        stack.push(next);
+                    }
+                }
+            }
+            members.sort_unstable();
+            components.push(members);
+        }
+        components
+    }
+}
+
+impl Context {
+    pub(super) fn graph(&mut self) {
+        define!(
+            self,
+            "topological-sort",
+            |e| {
+                let graph = Graph::from_alist(e.car()?)?;
+                Ok(graph
+                    .topological_sort()
+                    .map_or_else(|| false.into(), |order| graph.list(order)))
+            },
+            1
+        );
+        define!(
+            self,
+            "shortest-path",
+            |e| {
+                let (alist, rest) = e.split_car()?;
+                let (from, rest) = rest.split_car()?;
+                let to = rest.car()?;
+                let graph = Graph::from_alist(alist)?;
+                let path = match (graph.lookup(&from), graph.lookup(&to)) {
+                    (Some(from), Some(to)) => graph.shortest_path(from, to),
+                    _ => None,
+                };
+                Ok(path.map_or_else(|| false.into(), |path| graph.list(path)))
+            },
+            3
+        );
+        define!(
+            self,
+            "connected-components",
+            |e| {
+                let graph = Graph::from_alist(e.car()?)?;
+                Ok(graph
+                    .connected_components()
+                    .into_iter()
+                    .map(|members| graph.list(members))
+                    .collect())
+            },
+            1
+        );
+    }
+}
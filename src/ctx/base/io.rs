@@ -0,0 +1,146 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+
+use super::super::super::primitives::Port;
+use super::super::super::proc::utils::make_unary_expr;
+use super::super::super::Primitive::{Port as PortPrim, String as LispString, Undefined};
+use super::super::super::SExp::{self, Atom};
+use super::super::super::{effect, Error, Result};
+use super::super::Context;
+
+macro_rules! define_with {
+    ( $ctx:ident, $name:expr, $proc:expr, $tform:expr ) => {
+        $ctx.lang
+            .insert($name.to_string(), $tform($proc, Some($name)))
+    };
+}
+
+macro_rules! define_ctx {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Ctx(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+macro_rules! define {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Pure(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("\\n", "\n")
+        .replace("\\t", "\t")
+        .replace("\\\\", "\\")
+        .replace("\\r", "\r")
+        .replace("\\0", "\0")
+        .replace("\\\"", "\"")
+}
+
+impl Context {
+    pub(super) fn io(&mut self) {
+        define_ctx!(
+            self,
+            "display",
+            |e, c| Self::do_print(e, c, false, false),
+            (1, 2)
+        );
+        define_ctx!(
+            self,
+            "displayln",
+            |e, c| Self::do_print(e, c, true, false),
+            (1, 2)
+        );
+        define_ctx!(
+            self,
+            "write",
+            |e, c| Self::do_print(e, c, false, true),
+            (1, 2)
+        );
+        define_ctx!(
+            self,
+            "writeln",
+            |e, c| Self::do_print(e, c, true, true),
+            (1, 2)
+        );
+        define!(self, "current-output-port", |_| Ok(Port::Console.into()), 0);
+        define!(self, "open-output-string", |_| Ok(Port::string().into()), 0);
+        define_with!(
+            self,
+            "get-output-string",
+            |e| match e {
+                Atom(PortPrim(p @ Port::String(_))) => Ok(p.contents().unwrap_or_default().into()),
+                other => Err(Error::Type {
+                    expected: "string port",
+                    given: other.type_of().to_string(),
+                }),
+            },
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "port?",
+            |e| Ok(matches!(e, Atom(PortPrim(_))).into()),
+            make_unary_expr
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        define_ctx!(self, "include", Self::eval_include, (1,));
+        #[cfg(not(target_arch = "wasm32"))]
+        define_ctx!(
+            self,
+            "require",
+            |c, e| match c.eval(e.car()?)? {
+                Atom(LispString(f_name)) => c.run(&fs::read_to_string(c.resolve_path(&f_name)?)?),
+                other => Err(Error::Type {
+                    expected: "string",
+                    given: other.type_of().to_string(),
+                }),
+            },
+            1
+        );
+    }
+
+    /// Print a value to the port given as the second argument, or to the
+    /// output of the context if there is none.
+    fn do_print(&mut self, expr: SExp, newline: bool, debug: bool) -> Result {
+        let ending = if newline { "\n" } else { "" };
+        let (hevl, rest) = self.eval_args(expr)?.split_car()?;
+        // `write` leaves escapes in strings as they are, and names characters
+        // like `#\newline`, so that what it prints can be read back
+        let unescaped = if debug {
+            format!("{hevl:?}{ending}")
+        } else {
+            unescape(&format!("{hevl}{ending}"))
+        };
+
+        match rest.car() {
+            Err(_) | Ok(Atom(PortPrim(Port::Console))) => self.write_output(&unescaped)?,
+            Ok(Atom(PortPrim(Port::String(s)))) => {
+                effect::record();
+                s.borrow_mut().push_str(&unescaped);
+            }
+            Ok(other) => {
+                return Err(Error::Type {
+                    expected: "port",
+                    given: other.type_of().to_string(),
+                })
+            }
+        }
+
+        Ok(Atom(Undefined))
+    }
+}
@@ -1,20 +1,37 @@
-use std::fmt::Write;
-#[cfg(not(target_arch = "wasm32"))]
-use std::fs;
+use std::convert::TryFrom;
+use std::rc::Rc;
 
+use super::super::primitives::WeakRef;
+use super::super::proc::Builtin;
 use super::super::Primitive::{
-    Boolean, Character, Env, Number, Procedure, String as LispString, Symbol, Undefined, Void,
+    self, Boolean, Env, Number, Procedure, Promise as PromisePrim, String as LispString, Symbol,
+    Void,
 };
-use super::super::SExp::{self, Atom, Null, Pair};
-use super::super::{Error, Num, Result};
+use super::super::SExp::{self, Atom, Null};
+use super::super::{Error, Func, Ns, Num, Proc, Promise, Result};
 
 use super::super::proc::utils::{
-    make_binary_expr, make_binary_numeric, make_fold_from0_numeric, make_fold_numeric,
-    make_unary_expr, make_unary_numeric,
+    make_binary_expr, make_binary_numeric, make_chained_comparison, make_checked_binary_numeric,
+    make_checked_fold_numeric, make_checked_unary_numeric, make_fold_from0_numeric,
+    make_fold_numeric, make_unary_expr, make_unary_numeric,
 };
+use super::core::{equal, eqv};
 use super::Context;
 
+#[cfg(feature = "assert-arity")]
+mod arity;
+mod chars;
+#[cfg(feature = "collections")]
+mod collections;
+#[cfg(feature = "graph")]
+mod graph;
+#[cfg(feature = "io")]
+mod io;
+mod stream;
+#[cfg(feature = "strings")]
+mod string;
 mod tests;
+#[cfg(feature = "vectors")]
 mod vec;
 
 macro_rules! define_with {
@@ -22,6 +39,17 @@ macro_rules! define_with {
         $ctx.lang
             .insert($name.to_string(), $tform($proc, Some($name)))
     };
+    ( $ctx:ident, $name:expr, $proc:expr, $tform:expr, $doc:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            match $tform($proc, Some($name)) {
+                $crate::SExp::Atom($crate::Primitive::Procedure(p)) => {
+                    $crate::SExp::from(p.with_doc($doc))
+                }
+                other => other,
+            },
+        )
+    };
 }
 
 macro_rules! define_ctx {
@@ -35,6 +63,19 @@ macro_rules! define_ctx {
             )),
         )
     };
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr, $doc:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from(
+                $crate::Proc::new(
+                    $crate::Func::Ctx(::std::rc::Rc::new($proc)),
+                    $arity,
+                    ::std::option::Option::Some($name),
+                )
+                .with_doc($doc),
+            ),
+        )
+    };
 }
 
 macro_rules! define {
@@ -48,15 +89,226 @@ macro_rules! define {
             )),
         )
     };
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr, $doc:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from(
+                $crate::Proc::new(
+                    $crate::Func::Pure(::std::rc::Rc::new($proc)),
+                    $arity,
+                    Some($name),
+                )
+                .with_doc($doc),
+            ),
+        )
+    };
+}
+
+/// An operation on integers for `gcd` and `lcm`, failing for any other
+/// numbers.
+fn integer_op(
+    f: fn(Num, Num) -> Option<Num>,
+) -> impl Fn(Num, Num) -> std::result::Result<Num, Error> {
+    move |l, r| {
+        let given = if l.is_integer() { &r } else { &l }.to_string();
+        f(l, r).ok_or(Error::Type {
+            expected: "integer",
+            given,
+        })
+    }
+}
+
+/// The root and remainder of `exact-integer-sqrt`, as two values.
+fn exact_integer_sqrt(n: Num) -> Result {
+    let given = n.to_string();
+    n.exact_integer_sqrt()
+        .map(|(root, rest)| SExp::values(vec![root.into(), rest.into()]))
+        .ok_or(Error::Type {
+            expected: "exact non-negative integer",
+            given,
+        })
+}
+
+fn number_arg(e: SExp) -> std::result::Result<Num, Error> {
+    match e {
+        Atom(Number(n)) => Ok(n),
+        other => Err(Error::Type {
+            expected: "number",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// A numeric procedure which does `one` with a single argument, or `two`
+/// with a pair of them, like `atan` and `log`.
+fn one_or_two(one: fn(Num) -> Num, two: fn(Num, Num) -> Num) -> impl Fn(SExp) -> Result {
+    move |e| {
+        let mut nums = e.into_iter().map(number_arg);
+
+        let first = nums.next().unwrap_or(Ok(Num::Int(0)))?;
+        Ok(match nums.next().transpose()? {
+            Some(second) => two(first, second),
+            None => one(first),
+        }
+        .into())
+    }
+}
+
+/// A numeric procedure which folds `f` over its arguments from the first,
+/// or applies it to `unit` and a single argument, like `-` and `/`.
+fn fold_or_invert(
+    unit: Num,
+    f: fn(Num, Num) -> std::result::Result<Num, Error>,
+) -> impl Fn(SExp) -> Result {
+    move |e| {
+        let mut nums = e.into_iter().map(number_arg).peekable();
+
+        let first = nums.next().unwrap_or(Ok(Num::Int(0)))?;
+        if nums.peek().is_none() {
+            return f(unit.clone(), first).map(SExp::from);
+        }
+        nums.try_fold(first, |acc, n| f(acc, n?)).map(SExp::from)
+    }
+}
+
+/// Divide two numbers, or fail on division by zero.
+fn divide(l: Num, r: Num) -> std::result::Result<Num, Error> {
+    l.checked_div(r).ok_or(Error::DivisionByZero)
+}
+
+/// A type predicate which holds for numbers satisfying `f`, and for nothing
+/// else.
+fn number_where(f: fn(&Num) -> bool) -> impl Fn(SExp) -> Result {
+    move |e| Ok(matches!(e, Atom(Number(n)) if f(&n)).into())
+}
+
+/// Whether an integer is even, failing for any other number.
+fn is_even(n: Num) -> std::result::Result<bool, Error> {
+    if !n.is_integer() {
+        return Err(Error::Type {
+            expected: "integer",
+            given: n.to_string(),
+        });
+    }
+    Ok(n.checked_rem(2) == Some(Num::Int(0)))
+}
+
+/// The number of elements to make, for `make-list` and `list-tabulate`, or the
+/// index of one to replace, for `list-set!`.
+fn count_arg(e: SExp) -> std::result::Result<usize, Error> {
+    match e {
+        Atom(Number(Num::Int(n))) => usize::try_from(n).map_err(|_| Error::Type {
+            expected: "non-negative exact integer",
+            given: n.to_string(),
+        }),
+        Atom(Number(n)) => Err(Error::Type {
+            expected: "non-negative exact integer",
+            given: n.to_string(),
+        }),
+        other => Err(Error::Type {
+            expected: "non-negative exact integer",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// The first pair in an association list whose car is the same as the key by
+/// `same`, or `#f` if there is none.
+fn assoc_by(same: fn(&SExp, &SExp) -> bool) -> impl Fn(SExp, SExp) -> Result {
+    move |key, alist| {
+        for entry in alist {
+            if same(&key, &entry.clone().car()?) {
+                return Ok(entry);
+            }
+        }
+        Ok(false.into())
+    }
 }
 
-fn unescape(s: &str) -> String {
-    s.replace("\\n", "\n")
-        .replace("\\t", "\t")
-        .replace("\\\\", "\\")
-        .replace("\\r", "\r")
-        .replace("\\0", "\0")
-        .replace("\\\"", "\"")
+/// The base procedures which are plain functions of the context, registered
+/// from this table rather than one by one.
+static CONTEXT_PROCS: &[Builtin] = &[
+    // environments
+    Builtin::new("environment-bound?", Context::environment_bound, 2, Some(2)),
+    Builtin::new(
+        "interaction-environment",
+        Context::interaction_environment,
+        0,
+        Some(0),
+    ),
+    Builtin::new("make-environment", Context::make_environment, 0, Some(1)),
+    Builtin::new("environment-tree", Context::environment_tree, 0, Some(1)),
+    Builtin::new(
+        "procedure-environment",
+        Context::procedure_environment,
+        1,
+        Some(1),
+    ),
+    // libraries
+    Builtin::new("import", Context::import, 1, None),
+    // classes
+    #[cfg(feature = "clos")]
+    Builtin::new("define-class", Context::eval_define_class, 3, Some(3)),
+    #[cfg(feature = "clos")]
+    Builtin::new("make", Context::make_instance, 1, None),
+    #[cfg(feature = "clos")]
+    Builtin::new("is-a?", Context::is_a, 2, Some(2)),
+    // symbols and lists
+    Builtin::new("gensym", Context::gensym, 0, Some(1)),
+    Builtin::new("generate-uninterned-symbol", Context::gensym, 0, Some(1)),
+    Builtin::new("list-tabulate", Context::list_tabulate, 2, Some(2)),
+    Builtin::new("build-list", Context::list_tabulate, 2, Some(2)),
+    // continuations and control
+    Builtin::new(
+        "call-with-current-continuation",
+        Context::call_cc,
+        1,
+        Some(1),
+    ),
+    Builtin::new("call/cc", Context::call_cc, 1, Some(1)),
+    Builtin::new(
+        "call-with-escape-continuation",
+        Context::call_cc,
+        1,
+        Some(1),
+    ),
+    Builtin::new("call/ec", Context::call_cc, 1, Some(1)),
+    Builtin::new(
+        "with-exception-handler",
+        Context::with_exception_handler,
+        2,
+        Some(2),
+    ),
+    Builtin::new("await", Context::await_request, 1, Some(1)),
+    Builtin::new("sleep", Context::sleep, 1, Some(1)),
+    Builtin::new("after", Context::after, 2, Some(2)),
+    Builtin::new("dynamic-wind", Context::dynamic_wind, 3, Some(3)),
+    Builtin::new("exit", Context::exit, 0, Some(1)),
+    Builtin::new("restart", Context::restart, 0, Some(0)),
+    Builtin::new("call-with-values", Context::call_with_values, 2, Some(2)),
+    Builtin::new("make-parameter", Context::make_parameter, 1, Some(2)),
+    // functional goodness
+    Builtin::new("map", Context::eval_map, 2, Some(2)),
+    Builtin::new("foldl", Context::eval_fold, 3, Some(3)),
+    Builtin::new("fold-left", Context::eval_fold, 3, Some(3)),
+    Builtin::new("filter", Context::eval_filter, 2, Some(2)),
+    // macros
+    Builtin::new("macroexpand", Context::eval_macroexpand, 1, Some(1)),
+    Builtin::new("macroexpand-1", Context::eval_macroexpand_1, 1, Some(1)),
+    // numbers
+    Builtin::new("number->string", Context::number_to_string, 1, Some(4)),
+];
+
+thread_local! {
+    /// The language-level definitions of the base context, registered the
+    /// first time one is made on each thread. Every base context starts with
+    /// a copy of them, so it need not register them again.
+    ///
+    /// The procedures which are plain functions of the context are listed in
+    /// the static `CONTEXT_PROCS`, as the special forms are in `CORE_FORMS`.
+    /// The rest are built from closures, which can't be kept in a static, so
+    /// the first base context on each thread still registers those.
+    static BASE_LANG: Ns = Context::build_base().lang;
 }
 
 impl Context {
@@ -80,10 +332,36 @@ impl Context {
     /// ```
     #[must_use]
     pub fn base() -> Self {
+        Self {
+            lang: BASE_LANG.with(Ns::clone),
+            ..Self::default()
+        }
+    }
+
+    /// Register every language-level definition of the base context.
+    fn build_base() -> Self {
         let mut ret = Self::default();
+        ret.lang.extend(CONTEXT_PROCS.iter().map(Builtin::entry));
         ret.std();
         ret.num_base();
+        ret.num_predicates();
+        ret.num_transcendental();
+        ret.chars();
+        #[cfg(feature = "vectors")]
         ret.vector();
+        #[cfg(feature = "strings")]
+        ret.string();
+        #[cfg(feature = "io")]
+        ret.io();
+        ret.stream();
+        #[cfg(feature = "collections")]
+        {
+            ret.heap();
+            ret.deque();
+            ret.sets();
+        }
+        #[cfg(feature = "graph")]
+        ret.graph();
 
         // Procedures
         define_with!(
@@ -107,50 +385,44 @@ impl Context {
             make_unary_expr
         );
 
-        // Strings
+        // Libraries
         define!(
             ret,
-            "string->list",
-            |e| match &e[0] {
-                Atom(LispString(s)) => Ok(s.chars().map(SExp::from).collect()),
-                exp => Err(Error::Type {
-                    expected: "string",
-                    given: exp.type_of().to_string()
-                }),
-            },
-            3
+            "available-libraries",
+            |_| Ok(Self::available_libraries()),
+            0
         );
-        define!(
+
+        // Weak references and resources
+        define_with!(
             ret,
-            "list->string",
-            |e| match e {
-                Pair { .. } => {
-                    match e.into_iter().fold(Ok(String::new()), |s, e| match e {
-                        Atom(Character(ref c)) => {
-                            if let Ok(st) = s {
-                                let mut stri = st;
-                                stri.push(*c);
-                                Ok(stri)
-                            } else {
-                                s
-                            }
-                        }
-                        _ => Err(Error::Type {
-                            expected: "char",
-                            given: e.type_of().to_string(),
-                        }),
-                    }) {
-                        Ok(s) => Ok(Atom(LispString(s))),
-                        Err(err) => Err(err),
-                    }
-                }
-                _ => Err(Error::Type {
-                    expected: "list",
-                    given: e.type_of().to_string()
-                }),
-            },
-            1
+            "make-weak-ref",
+            |e| Ok(Atom(Primitive::WeakRef(WeakRef::new(e)))),
+            make_unary_expr
+        );
+        define_with!(ret, "weak-ref-deref", Self::weak_ref_deref, make_unary_expr);
+        define_with!(
+            ret,
+            "weak-ref?",
+            |e| Ok(matches!(e, Atom(Primitive::WeakRef(_))).into()),
+            make_unary_expr
+        );
+        define_with!(
+            ret,
+            "resource?",
+            |e| Ok(matches!(e, Atom(Primitive::Resource(_))).into()),
+            make_unary_expr
         );
+        define_with!(ret, "close-resource", Self::close_resource, make_unary_expr);
+        define_with!(
+            ret,
+            "resource-closed?",
+            Self::resource_closed,
+            make_unary_expr
+        );
+
+        #[cfg(feature = "assert-arity")]
+        ret.assert_arities();
 
         ret
     }
@@ -158,29 +430,71 @@ impl Context {
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::similar_names)]
     fn std(&mut self) {
-        define!(self, "eq?", |e| Ok((e[0] == e[1]).into()), 2);
+        define_with!(
+            self,
+            "eq?",
+            |e0, e1| Ok(equal(&e0, &e1).into()),
+            make_binary_expr
+        );
         define_with!(
             self,
             "eqv?",
-            |e0, e1| Ok(match (e0, e1) {
-                (Null, Null) => true,
-                (Atom(Boolean(b0)), Atom(Boolean(b1))) => b0 == b1,
-                (Atom(Character(c0)), Atom(Character(c1))) => c0 == c1,
-                (Atom(Symbol(s0)), Atom(Symbol(s1))) => s0 == s1,
-                (Atom(Number(n0)), Atom(Number(n1))) => n0 == n1,
-                (Atom(Procedure(p0)), Atom(Procedure(p1))) => p0 == p1,
-                _ => false,
-            }
-            .into()),
+            |e0, e1| Ok(eqv(&e0, &e1).into()),
+            make_binary_expr
+        );
+        define_with!(
+            self,
+            "equal?",
+            |e0, e1| Ok(equal(&e0, &e1).into()),
             make_binary_expr
         );
-        define!(self, "equal?", |e| Ok((e[0] == e[1]).into()), 2);
 
-        define!(self, "null?", |e| Ok((e == ((),).into()).into()), 1);
+        self.lang.insert(
+            "boolean=?".to_string(),
+            make_chained_comparison(
+                |e| match e {
+                    Atom(Boolean(b)) => Ok(b),
+                    other => Err(Error::Type {
+                        expected: "bool",
+                        given: other.type_of().to_string(),
+                    }),
+                },
+                PartialEq::eq,
+                Some("boolean=?"),
+            ),
+        );
+        self.lang.insert(
+            "symbol=?".to_string(),
+            make_chained_comparison(
+                |e| match e {
+                    Atom(Symbol(s)) => Ok(s),
+                    other => Err(Error::Type {
+                        expected: "symbol",
+                        given: other.type_of().to_string(),
+                    }),
+                },
+                PartialEq::eq,
+                Some("symbol=?"),
+            ),
+        );
+
+        define!(self, "null?", |e| Ok((e.car()? == SExp::null()).into()), 1);
         self.lang.insert("null".to_string(), Null);
         define!(self, "void", |_| Ok(Atom(Void)), 0);
-        define!(self, "list", Ok, (0,));
-        define!(self, "not", |e| Ok((e == (false,).into()).into()), 1);
+        define!(
+            self,
+            "default-object?",
+            |e| Ok((e.car()? == Atom(Primitive::Default)).into()),
+            1
+        );
+        define!(self, "list", Ok, (0,), "Make a list of the arguments.");
+        define!(
+            self,
+            "values",
+            |e: SExp| Ok(SExp::values(e.into_iter().collect())),
+            (0,)
+        );
+        define!(self, "not", |e| Ok((e.car()? == false.into()).into()), 1);
 
         define!(
             self,
@@ -193,8 +507,27 @@ impl Context {
             2
         );
 
-        define_with!(self, "car", SExp::car, make_unary_expr);
-        define_with!(self, "cdr", SExp::cdr, make_unary_expr);
+        define_with!(
+            self,
+            "car",
+            SExp::car,
+            make_unary_expr,
+            "The first element of a pair."
+        );
+        define_with!(
+            self,
+            "cdr",
+            SExp::cdr,
+            make_unary_expr,
+            "Everything after the first element of a pair."
+        );
+        define_with!(
+            self,
+            "cadr",
+            |e| e.cdr()?.car(),
+            make_unary_expr,
+            "The second element of a list."
+        );
 
         define_ctx!(
             self,
@@ -209,7 +542,7 @@ impl Context {
                             val.set_car(c.eval(new)?)?;
                             c.set(&key, val)
                         } else {
-                            Err(Error::UndefinedSymbol { sym: key })
+                            Err(c.undefined(&key))
                         }
                     }
                     other => Err(Error::Type {
@@ -234,7 +567,7 @@ impl Context {
                             val.set_cdr(c.eval(new)?)?;
                             c.set(&key, val)
                         } else {
-                            Err(Error::UndefinedSymbol { sym: key })
+                            Err(c.undefined(&key))
                         }
                     }
                     other => Err(Error::Type {
@@ -246,6 +579,33 @@ impl Context {
             2
         );
 
+        define!(
+            self,
+            "make-list",
+            |e| {
+                let (k, rest) = e.split_car()?;
+                let fill = rest.car().unwrap_or(Null);
+
+                Ok((0..count_arg(k)?).map(|_| fill.clone()).collect())
+            },
+            (1, 2)
+        );
+        define_with!(self, "list-copy", Ok, make_unary_expr);
+        define_with!(self, "assq", assoc_by(equal), make_binary_expr);
+        define_with!(self, "assv", assoc_by(eqv), make_binary_expr);
+        define!(
+            self,
+            "list-set!",
+            |e| {
+                let (mut list, rest) = e.split_car()?;
+                let (k, rest) = rest.split_car()?;
+                list.set_nth(count_arg(k)?, rest.car()?)?;
+                Ok(list)
+            },
+            3,
+            "A copy of the list with the element at an index replaced."
+        );
+
         define_with!(
             self,
             "type-of",
@@ -253,40 +613,121 @@ impl Context {
             make_unary_expr
         );
 
-        // i/o
+        // exceptions
         define_ctx!(
             self,
-            "display",
-            |e, c| Self::do_print(e, c, false, false),
+            "raise",
+            |ctx: &mut Self, e: SExp| {
+                let value = ctx.eval(e.car()?)?;
+                ctx.raise(value, false)
+            },
             1
         );
         define_ctx!(
             self,
-            "displayln",
-            |e, c| Self::do_print(e, c, true, false),
+            "raise-continuable",
+            |ctx: &mut Self, e: SExp| {
+                let value = ctx.eval(e.car()?)?;
+                ctx.raise(value, true)
+            },
             1
         );
-        define_ctx!(self, "write", |e, c| Self::do_print(e, c, false, true), 1);
-        define_ctx!(self, "writeln", |e, c| Self::do_print(e, c, true, true), 1);
-
-        #[cfg(not(target_arch = "wasm32"))]
-        define_ctx!(
+        define!(
             self,
-            "require",
-            |c, e| match c.eval(e.car()?)? {
-                Atom(LispString(f_name)) => c.run(&fs::read_to_string(f_name)?),
+            "error",
+            |e: SExp| {
+                let (message, irritants) = e.split_car()?;
+                match message {
+                    Atom(LispString(message)) => Err(Error::User {
+                        message,
+                        irritants: irritants.into_iter().collect(),
+                    }),
+                    other => Err(Error::Type {
+                        expected: "string",
+                        given: other.type_of().to_string(),
+                    }),
+                }
+            },
+            (1,)
+        );
+        define!(
+            self,
+            "error-object?",
+            |e| Ok(matches!(e.car()?, Atom(Primitive::Condition(_))).into()),
+            1
+        );
+        define!(
+            self,
+            "error-object-message",
+            |e| match e.car()? {
+                Atom(Primitive::Condition(c)) => Ok(c.message().into()),
+                other => Err(Error::Type {
+                    expected: "error object",
+                    given: other.type_of().to_string(),
+                }),
+            },
+            1
+        );
+        define!(
+            self,
+            "error-object-irritants",
+            |e| match e.car()? {
+                Atom(Primitive::Condition(c)) => Ok(c.irritants()),
                 other => Err(Error::Type {
-                    expected: "string",
+                    expected: "error object",
                     given: other.type_of().to_string(),
                 }),
             },
             1
         );
 
-        // functional goodness
-        define_ctx!(self, "map", Self::eval_map, 2);
-        define_ctx!(self, "foldl", Self::eval_fold, 3);
-        define_ctx!(self, "filter", Self::eval_filter, 2);
+        define_ctx!(
+            self,
+            "bindings",
+            |c, _| Ok(c.bindings().into_iter().map(SExp::from).collect()),
+            0,
+            "List the name, kind and arity of everything defined."
+        );
+        define_ctx!(
+            self,
+            "help",
+            Self::eval_help,
+            1,
+            "Print the name, arity and documentation of a binding or procedure."
+        );
+        define_ctx!(
+            self,
+            "describe",
+            Self::eval_help,
+            1,
+            "Print the name, arity and documentation of a binding or procedure."
+        );
+
+        // promises
+        define_ctx!(
+            self,
+            "force",
+            |c, e| match c.eval(e.car()?)? {
+                Atom(PromisePrim(p)) => p.force(c),
+                other => Ok(other),
+            },
+            1
+        );
+        define_with!(
+            self,
+            "make-promise",
+            |e| Ok(match e {
+                p @ Atom(PromisePrim(_)) => p,
+                other => Promise::ready(other).into(),
+            }),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "promise?",
+            |e| Ok(matches!(e, Atom(PromisePrim(_))).into()),
+            make_unary_expr
+        );
 
         // procedures
         define_with!(
@@ -301,6 +742,18 @@ impl Context {
             },
             make_unary_expr
         );
+        define_with!(
+            self,
+            "procedure-parameters",
+            |e| match e {
+                Atom(Procedure(p)) => Ok(p.parameters().unwrap_or(Atom(Boolean(false)))),
+                other => Err(Error::Type {
+                    expected: "procedure",
+                    given: other.type_of().to_string(),
+                }),
+            },
+            make_unary_expr
+        );
         let check_proc_arity = |e0, e1| match (e0, e1) {
             (Atom(Procedure(p)), Atom(Number(n))) => Ok(p.check_arity(n.into()).is_ok().into()),
             (Atom(Procedure(_)), other) => Err(Error::Type {
@@ -342,17 +795,19 @@ impl Context {
         );
     }
 
-    fn do_print(&mut self, expr: SExp, newline: bool, debug: bool) -> Result {
-        let ending = if newline { "\n" } else { "" };
-        let hevl = self.eval(expr.car()?)?;
-        let unescaped = unescape(&if debug {
-            format!("{:?}{}", hevl, ending)
-        } else {
-            format!("{}{}", hevl, ending)
-        });
-        write!(self, "{}", unescaped)?;
+    /// Call a procedure with each index from 0 up to (but not including) `n`,
+    /// collecting the results.
+    fn tabulate(&mut self, n: SExp, proc: &SExp) -> std::result::Result<Vec<SExp>, Error> {
+        (0..count_arg(n)?)
+            .map(|i| self.eval(sexp![proc.clone(), i]))
+            .collect()
+    }
 
-        Ok(Atom(Undefined))
+    /// `(list-tabulate n proc)`: a list of the results of calling `proc` with
+    /// each index from 0 to `n - 1`.
+    fn list_tabulate(&mut self, expr: SExp) -> Result {
+        let (n, rest) = self.eval_args(expr)?.split_car()?;
+        Ok(self.tabulate(n, &rest.car()?)?.into_iter().collect())
     }
 
     fn eval_map(&mut self, expr: SExp) -> Result {
@@ -390,6 +845,55 @@ impl Context {
             .collect()
     }
 
+    fn num_predicates(&mut self) {
+        define_with!(self, "number?", number_where(|_| true), make_unary_expr);
+        define_with!(self, "real?", number_where(|_| true), make_unary_expr);
+        define_with!(
+            self,
+            "rational?",
+            number_where(|n| n.clone().is_finite()),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "integer?",
+            number_where(Num::is_integer),
+            make_unary_expr
+        );
+        define_with!(self, "positive?", |n| n > Num::Int(0), make_unary_numeric);
+        define_with!(self, "negative?", |n| n < Num::Int(0), make_unary_numeric);
+        define_with!(self, "even?", is_even, make_checked_unary_numeric);
+        define_with!(
+            self,
+            "odd?",
+            |n| is_even(n).map(|even| !even),
+            make_checked_unary_numeric
+        );
+        define_with!(self, "nan?", Num::is_nan, make_unary_numeric);
+        define_with!(self, "finite?", Num::is_finite, make_unary_numeric);
+        define_with!(self, "infinite?", Num::is_infinite, make_unary_numeric);
+    }
+
+    fn num_transcendental(&mut self) {
+        define_with!(self, "sin", Num::sin, make_unary_numeric);
+        define_with!(self, "cos", Num::cos, make_unary_numeric);
+        define_with!(self, "tan", Num::tan, make_unary_numeric);
+        define_with!(self, "asin", Num::asin, make_unary_numeric);
+        define_with!(self, "acos", Num::acos, make_unary_numeric);
+        define!(self, "atan", one_or_two(Num::atan, Num::atan2), (1, 2));
+
+        define_with!(self, "exp", Num::exp, make_unary_numeric);
+        define!(self, "log", one_or_two(Num::ln, Num::log), (1, 2));
+        define_with!(self, "sqrt", Num::sqrt, make_unary_numeric);
+        define_with!(self, "expt", Num::checked_pow, make_checked_binary_numeric);
+        define_with!(
+            self,
+            "exact-integer-sqrt",
+            exact_integer_sqrt,
+            make_checked_unary_numeric
+        );
+    }
+
     fn num_base(&mut self) {
         define!(
             self,
@@ -405,24 +909,250 @@ impl Context {
         define_with!(self, "<", |l, r| l < r, make_binary_numeric);
         define_with!(self, ">", |l, r| l > r, make_binary_numeric);
         define_with!(self, "abs", Num::abs, make_unary_numeric);
+        define_with!(self, "floor", Num::floor, make_unary_numeric);
+        define_with!(self, "ceiling", Num::ceil, make_unary_numeric);
+        define_with!(self, "round", Num::round, make_unary_numeric);
+        define_with!(self, "truncate", Num::trunc, make_unary_numeric);
+        define_with!(self, "numerator", Num::numerator, make_unary_numeric);
+        define_with!(self, "denominator", Num::denominator, make_unary_numeric);
+
+        define_with!(self, "min", Num::min, make_fold_from0_numeric);
+        define_with!(self, "max", Num::max, make_fold_from0_numeric);
+        self.lang.insert(
+            "gcd".to_string(),
+            make_checked_fold_numeric(Num::Int(0), integer_op(Num::checked_gcd), Some("gcd")),
+        );
+        self.lang.insert(
+            "lcm".to_string(),
+            make_checked_fold_numeric(Num::Int(1), integer_op(Num::checked_lcm), Some("lcm")),
+        );
 
         self.lang.insert(
             "+".to_string(),
             make_fold_numeric(Num::Int(0), std::ops::Add::add, Some("+")),
         );
 
-        define_with!(self, "-", std::ops::Sub::sub, make_fold_from0_numeric);
+        define!(
+            self,
+            "-",
+            fold_or_invert(Num::Int(0), |l, r| Ok(l - r)),
+            (1,)
+        );
 
         self.lang.insert(
             "*".to_string(),
             make_fold_numeric(Num::Int(1), std::ops::Mul::mul, Some("*")),
         );
 
-        define_with!(self, "/", std::ops::Div::div, make_fold_from0_numeric);
-        define_with!(self, "remainder", std::ops::Rem::rem, make_binary_numeric);
-        define_with!(self, "pow", Num::pow, make_binary_numeric);
+        define!(self, "/", fold_or_invert(Num::Int(1), divide), (1,));
+        define_with!(
+            self,
+            "remainder",
+            |l: Num, r| l.checked_rem(r).ok_or(Error::DivisionByZero),
+            make_checked_binary_numeric
+        );
+        define_with!(
+            self,
+            "modulo",
+            |l: Num, r| l.checked_modulo(r).ok_or(Error::DivisionByZero),
+            make_checked_binary_numeric
+        );
+        define_with!(
+            self,
+            "floor/",
+            |l: Num, r| {
+                let m = l
+                    .clone()
+                    .checked_modulo(r.clone())
+                    .ok_or(Error::DivisionByZero)?;
+                Ok(SExp::values(vec![((l - m.clone()) / r).into(), m.into()]))
+            },
+            make_checked_binary_numeric
+        );
+        define_with!(
+            self,
+            "truncate/",
+            |l: Num, r| {
+                let m = l
+                    .clone()
+                    .checked_rem(r.clone())
+                    .ok_or(Error::DivisionByZero)?;
+                Ok(SExp::values(vec![((l - m.clone()) / r).into(), m.into()]))
+            },
+            make_checked_binary_numeric
+        );
+        define_with!(self, "pow", Num::checked_pow, make_checked_binary_numeric);
+
+        define_with!(self, "exact?", |n: Num| n.is_exact(), make_unary_numeric);
+        define_with!(self, "inexact?", |n: Num| !n.is_exact(), make_unary_numeric);
+        define_with!(
+            self,
+            "exact",
+            Num::checked_to_exact,
+            make_checked_unary_numeric
+        );
+        define_with!(
+            self,
+            "inexact->exact",
+            Num::checked_to_exact,
+            make_checked_unary_numeric
+        );
+        define_with!(self, "inexact", Num::to_inexact, make_unary_numeric);
+        define_with!(self, "exact->inexact", Num::to_inexact, make_unary_numeric);
 
         self.lang
             .insert("pi".to_string(), std::f64::consts::PI.into());
     }
+
+    /// Call a procedure with an escape-only continuation: invoking it from
+    /// within the procedure unwinds back to here, with the given value as the
+    /// result. It cannot be re-entered once `call/cc` has returned, so this
+    /// is also `call/ec`.
+    fn call_cc(&mut self, expr: SExp) -> Result {
+        let proc = self.eval(expr.car()?)?;
+
+        self.continuation_count += 1;
+        let id = self.continuation_count;
+        let k = Proc::new(
+            Func::Pure(Rc::new(move |e: SExp| {
+                Err(Error::Continuation {
+                    id,
+                    value: e.car().unwrap_or(Atom(Void)),
+                })
+            })),
+            (0, 1),
+            Some("continuation"),
+        );
+
+        match self.eval(Null.cons(k.into()).cons(proc)) {
+            Err(Error::Continuation { id: i, value }) if i == id => Ok(value),
+            result => result,
+        }
+    }
+
+    /// Call `before`, then `thunk`, then `after`. Since continuations and
+    /// errors both escape by unwinding back up the stack, `after` is called on
+    /// the way out of the extent whichever way it is left.
+    fn dynamic_wind(&mut self, expr: SExp) -> Result {
+        let thunks = self.eval_args(expr)?;
+        let (before, rest) = thunks.split_car()?;
+        let (thunk, rest) = rest.split_car()?;
+        let after = rest.car()?;
+
+        self.eval(Null.cons(before))?;
+        let result = self.eval(Null.cons(thunk));
+        self.eval(Null.cons(after))?;
+        result
+    }
+
+    /// `(gensym [prefix])`: make a symbol which is different from every other
+    /// symbol, including any the reader could produce, from an optional
+    /// symbol or string prefix.
+    fn gensym(&mut self, expr: SExp) -> Result {
+        let prefix = match self.eval_args(expr)?.car() {
+            Ok(Atom(Symbol(s) | LispString(s))) => s,
+            Ok(other) => {
+                return Err(Error::Type {
+                    expected: "symbol or string",
+                    given: other.type_of().to_string(),
+                })
+            }
+            Err(_) => "g".to_string(),
+        };
+
+        Ok(Atom(Symbol(self.fresh_symbol(&prefix))))
+    }
+
+    /// Call `producer` with no arguments, then call `consumer` with the values
+    /// it returned as its arguments.
+    fn call_with_values(&mut self, expr: SExp) -> Result {
+        let procs = self.eval_args(expr)?;
+        let (producer, rest) = procs.split_car()?;
+        let consumer = rest.car()?;
+
+        // the values are already evaluated, so keep them from being evaluated
+        // again as arguments
+        let args = self
+            .eval(Null.cons(producer))?
+            .into_values()
+            .into_iter()
+            .map(|v| Null.cons(v).cons(SExp::sym("quote")))
+            .collect::<SExp>();
+
+        self.eval(args.cons(consumer))
+    }
+
+    /// `(number->string z [radix [notation [precision]]])`: exact integers
+    /// can be written in radix 2, 8, 10 or 16, and inexact numbers in radix
+    /// 10 with the notation and precision given, or the context's defaults.
+    fn number_to_string(&mut self, expr: SExp) -> Result {
+        let mut args = self.eval_args(expr)?.into_iter();
+        let mut format = self.number_format;
+
+        let num = match args.next() {
+            Some(Atom(Number(n))) => n,
+            other => {
+                return Err(Error::Type {
+                    expected: "number",
+                    given: other.unwrap_or(Null).type_of().to_string(),
+                })
+            }
+        };
+
+        let radix = match args.next() {
+            Some(Atom(Number(Num::Int(2)))) => 2,
+            Some(Atom(Number(Num::Int(8)))) => 8,
+            Some(Atom(Number(Num::Int(10)))) | None => 10,
+            Some(Atom(Number(Num::Int(16)))) => 16,
+            Some(other @ Atom(Number(_))) => {
+                return Err(Error::Type {
+                    expected: "radix (2, 8, 10 or 16)",
+                    given: other.to_string(),
+                })
+            }
+            Some(other) => {
+                return Err(Error::Type {
+                    expected: "number",
+                    given: other.type_of().to_string(),
+                })
+            }
+        };
+
+        match args.next() {
+            Some(Atom(Symbol(s))) => {
+                format.notation = s.parse().map_err(|given| Error::Type {
+                    expected: "notation ('auto, 'fixed or 'scientific)",
+                    given,
+                })?;
+            }
+            Some(other) => {
+                return Err(Error::Type {
+                    expected: "symbol",
+                    given: other.type_of().to_string(),
+                })
+            }
+            None => (),
+        }
+
+        match args.next() {
+            Some(Atom(Number(n))) => format.precision = Some(n.into()),
+            Some(other) => {
+                return Err(Error::Type {
+                    expected: "number",
+                    given: other.type_of().to_string(),
+                })
+            }
+            None => (),
+        }
+
+        if radix == 10 {
+            return Ok(Atom(LispString(num.format(format))));
+        }
+        num.to_radix(radix)
+            .map(|s| Atom(LispString(s)))
+            .ok_or_else(|| Error::Type {
+                expected: "exact integer",
+                given: num.to_string(),
+            })
+    }
 }
@@ -0,0 +1,250 @@
+use std::rc::Rc;
+
+use super::super::super::Primitive::{Number, Promise as PromisePrim};
+use super::super::super::SExp::{self, Atom, Null, Pair};
+use super::super::super::{Error, Func, Proc, Promise};
+use super::super::{quoted, Context};
+
+macro_rules! define_ctx {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Ctx(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+type StreamResult = Result<SExp, Error>;
+
+/// A promise which computes its value by calling `f` when it is forced.
+fn lazy(f: impl Fn(&mut Context) -> StreamResult + 'static) -> SExp {
+    let thunk = Proc::new(Func::Ctx(Rc::new(move |ctx, _| f(ctx))), 0, None::<&str>);
+    Promise::delayed(thunk.into(), false).into()
+}
+
+fn is_stream_pair(e: &SExp) -> bool {
+    match e {
+        Pair { head, tail } => {
+            matches!(**head, Atom(PromisePrim(_))) && matches!(**tail, Atom(PromisePrim(_)))
+        }
+        _ => false,
+    }
+}
+
+/// Split a stream into the promises of its first element and of the rest of
+/// it, or `None` if it is empty.
+fn split_stream(e: SExp) -> Result<Option<(Promise, Promise)>, Error> {
+    match e {
+        Null => Ok(None),
+        Pair { head, tail } => match (*head, *tail) {
+            (Atom(PromisePrim(head)), Atom(PromisePrim(tail))) => Ok(Some((head, tail))),
+            (head, tail) => Err(Error::Type {
+                expected: "stream",
+                given: tail.cons(head).type_of().to_string(),
+            }),
+        },
+        other @ Atom(_) => Err(Error::Type {
+            expected: "stream",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+fn count_arg(e: SExp) -> Result<usize, Error> {
+    match e {
+        Atom(Number(n)) => Ok(n.into()),
+        other => Err(Error::Type {
+            expected: "number",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// Make a stream of values which have already been computed.
+fn list_to_stream(values: SExp) -> SExp {
+    let values = values.into_iter().collect::<Vec<_>>();
+    values.into_iter().rev().fold(Null, |rest, value| {
+        SExp::from(Promise::ready(rest)).cons(Promise::ready(value).into())
+    })
+}
+
+/// `(stream-cons head tail)`: a stream whose first element and rest are only
+/// evaluated when they are needed.
+fn stream_cons(ctx: &mut Context, expr: SExp) -> StreamResult {
+    let (head, rest) = expr.split_car()?;
+    let tail = rest.car()?;
+
+    let head = ctx.eval(sexp![SExp::sym("delay"), head])?;
+    let tail = ctx.eval(sexp![SExp::sym("delay"), tail])?;
+    Ok(tail.cons(head))
+}
+
+fn stream_car(ctx: &mut Context, expr: SExp) -> StreamResult {
+    match split_stream(ctx.eval_args(expr)?.car()?)? {
+        Some((head, _)) => head.force(ctx),
+        None => Err(Error::NullList),
+    }
+}
+
+fn stream_cdr(ctx: &mut Context, expr: SExp) -> StreamResult {
+    match split_stream(ctx.eval_args(expr)?.car()?)? {
+        Some((_, tail)) => tail.force(ctx),
+        None => Err(Error::NullList),
+    }
+}
+
+/// `(stream-ref stream n)`: the element at index `n`.
+fn stream_ref(ctx: &mut Context, expr: SExp) -> StreamResult {
+    let (mut stream, rest) = ctx.eval_args(expr)?.split_car()?;
+    let n = count_arg(rest.car()?)?;
+
+    for _ in 0..n {
+        stream = match split_stream(stream)? {
+            Some((_, tail)) => tail.force(ctx)?,
+            None => return Err(Error::Index { i: n }),
+        };
+    }
+
+    match split_stream(stream)? {
+        Some((head, _)) => head.force(ctx),
+        None => Err(Error::Index { i: n }),
+    }
+}
+
+/// `(stream->list [n] stream)`: the first `n` elements of a stream (or all of
+/// them, if it is finite) as a list.
+fn stream_to_list(ctx: &mut Context, expr: SExp) -> StreamResult {
+    let args = ctx.eval_args(expr)?;
+    let (limit, mut stream) = if args.len() == 1 {
+        (None, args.car()?)
+    } else {
+        let (n, rest) = args.split_car()?;
+        (Some(count_arg(n)?), rest.car()?)
+    };
+
+    let mut items = Vec::new();
+    while limit != Some(items.len()) {
+        match split_stream(stream)? {
+            Some((head, tail)) => {
+                items.push(head.force(ctx)?);
+                stream = tail.force(ctx)?;
+            }
+            None => break,
+        }
+    }
+
+    Ok(items.into_iter().collect())
+}
+
+/// `(stream-take n stream)`: a stream of the first `n` elements of another.
+fn stream_take(ctx: &mut Context, expr: SExp) -> StreamResult {
+    let (n, rest) = ctx.eval_args(expr)?.split_car()?;
+    take(count_arg(n)?, rest.car()?)
+}
+
+fn take(n: usize, stream: SExp) -> StreamResult {
+    if n == 0 {
+        return Ok(Null);
+    }
+
+    Ok(match split_stream(stream)? {
+        Some((head, tail)) => lazy(move |ctx| take(n - 1, tail.force(ctx)?)).cons(head.into()),
+        None => Null,
+    })
+}
+
+/// `(stream-map proc stream)`: a stream of the results of applying `proc` to
+/// each element of another, which are computed as they are needed.
+fn stream_map(ctx: &mut Context, expr: SExp) -> StreamResult {
+    let (proc, rest) = ctx.eval_args(expr)?.split_car()?;
+    map(proc, rest.car()?)
+}
+
+fn map(proc: SExp, stream: SExp) -> StreamResult {
+    Ok(match split_stream(stream)? {
+        Some((head, tail)) => {
+            let f = proc.clone();
+            let head = lazy(move |ctx| {
+                let value = head.force(ctx)?;
+                ctx.eval(sexp![f.clone(), quoted(value)])
+            });
+            lazy(move |ctx| map(proc.clone(), tail.force(ctx)?)).cons(head)
+        }
+        None => Null,
+    })
+}
+
+/// `(stream-filter pred stream)`: a stream of the elements of another which
+/// satisfy `pred`. Elements are tested up to the first match when the stream
+/// is made, and the rest as they are needed.
+fn stream_filter(ctx: &mut Context, expr: SExp) -> StreamResult {
+    let (pred, rest) = ctx.eval_args(expr)?.split_car()?;
+    filter(ctx, pred, rest.car()?)
+}
+
+fn filter(ctx: &mut Context, pred: SExp, mut stream: SExp) -> StreamResult {
+    while let Some((head, tail)) = split_stream(stream)? {
+        let value = head.force(ctx)?;
+        if ctx.eval(sexp![pred.clone(), quoted(value.clone())])? != SExp::from(false) {
+            let rest = lazy(move |ctx| {
+                let stream = tail.force(ctx)?;
+                filter(ctx, pred.clone(), stream)
+            });
+            return Ok(rest.cons(Promise::ready(value).into()));
+        }
+        stream = tail.force(ctx)?;
+    }
+
+    Ok(Null)
+}
+
+impl Context {
+    pub(super) fn stream(&mut self) {
+        self.lang.insert("stream-null".to_string(), Null);
+        define_ctx!(self, "stream-cons", stream_cons, 2);
+        define_ctx!(
+            self,
+            "stream-null?",
+            |c, e| Ok((c.eval_args(e)?.car()? == Null).into()),
+            1
+        );
+        define_ctx!(
+            self,
+            "stream-pair?",
+            |c, e| Ok(is_stream_pair(&c.eval_args(e)?.car()?).into()),
+            1
+        );
+        define_ctx!(
+            self,
+            "stream?",
+            |c, e| {
+                let e = c.eval_args(e)?.car()?;
+                Ok((e == Null || is_stream_pair(&e)).into())
+            },
+            1
+        );
+        define_ctx!(self, "stream-car", stream_car, 1);
+        define_ctx!(self, "stream-cdr", stream_cdr, 1);
+        define_ctx!(self, "stream-ref", stream_ref, 2);
+        define_ctx!(
+            self,
+            "stream",
+            |c, e| Ok(list_to_stream(c.eval_args(e)?)),
+            (0,)
+        );
+        define_ctx!(
+            self,
+            "list->stream",
+            |c, e| Ok(list_to_stream(c.eval_args(e)?.car()?)),
+            1
+        );
+        define_ctx!(self, "stream->list", stream_to_list, (1, 2));
+        define_ctx!(self, "stream-take", stream_take, 2);
+        define_ctx!(self, "stream-map", stream_map, 2);
+        define_ctx!(self, "stream-filter", stream_filter, 2);
+    }
+}
@@ -0,0 +1,350 @@
+use std::convert::TryFrom;
+
+use super::super::super::proc::utils::{
+    make_binary_expr, make_chained_comparison, make_unary_expr,
+};
+use super::super::super::Primitive::{Boolean, Character, Number, String as LispString, Void};
+use super::super::super::SExp::{self, Atom, Null, Pair};
+use super::super::super::{Error, Num, Span, SyntaxError};
+use super::super::{quoted, Context};
+
+macro_rules! define_with {
+    ( $ctx:ident, $name:expr, $proc:expr, $tform:expr ) => {
+        $ctx.lang
+            .insert($name.to_string(), $tform($proc, Some($name)))
+    };
+}
+
+macro_rules! define {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Pure(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+macro_rules! define_ctx {
+    ( $ctx:ident, $name:expr, $proc:expr, $arity:expr ) => {
+        $ctx.lang.insert(
+            $name.to_string(),
+            $crate::SExp::from($crate::Proc::new(
+                $crate::Func::Ctx(::std::rc::Rc::new($proc)),
+                $arity,
+                Some($name),
+            )),
+        )
+    };
+}
+
+fn string_arg(e: SExp) -> Result<String, Error> {
+    match e {
+        Atom(LispString(s)) => Ok(s),
+        other => Err(Error::Type {
+            expected: "string",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// An index into a string, counted in characters.
+fn index_arg(e: SExp) -> Result<usize, Error> {
+    match e {
+        Atom(Number(Num::Int(i))) => usize::try_from(i).map_err(|_| Error::Type {
+            expected: "non-negative index",
+            given: i.to_string(),
+        }),
+        other => Err(Error::Type {
+            expected: "exact integer",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// The characters of a string from `start` (inclusive) to `end` (exclusive),
+/// which default to its start and its end.
+fn char_range(s: &str, start: Option<SExp>, end: Option<SExp>) -> Result<String, Error> {
+    let len = s.chars().count();
+    let start = start.map_or(Ok(0), index_arg)?;
+    let end = end.map_or(Ok(len), index_arg)?;
+
+    if end > len {
+        return Err(Error::Index { i: end });
+    }
+    if start > end {
+        return Err(Error::Index { i: start });
+    }
+
+    Ok(s.chars().skip(start).take(end - start).collect())
+}
+
+/// The characters of a string given as the first argument, in the range
+/// given by the optional arguments after it, as for
+/// `(string-copy string [start [end]])`.
+fn string_range(e: SExp) -> Result<String, Error> {
+    let (s, rest) = e.split_car()?;
+    let (start, end) = match rest.split_car() {
+        Ok((start, rest)) => (Some(start), rest.car().ok()),
+        Err(_) => (None, None),
+    };
+
+    char_range(&string_arg(s)?, start, end)
+}
+
+/// `(string-ref string k)`
+fn string_ref(s: SExp, k: SExp) -> Result<SExp, Error> {
+    let k = index_arg(k)?;
+    string_arg(s)?
+        .chars()
+        .nth(k)
+        .map(SExp::from)
+        .ok_or(Error::Index { i: k })
+}
+
+/// `(string-append string...)`
+fn string_append(e: SExp) -> Result<SExp, Error> {
+    Ok(e.into_iter()
+        .map(string_arg)
+        .collect::<Result<String, _>>()?
+        .into())
+}
+
+/// `(string-contains string pattern)`: the index of the first character of
+/// the first occurrence of the pattern, or `#f` if there is none.
+fn string_contains(s: SExp, pattern: SExp) -> Result<SExp, Error> {
+    let (s, pattern) = (string_arg(s)?, string_arg(pattern)?);
+    Ok(match s.find(&pattern) {
+        Some(byte) => s[..byte].chars().count().into(),
+        None => false.into(),
+    })
+}
+
+/// A separator for `string-split` and `string-join`, which may be a string
+/// or a single character.
+fn separator_arg(e: SExp) -> Result<String, Error> {
+    match e {
+        Atom(Character(c)) => Ok(c.to_string()),
+        other => string_arg(other),
+    }
+}
+
+/// `(string-split string [separator])`: split on every occurrence of the
+/// separator, or on runs of whitespace if there is none.
+fn string_split(e: SExp) -> Result<SExp, Error> {
+    let (s, rest) = e.split_car()?;
+    let s = string_arg(s)?;
+
+    Ok(match rest.car() {
+        Ok(sep) => {
+            let sep = separator_arg(sep)?;
+            if sep.is_empty() {
+                return Err(Error::Type {
+                    expected: "non-empty separator",
+                    given: "empty string".to_string(),
+                });
+            }
+            s.split(sep.as_str()).map(SExp::from).collect()
+        }
+        Err(_) => s.split_whitespace().map(SExp::from).collect(),
+    })
+}
+
+/// `(string-join list [separator])`: join a list of strings, with a space
+/// between each if no separator is given.
+fn string_join(e: SExp) -> Result<SExp, Error> {
+    let (parts, rest) = e.split_car()?;
+    let sep = match rest.car() {
+        Ok(sep) => separator_arg(sep)?,
+        Err(_) => " ".to_string(),
+    };
+
+    let parts = parts
+        .into_iter()
+        .map(string_arg)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SExp::from(parts.join(&sep).as_str()))
+}
+
+/// Split one line of CSV into its fields. Fields may be quoted, in which case
+/// they can contain commas, and `""` stands for a quote.
+fn csv_fields(line: &str) -> Result<Vec<String>, Error> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.char_indices().peekable();
+    let mut quote_start = None;
+
+    while let Some((i, c)) = chars.next() {
+        match (c, quote_start) {
+            ('"', Some(_)) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', Some(_)) => quote_start = None,
+            ('"', None) if field.is_empty() => quote_start = Some(i),
+            (',', None) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+
+    if let Some(start) = quote_start {
+        return Err(SyntaxError::UnterminatedString {
+            span: Span::new(start, line.len()),
+        }
+        .into());
+    }
+
+    fields.push(field);
+    Ok(fields)
+}
+
+/// `(csv-read-line line)`: the fields of a line of CSV, as a list of strings.
+fn csv_read_line(e: SExp) -> Result<SExp, Error> {
+    let fields = csv_fields(&string_arg(e)?)?;
+    Ok(fields.iter().map(|f| SExp::from(f.as_str())).collect())
+}
+
+/// `(csv-write-row list)`: a line of CSV (without a line ending) with the
+/// values in the list as its fields. Fields are quoted only if they need to
+/// be, and values other than strings are written as they are displayed.
+#[allow(clippy::unnecessary_wraps)]
+fn csv_write_row(e: SExp) -> Result<SExp, Error> {
+    let fields = e
+        .into_iter()
+        .map(|value| {
+            let field = value.to_string();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(SExp::from(fields.join(",").as_str()))
+}
+
+impl Context {
+    /// `(string-index string pred)`: the index of the first character which
+    /// is equal to `pred`, if it is a character, or which satisfies it
+    /// otherwise. `#f` if there is none.
+    fn string_index(&mut self, expr: SExp) -> Result<SExp, Error> {
+        let (s, rest) = self.eval_args(expr)?.split_car()?;
+        let pred = rest.car()?;
+
+        for (i, c) in string_arg(s)?.chars().enumerate() {
+            let found = match &pred {
+                Atom(Character(target)) => c == *target,
+                pred => !matches!(
+                    self.eval(sexp![pred.clone(), quoted(c.into())])?,
+                    Atom(Boolean(false))
+                ),
+            };
+            if found {
+                return Ok(i.into());
+            }
+        }
+
+        Ok(false.into())
+    }
+
+    /// `(string-for-each proc string)`: call `proc` with each character.
+    fn string_for_each(&mut self, expr: SExp) -> Result<SExp, Error> {
+        let (proc, rest) = self.eval_args(expr)?.split_car()?;
+
+        for c in string_arg(rest.car()?)?.chars() {
+            self.eval(sexp![proc.clone(), quoted(c.into())])?;
+        }
+
+        Ok(Atom(Void))
+    }
+
+    pub(super) fn string(&mut self) {
+        define!(
+            self,
+            "string->list",
+            |e| Ok(string_range(e)?.chars().map(SExp::from).collect()),
+            (1, 3)
+        );
+        define!(
+            self,
+            "list->string",
+            |e| match e.car()? {
+                Null => Ok(Atom(LispString(String::new()))),
+                e @ Pair { .. } => {
+                    match e.into_iter().try_fold(String::new(), |mut s, e| match e {
+                        Atom(Character(c)) => {
+                            s.push(c);
+                            Ok(s)
+                        }
+                        _ => Err(Error::Type {
+                            expected: "char",
+                            given: e.type_of().to_string(),
+                        }),
+                    }) {
+                        Ok(s) => Ok(Atom(LispString(s))),
+                        Err(err) => Err(err),
+                    }
+                }
+                other => Err(Error::Type {
+                    expected: "list",
+                    given: other.type_of().to_string()
+                }),
+            },
+            1
+        );
+        self.lang.insert(
+            "string=?".to_string(),
+            make_chained_comparison(
+                |e| match e {
+                    Atom(LispString(s)) => Ok(s),
+                    other => Err(Error::Type {
+                        expected: "string",
+                        given: other.type_of().to_string(),
+                    }),
+                },
+                PartialEq::eq,
+                Some("string=?"),
+            ),
+        );
+        define_with!(
+            self,
+            "string-length",
+            |e| Ok(string_arg(e)?.chars().count().into()),
+            make_unary_expr
+        );
+        define_with!(self, "string-ref", string_ref, make_binary_expr);
+        define!(self, "substring", |e| Ok(string_range(e)?.into()), (2, 3));
+        define!(self, "string-copy", |e| Ok(string_range(e)?.into()), (1, 3));
+        define!(self, "string-append", string_append, (0,));
+        define_with!(
+            self,
+            "string-upcase",
+            |e| Ok(string_arg(e)?.to_uppercase().into()),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "string-downcase",
+            |e| Ok(string_arg(e)?.to_lowercase().into()),
+            make_unary_expr
+        );
+        define_with!(
+            self,
+            "string-reverse",
+            |e| Ok(string_arg(e)?.chars().rev().collect::<String>().into()),
+            make_unary_expr
+        );
+        define_ctx!(self, "string-index", Self::string_index, 2);
+        define_with!(self, "string-contains", string_contains, make_binary_expr);
+        define_ctx!(self, "string-for-each", Self::string_for_each, 2);
+        define!(self, "string-split", string_split, (1, 2));
+        define!(self, "string-join", string_join, (1, 2));
+        define_with!(self, "csv-read-line", csv_read_line, make_unary_expr);
+        define_with!(self, "csv-write-row", csv_write_row, make_unary_expr);
+    }
+}
@@ -1,5 +1,8 @@
 #![cfg(test)]
 
+use super::super::super::Resource;
+use super::super::super::SExp::Pair;
+use super::super::ReplControl;
 use super::*;
 
 fn eval(e: SExp) -> Result {
@@ -23,6 +26,10 @@ fn eq_test() {
 
     assert_eq!(
         eval(sexp![eq(), 1 + 2 + 3, 9. - 3.5 + 0.25 * 2.]).unwrap(),
+        SExp::from(false)
+    );
+    assert_eq!(
+        eval(sexp![eq(), 1. + 2. + 3., 9. - 3.5 + 0.25 * 2.]).unwrap(),
         SExp::from(true)
     );
 
@@ -185,3 +192,1669 @@ fn type_of() {
         eval(sexp![tpf(), sexp![SExp::sym("list"), false, '\0']]).unwrap(),
     );
 }
+
+#[test]
+fn division() {
+    let div = || SExp::sym("/");
+
+    assert_eq!(eval(sexp![div(), 12, 3, 2]).unwrap(), SExp::from(2));
+    assert_eq!(eval(sexp![div(), 1, 4]).unwrap(), SExp::from(0.25));
+
+    assert!(matches!(
+        eval(sexp![div(), 1, 0]),
+        Err(Error::DivisionByZero)
+    ));
+    assert!(matches!(
+        eval(sexp![div(), 8, 2, 0]),
+        Err(Error::DivisionByZero)
+    ));
+
+    assert_eq!(
+        eval(sexp![div(), 1.0, 0.0]).unwrap(),
+        SExp::from(std::f64::INFINITY)
+    );
+    assert_eq!(
+        eval(sexp![div(), -1.0, 0]).unwrap(),
+        SExp::from(std::f64::NEG_INFINITY)
+    );
+
+    // a single argument is inverted
+    assert_eq!(eval(sexp![div(), 4]).unwrap(), SExp::from(0.25));
+    assert!(matches!(eval(sexp![div(), 0]), Err(Error::DivisionByZero)));
+}
+
+#[test]
+fn negation() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run("(list (- 5) (- -2.5) (- 0) (- +inf.0) (- -inf.0) (- 7 2 1))"),
+        "(-5 2.5 0 -inf.0 +inf.0 4)"
+    );
+    assert!(ctx.run("(-)").is_err());
+    assert!(ctx.run("(- 'a)").is_err());
+}
+
+#[test]
+fn remainder_and_modulo() {
+    let rem = || SExp::sym("remainder");
+    let md = || SExp::sym("modulo");
+
+    for (n, d, r, m) in &[
+        (13, 4, 1, 1),
+        (-13, 4, -1, 3),
+        (13, -4, 1, -3),
+        (-13, -4, -1, -1),
+        (12, 4, 0, 0),
+        (-12, 4, 0, 0),
+    ] {
+        assert_eq!(eval(sexp![rem(), *n, *d]).unwrap(), SExp::from(*r));
+        assert_eq!(eval(sexp![md(), *n, *d]).unwrap(), SExp::from(*m));
+    }
+
+    assert_eq!(eval(sexp![md(), -7.5, 2]).unwrap(), SExp::from(0.5));
+    assert_eq!(eval(sexp![rem(), -7.5, 2]).unwrap(), SExp::from(-1.5));
+
+    assert!(matches!(
+        eval(sexp![md(), 1, 0]),
+        Err(Error::DivisionByZero)
+    ));
+    assert!(matches!(
+        eval(sexp![rem(), 1, 0]),
+        Err(Error::DivisionByZero)
+    ));
+}
+
+#[test]
+fn number_to_string() {
+    let n2s = || SExp::sym("number->string");
+    let q = |s: &str| sexp![SExp::sym("quote"), SExp::sym(s)];
+
+    assert_eq!(eval(sexp![n2s(), 42]).unwrap(), SExp::from("42"));
+    assert_eq!(eval(sexp![n2s(), 2.5]).unwrap(), SExp::from("2.5"));
+    assert_eq!(eval(sexp![n2s(), 0.0000001]).unwrap(), SExp::from("1e-7"));
+    assert_eq!(eval(sexp![n2s(), 1e22]).unwrap(), SExp::from("1e22"));
+    assert_eq!(
+        eval(sexp![n2s(), 0.0000001, 10, q("fixed")]).unwrap(),
+        SExp::from("0.0000001")
+    );
+    assert_eq!(
+        eval(sexp![n2s(), 1234.5, 10, q("scientific")]).unwrap(),
+        SExp::from("1.2345e3")
+    );
+    assert_eq!(
+        eval(sexp![n2s(), 1234.5, 10, q("scientific"), 1]).unwrap(),
+        SExp::from("1.2e3")
+    );
+    assert_eq!(
+        eval(sexp![n2s(), 2.0 / 3.0, 10, q("fixed"), 3]).unwrap(),
+        SExp::from("0.667")
+    );
+    // exact integers are unaffected
+    assert_eq!(
+        eval(sexp![n2s(), 1234, 10, q("scientific"), 1]).unwrap(),
+        SExp::from("1234")
+    );
+
+    assert!(eval(sexp![n2s(), "1"]).is_err());
+    assert!(eval(sexp![n2s(), 1, 10, q("potato")]).is_err());
+
+    // the radix comes second
+    assert_eq!(eval(sexp![n2s(), 255, 16]).unwrap(), SExp::from("ff"));
+    assert_eq!(eval(sexp![n2s(), -255, 16]).unwrap(), SExp::from("-ff"));
+    assert_eq!(eval(sexp![n2s(), 5, 2]).unwrap(), SExp::from("101"));
+    assert_eq!(eval(sexp![n2s(), 64, 8]).unwrap(), SExp::from("100"));
+    assert_eq!(eval(sexp![n2s(), 42, 10]).unwrap(), SExp::from("42"));
+    assert!(eval(sexp![n2s(), 255, 3]).is_err());
+    assert!(eval(sexp![n2s(), 2.5, 16]).is_err());
+    assert!(eval(sexp![n2s(), 1, q("fixed")]).is_err());
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn string_to_list() {
+    assert_eq!(
+        eval(sexp![SExp::sym("string->list"), "abc"]).unwrap(),
+        sexp!['a', 'b', 'c']
+    );
+    assert!(eval(sexp![SExp::sym("string->list"), 'a']).is_err());
+
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+    assert_eq!(run(r#"(list->string (string->list "hello" 1))"#), "ello");
+    assert_eq!(run(r#"(list->string (string->list "hello" 1 3))"#), "el");
+    assert_eq!(run(r#"(string->list "hello" 5)"#), "()");
+    assert!(ctx.run(r#"(string->list "hello" 3 2)"#).is_err());
+    assert!(ctx.run(r#"(string->list "hello" 0 6)"#).is_err());
+    assert!(ctx.run(r#"(string->list "hello" 'a)"#).is_err());
+    assert!(ctx.run(r#"(string->list "hello" 0 1 2)"#).is_err());
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn list_to_string() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(run(r"(list->string (list #\a #\b))"), "ab");
+    assert_eq!(run("(list->string (list))"), "");
+    assert_eq!(run(r#"(list->string (string->list "abc"))"#), "abc");
+    assert!(ctx.run(r#"(list->string "abc")"#).is_err());
+    assert!(ctx.run("(list->string (list 1))").is_err());
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn chars() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run(r"(list (char-alphabetic? #\a) (char-alphabetic? #\é) (char-alphabetic? #\1))"),
+        "(#t #t #f)"
+    );
+    assert_eq!(
+        run(
+            r"(list (char-numeric? #\7) (char-numeric? #\x) (char-whitespace? #\space) (char-whitespace? #\tab) (char-whitespace? #\a))"
+        ),
+        "(#t #f #t #t #f)"
+    );
+    assert_eq!(
+        run(r"(list (char-upper-case? #\A) (char-lower-case? #\A) (char? #\a) (char? 1))"),
+        "(#t #f #t #f)"
+    );
+
+    assert_eq!(
+        run(
+            r#"(list->string (list (char-upcase #\a) (char-downcase #\Q) (char-upcase #\1) (char-upcase #\ß)))"#
+        ),
+        "Aq1ß"
+    );
+    assert_eq!(run(r"(char->integer #\A)"), "65");
+    assert_eq!(run(r"(char->integer #\x3bb)"), "955");
+    assert_eq!(run("(integer->char 97)"), "a");
+    assert_eq!(
+        run(r"(char=? (integer->char (char->integer #\λ)) #\λ)"),
+        "#t"
+    );
+
+    assert_eq!(
+        run(
+            r"(list (char=? #\a #\a #\a) (char=? #\a #\a #\b) (char<? #\a #\b #\c) (char<? #\a #\c #\b))"
+        ),
+        "(#t #f #t #f)"
+    );
+    assert_eq!(
+        run(r"(list (char>? #\b #\a) (char<=? #\a #\a #\b) (char>=? #\b #\b #\c))"),
+        "(#t #t #f)"
+    );
+
+    assert!(ctx.run(r#"(char-upcase "a")"#).is_err());
+    assert!(ctx.run(r"(char<? #\a 1)").is_err());
+    assert!(ctx.run("(integer->char -1)").is_err());
+    assert!(ctx.run("(integer->char 55296)").is_err());
+    assert!(ctx.run("(integer->char 65.0)").is_err());
+}
+
+#[test]
+fn bindings() {
+    let mut ctx = Context::base();
+    ctx.run("(define (my-proc x y) x)").unwrap();
+    let listing = ctx.run("(bindings)").unwrap();
+    let find = |name: &str| listing.iter().find(|b| b[0] == SExp::sym(name)).cloned();
+
+    assert_eq!(
+        find("car"),
+        Some(sexp![SExp::sym("car"), "procedure", (1, 1)])
+    );
+    assert_eq!(
+        find("my-proc"),
+        Some(sexp![SExp::sym("my-proc"), "procedure", (2, 2)])
+    );
+    assert_eq!(
+        find("if"),
+        Some(sexp![SExp::sym("if"), "special form", (3, 3)])
+    );
+    assert_eq!(find("pi").map(|b| b[2].clone()), Some(SExp::from(false)));
+    assert_eq!(find("potato"), None);
+}
+
+#[test]
+fn list_constructors() {
+    let mut ctx = Context::base();
+
+    assert_eq!(
+        ctx.run("(make-list 3 'x)").unwrap(),
+        ctx.run("'(x x x)").unwrap()
+    );
+    assert_eq!(ctx.run("(make-list 0 'x)").unwrap(), Null);
+    assert!(ctx.run("(make-list 'x)").is_err());
+    // the count must be a non-negative exact integer
+    assert!(matches!(
+        ctx.run("(make-list -1 0)"),
+        Err(Error::Type { ref given, .. }) if given == "-1"
+    ));
+    assert!(ctx.run("(make-list 2.5 0)").is_err());
+    assert!(ctx.run("(make-list 2.0 0)").is_err());
+
+    ctx.run("(define a '(1 2 3))").unwrap();
+    ctx.run("(define b (list-copy a))").unwrap();
+    assert_eq!(ctx.run("(equal? a b)").unwrap(), SExp::from(true));
+
+    assert_eq!(
+        ctx.run("(list-set! b 1 'two)").unwrap(),
+        ctx.run("'(1 two 3)").unwrap()
+    );
+    assert_eq!(
+        ctx.run("(list-set! (list 1 2) 0 'zero)").unwrap(),
+        ctx.run("'(zero 2)").unwrap()
+    );
+    // the original is left as it was
+    assert_eq!(ctx.run("a").unwrap(), ctx.run("'(1 2 3)").unwrap());
+
+    assert!(ctx.run("(list-set! b 3 'four)").is_err());
+    // the index must be a non-negative exact integer
+    assert!(matches!(
+        ctx.run("(list-set! b -1 'x)"),
+        Err(Error::Type { ref given, .. }) if given == "-1"
+    ));
+    assert!(ctx.run("(list-set! b 1.0 'x)").is_err());
+    assert!(ctx.run("(list-set! b 'x 'x)").is_err());
+}
+
+#[test]
+fn association_lists() {
+    let mut ctx = Context::base();
+
+    assert_eq!(
+        ctx.run("(assq 'b '((a 1) (b 2)))").unwrap(),
+        ctx.run("'(b 2)").unwrap()
+    );
+    assert_eq!(
+        ctx.run("(assv 2 '((1 one) (2 two)))").unwrap(),
+        ctx.run("'(2 two)").unwrap()
+    );
+    assert_eq!(ctx.run("(assq 'c '((a 1) (b 2)))").unwrap(), false.into());
+    assert_eq!(ctx.run("(assv 1 '())").unwrap(), false.into());
+    assert!(ctx.run("(assq 'a '(1 2))").is_err());
+    assert_eq!(
+        ctx.run("(cadr (assv 2 '((1 one) (2 two))))").unwrap(),
+        SExp::sym("two")
+    );
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn typed_equality() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap();
+
+    assert_eq!(run("(boolean=? #t #t)"), SExp::from(true));
+    assert_eq!(run("(boolean=? #f #f #f)"), SExp::from(true));
+    assert_eq!(run("(boolean=? #t #t #f)"), SExp::from(false));
+    assert_eq!(run("(string=? \"ab\" \"ab\" \"ab\")"), SExp::from(true));
+    assert_eq!(run("(string=? \"ab\" \"abc\")"), SExp::from(false));
+    assert_eq!(run("(symbol=? 'a 'a 'a)"), SExp::from(true));
+    assert_eq!(run("(symbol=? 'a 'a 'b)"), SExp::from(false));
+
+    // types are checked for every argument
+    assert!(ctx.run("(boolean=? #t #f 1)").is_err());
+    assert!(ctx.run("(symbol=? 'a \"a\")").is_err());
+    assert!(ctx.run("(string=? \"a\")").is_err());
+}
+
+#[test]
+fn shadowing_globals() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap();
+
+    assert_eq!(run("(+ 2 3)"), SExp::from(5));
+    assert_eq!(run("((lambda (+) (+ 2 3)) *)"), SExp::from(6));
+    assert_eq!(run("(+ 2 3)"), SExp::from(5));
+
+    // a closure over a scope which shadows a global, called after the global
+    // has been looked up elsewhere
+    run("(define g (let ((car cdr)) (lambda (x) (car x))))");
+    assert_eq!(run("(car '(1 2))"), SExp::from(1));
+    assert_eq!(run("(g '(1 2))"), sexp![2]);
+}
+
+#[test]
+fn multiple_values() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap();
+
+    assert_eq!(run("(values 4)"), SExp::from(4));
+    assert_eq!(
+        run("(call-with-values (lambda () (values 1 2)) +)"),
+        SExp::from(3)
+    );
+    assert_eq!(
+        run("(call-with-values (lambda () (values 'a 'b)) list)"),
+        sexp![SExp::sym("a"), SExp::sym("b")]
+    );
+    assert_eq!(run("(call-with-values (lambda () 5) list)"), sexp![5]);
+    assert_eq!(run("(call-with-values values list)"), Null);
+
+    assert_eq!(
+        run("(call-with-values (lambda () (floor/ 7 2)) list)"),
+        sexp![3, 1]
+    );
+    assert_eq!(
+        run("(call-with-values (lambda () (floor/ -7 2)) list)"),
+        sexp![-4, 1]
+    );
+    assert_eq!(
+        run("(call-with-values (lambda () (truncate/ -7 2)) list)"),
+        sexp![-3, -1]
+    );
+    assert!(ctx.run("(floor/ 1 0)").is_err());
+}
+
+#[cfg(feature = "clos")]
+#[test]
+fn classes() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap();
+
+    run("(define-class <point> () (x y))");
+    run("(define-class <point3d> (<point>) (z))");
+    run("(define p (make <point3d> 'x 1 'y 2 'z 3))");
+
+    // inherited accessors apply to subclasses
+    assert_eq!(
+        run("(list (point-x p) (point-y p) (point3d-z p))"),
+        sexp![1, 2, 3]
+    );
+    assert_eq!(run("(point-x (make <point> 'x 5))"), SExp::from(5));
+
+    // setters change the instance in place, including inherited slots
+    run("(define r (make <point3d> 'x 1 'y 2 'z 3)) (define q r)");
+    run("(set-point-x! q 10) (set-point3d-z! (car (list r)) 30)");
+    assert_eq!(
+        run("(list (point-x r) (point-y r) (point3d-z r))"),
+        sexp![10, 2, 30]
+    );
+
+    assert_eq!(run("(is-a? p <point3d>)"), SExp::from(true));
+    assert_eq!(run("(is-a? p <point>)"), SExp::from(true));
+    assert_eq!(run("(is-a? (make <point>) <point3d>)"), SExp::from(false));
+    assert_eq!(run("(is-a? 5 <point>)"), SExp::from(false));
+
+    // methods on a class apply to its subclasses, unless overridden
+    run("(define-generic norm)");
+    run("(define-method (norm (p <point>)) (+ (point-x p) (point-y p)))");
+    assert_eq!(run("(norm p)"), SExp::from(3));
+    run("(define-method (norm (p <point3d>)) (+ (point-x p) (point-y p) (point3d-z p)))");
+    assert_eq!(run("(norm p)"), SExp::from(6));
+    assert_eq!(run("(norm (make <point> 'x 1 'y 1))"), SExp::from(2));
+
+    assert!(ctx.run("(make <point> 'w 1)").is_err());
+    assert!(ctx.run("(make <point> 'x)").is_err());
+    assert!(ctx.run("(define-class <c> (<nothing>) ())").is_err());
+}
+
+#[test]
+fn error_objects() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    match run("(error \"bad thing:\" 1 'two \"three\" '(4 #\\a))") {
+        Err(err @ Error::User { .. }) => {
+            assert_eq!(err.to_string(), "Error: bad thing: 1 two three (4 a)");
+        }
+        other => panic!("expected a user error, got {:?}", other),
+    }
+
+    run("(define (catch thunk) (guard (e ((error-object? e) e)) (thunk)))").unwrap();
+    run("(define e (catch (lambda () (error \"oops\" 1 2))))").unwrap();
+    assert_eq!(run("(error-object-message e)").unwrap(), SExp::from("oops"));
+    assert_eq!(run("(error-object-irritants e)").unwrap(), sexp![1, 2]);
+
+    // errors from the interpreter are error objects too
+    run("(define e (catch (lambda () (car 5))))").unwrap();
+    assert_eq!(run("(error-object? e)").unwrap(), SExp::from(true));
+    assert_eq!(run("(error-object-irritants e)").unwrap(), Null);
+    assert_eq!(run("(error-object? 'oops)").unwrap(), SExp::from(false));
+    assert!(run("(error-object-message 'oops)").is_err());
+
+    // ...which are raised again as they were
+    assert_eq!(
+        run("(guard (e ((eq? e 'x) e)) (raise (catch (lambda () (error \"again\")))))")
+            .unwrap_err()
+            .to_string(),
+        "Error: again"
+    );
+    assert_eq!(
+        run("(with-exception-handler (lambda (e) (error-object-message e)) (lambda () (raise-continuable (catch (lambda () (error \"handled\"))))))")
+            .unwrap(),
+        SExp::from("handled")
+    );
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn include() {
+    let dir = std::env::temp_dir().join("parsley-include-test");
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(
+        dir.join("main.scm"),
+        r#"(include "lib/util.scm") (define b 2)"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("lib/util.scm"),
+        r#"(include "more.scm") (define a 1)"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("lib/more.scm"), "(define (sum) (+ a b))").unwrap();
+
+    let mut ctx = Context::base();
+    let main = dir.join("main.scm").display().to_string();
+    ctx.run(&format!("(include {main:?})")).unwrap();
+    assert_eq!(ctx.run("(sum)").unwrap(), SExp::from(3));
+
+    // included forms are evaluated in the scope they're included in
+    assert_eq!(
+        ctx.run(&format!("(let ((a 10)) (include {main:?}) (sum))"))
+            .unwrap(),
+        SExp::from(3)
+    );
+
+    assert!(ctx.run(r#"(include "no-such-file.scm")"#).is_err());
+    assert!(ctx.run("(include 5)").is_err());
+}
+
+#[test]
+fn gensym() {
+    let mut ctx = Context::base();
+
+    let g1 = ctx.run("(gensym)").unwrap();
+    let g2 = ctx.run("(gensym)").unwrap();
+    assert_ne!(g1, g2);
+    assert_eq!(g1.type_of(), "symbol");
+
+    // the reader can't produce the same symbol
+    assert!(g1.to_string().parse::<SExp>().map_or(true, |s| s != g1));
+
+    let tmp = ctx.run("(generate-uninterned-symbol 'tmp)").unwrap();
+    assert!(tmp.to_string().starts_with("tmp"));
+    assert_ne!(tmp, SExp::sym("tmp"));
+    assert_eq!(ctx.run(r#"(gensym "loop")"#).unwrap().type_of(), "symbol");
+    assert!(ctx.run("(gensym 5)").is_err());
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn string_split_join() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run(r#"(string-split "a,b,,c" #\,)"#).unwrap(),
+        sexp!["a", "b", "", "c"]
+    );
+    assert_eq!(
+        run(r#"(string-split "one::two" "::")"#).unwrap(),
+        sexp!["one", "two"]
+    );
+    assert_eq!(
+        run(r#"(string-split "  some   words ")"#).unwrap(),
+        sexp!["some", "words"]
+    );
+    assert!(run(r#"(string-split "abc" "")"#).is_err());
+
+    assert_eq!(
+        run(r#"(string-join '("a" "b" "c") ", ")"#).unwrap(),
+        SExp::from("a, b, c")
+    );
+    assert_eq!(
+        run(r#"(string-join '("a" "b"))"#).unwrap(),
+        SExp::from("a b")
+    );
+    assert_eq!(run("(string-join '())").unwrap(), SExp::from(""));
+    assert!(run("(string-join '(1 2))").is_err());
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn string_procedures() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(run(r#"(string-length "λx.x")"#), "4");
+    assert_eq!(run(r#"(string-ref "λx.x" 1)"#), "x");
+    assert_eq!(run(r#"(substring "hello" 1 3)"#), "el");
+    assert_eq!(run(r#"(substring "hello" 2)"#), "llo");
+    assert_eq!(run(r#"(string-copy "hello" 1)"#), "ello");
+    assert_eq!(run(r#"(string=? (string-copy "hi") "hi")"#), "#t");
+    assert_eq!(run(r#"(string-append "a" "" "bc" "d")"#), "abcd");
+    assert_eq!(run("(string-length (string-append))"), "0");
+    assert_eq!(
+        run(r#"(list (string-upcase "Straße") (string-downcase "ABC"))"#),
+        "(STRASSE abc)"
+    );
+    assert_eq!(run(r#"(string-reverse "abc")"#), "cba");
+
+    assert_eq!(
+        run(r#"(list (string-index "hello" #\l) (string-index "hello" char-upper-case?))"#),
+        "(2 #f)"
+    );
+    assert_eq!(
+        run(r#"(string-index "ab1" (lambda (c) (char-numeric? c)))"#),
+        "2"
+    );
+    assert_eq!(
+        run(
+            r#"(list (string-contains "λ-calculus" "calc") (string-contains "abc" "x") (string-contains "abc" ""))"#
+        ),
+        "(2 #f 0)"
+    );
+
+    run(r#"(define chars '()) (string-for-each (lambda (c) (set! chars (cons c chars))) "abc")"#);
+    assert_eq!(run("(list->string chars)"), "cba");
+
+    assert!(ctx.run(r#"(string-ref "abc" 3)"#).is_err());
+    assert!(ctx.run(r#"(substring "abc" 2 1)"#).is_err());
+    assert!(ctx.run(r#"(substring "abc" 0 4)"#).is_err());
+    assert!(ctx.run(r#"(substring "abc" -1)"#).is_err());
+    assert!(ctx.run(r#"(string-append "a" #\b)"#).is_err());
+    assert!(ctx.run("(string-length 'abc)").is_err());
+}
+
+#[cfg(feature = "strings")]
+#[test]
+fn csv() {
+    let mut ctx = Context::base();
+
+    assert_eq!(
+        ctx.run(r#"(csv-read-line "name,age,,city")"#).unwrap(),
+        sexp!["name", "age", "", "city"]
+    );
+
+    // string literals can't contain quotes yet, so define them here
+    ctx.define("line", SExp::from("\"Smith, J\",\"say \"\"hi\"\"\",3\r\n"));
+    ctx.define("row", sexp!["Smith, J", "say \"hi\"", 3, SExp::sym("x")]);
+    ctx.define("open", SExp::from("a,\"b"));
+
+    assert_eq!(
+        ctx.run("(csv-read-line line)").unwrap(),
+        sexp!["Smith, J", "say \"hi\"", "3"]
+    );
+    assert!(ctx.run("(csv-read-line open)").is_err());
+
+    assert_eq!(
+        ctx.run("(csv-write-row row)").unwrap(),
+        SExp::from(r#""Smith, J","say ""hi""",3,x"#)
+    );
+    // writing and reading a row gets the same fields back
+    assert_eq!(
+        ctx.run("(csv-read-line (csv-write-row (csv-read-line line)))")
+            .unwrap(),
+        sexp!["Smith, J", "say \"hi\"", "3"]
+    );
+}
+
+#[test]
+fn procedure_parameters() {
+    let mut ctx = Context::base();
+    ctx.run("(define (f x y . rest) x)").unwrap();
+    ctx.run("(define g (lambda (a #!optional b (c 3)) a))")
+        .unwrap();
+    ctx.run("(define h (named-lambda (h . args) args))")
+        .unwrap();
+
+    let params = |ctx: &mut Context, name: &str| {
+        ctx.run(&format!("(procedure-parameters {})", name))
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(params(&mut ctx, "f"), "(x y . rest)");
+    assert_eq!(params(&mut ctx, "g"), "(a #!optional b (c 3))");
+    assert_eq!(params(&mut ctx, "h"), "args");
+    assert_eq!(params(&mut ctx, "car"), "#f");
+    assert!(ctx.run("(procedure-parameters 1)").is_err());
+
+    let f = ctx.bindings().into_iter().find(|b| b.name == "f").unwrap();
+    assert_eq!(f.signature.as_deref(), Some("(lambda (x y . rest) ...)"));
+}
+
+#[test]
+fn docstrings() {
+    let mut ctx = Context::base();
+    ctx.run(r#"(define (twice x) "Double a number." (* x 2))"#)
+        .unwrap();
+    ctx.run(r#"(define (greeting) "hello")"#).unwrap();
+
+    // the docstring is not part of the body, unless it is the whole body
+    assert_eq!(ctx.run("(twice 4)").unwrap(), SExp::from(8));
+    assert_eq!(ctx.run("(greeting)").unwrap(), SExp::from("hello"));
+
+    let help = |ctx: &mut Context, code: &str| {
+        ctx.capture();
+        ctx.run(code).unwrap();
+        ctx.get_output().unwrap()
+    };
+    assert_eq!(
+        help(&mut ctx, "(help twice)"),
+        "twice: procedure\n  (lambda (x) ...)\n  Double a number.\n"
+    );
+    assert_eq!(
+        help(&mut ctx, "(describe 'car)"),
+        "car: procedure\n  takes 1 argument(s)\n  The first element of a pair.\n"
+    );
+    assert_eq!(
+        help(&mut ctx, "(help if)"),
+        "if: special form\n  takes 3 argument(s)\n"
+    );
+    assert_eq!(
+        help(&mut ctx, r#"(help (lambda (a . b) "Anonymous." a))"#),
+        "#<procedure>: procedure\n  (lambda (a . b) ...)\n  Anonymous.\n"
+    );
+    assert!(ctx.run("(help potato)").is_err());
+}
+
+#[cfg(feature = "vectors")]
+#[test]
+fn environment_tree() {
+    let mut ctx = Context::base();
+    ctx.run("(define big (make-vector 1000 0))").unwrap();
+
+    assert_eq!(
+        ctx.run("(let ((x 1)) (let ((f car)) (environment-tree)))")
+            .unwrap()
+            .to_string(),
+        "((bindings (big vector #f)) \
+         (inner (bindings (x number #f)) \
+         (inner (bindings (f procedure (1 . 1))) (inner))))"
+    );
+    assert_eq!(
+        ctx.run("(environment-tree (make-environment))")
+            .unwrap()
+            .to_string(),
+        "((bindings) (inner))"
+    );
+    assert!(ctx.run("(environment-tree 1)").is_err());
+}
+
+#[test]
+fn streams() {
+    let mut ctx = Context::base();
+    ctx.run(
+        "(define (integers-from n) (stream-cons n (integers-from (+ n 1))))
+         (define naturals (integers-from 0))
+         (define evaluated 0)
+         (define s (stream-cons (begin (set! evaluated (+ evaluated 1)) 'a) stream-null))",
+    )
+    .unwrap();
+    let run = |ctx: &mut Context, code: &str| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(run(&mut ctx, "(stream->list 5 naturals)"), "(0 1 2 3 4)");
+    assert_eq!(run(&mut ctx, "(stream-ref naturals 100)"), "100");
+    assert_eq!(
+        run(
+            &mut ctx,
+            "(stream->list (stream-take 3 (stream-map (lambda (x) (* x x)) naturals)))"
+        ),
+        "(0 1 4)"
+    );
+    assert_eq!(
+        run(
+            &mut ctx,
+            "(stream->list 4 (stream-filter (lambda (x) (= 0 (modulo x 3))) naturals))"
+        ),
+        "(0 3 6 9)"
+    );
+    assert_eq!(
+        run(&mut ctx, "(stream->list (list->stream '(1 2 3)))"),
+        "(1 2 3)"
+    );
+    assert_eq!(run(&mut ctx, "(stream->list (stream))"), "()");
+    assert_eq!(
+        run(
+            &mut ctx,
+            "(list (stream? naturals) (stream-pair? stream-null) (stream-null? (stream)))"
+        ),
+        "(#t #f #t)"
+    );
+
+    // elements are evaluated once, when they are first needed
+    assert_eq!(run(&mut ctx, "evaluated"), "0");
+    assert_eq!(
+        run(&mut ctx, "(list (stream-car s) (stream-car s))"),
+        "(a a)"
+    );
+    assert_eq!(run(&mut ctx, "evaluated"), "1");
+
+    assert!(ctx.run("(stream-car stream-null)").is_err());
+    assert!(ctx.run("(stream-ref (stream 1) 1)").is_err());
+    assert!(ctx.run("(stream-car '(1 2))").is_err());
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn output_ports() {
+    let mut ctx = Context::base();
+    ctx.capture();
+
+    ctx.run(
+        "(define out (open-output-string))
+         (display \"x = \" out)
+         (write \"y\" out)
+         (writeln 1 out)
+         (display 'console (current-output-port))
+         (displayln '!)",
+    )
+    .unwrap();
+    assert_eq!(
+        ctx.run("(get-output-string out)").unwrap(),
+        SExp::from("x = \"y\"1\n")
+    );
+    assert_eq!(ctx.get_output().unwrap(), "console!\n");
+
+    assert_eq!(
+        ctx.run(
+            "(list (port? out) (port? \"out\") (eq? (current-output-port) (current-output-port)))"
+        )
+        .unwrap(),
+        sexp![true, false, true]
+    );
+    assert!(ctx.run("(display 1 2)").is_err());
+    assert!(ctx
+        .run("(get-output-string (current-output-port))")
+        .is_err());
+
+    // `write` gives characters by name, and keeps escapes, so that what it
+    // prints reads back as the same value
+    ctx.capture();
+    ctx.run(r#"(write (list #\newline #\tab #\null #\x41 "a\nb"))"#)
+        .unwrap();
+    let written = ctx.get_output().unwrap();
+    assert_eq!(written, r#"(#\newline #\tab #\null #\A "a\nb")"#);
+    assert_eq!(
+        ctx.run(&format!(
+            "(equal? '{written} (list #\\newline #\\tab #\\null #\\A \"a\\nb\"))"
+        ))
+        .unwrap(),
+        SExp::from(true)
+    );
+    ctx.capture();
+    ctx.run(r#"(display (list #\space #\x41 "a\nb"))"#).unwrap();
+    assert_eq!(ctx.get_output().unwrap(), "(  A a\nb)");
+}
+
+#[test]
+fn aliases_and_deprecation() {
+    let mut ctx = Context::base();
+    ctx.alias("car", "first").unwrap();
+    ctx.deprecate("car", Some("first"));
+    ctx.deprecate("cdr", None);
+
+    // a local binding of the same name is not the deprecated definition
+    assert_eq!(
+        ctx.run("(let ((car 1)) (first '(2 3)))").unwrap(),
+        SExp::from(2)
+    );
+    assert!(ctx.take_warnings().is_empty());
+
+    ctx.run("(cdr (list (car '(1)) (car '(2))))").unwrap();
+    assert_eq!(
+        ctx.take_warnings(),
+        [
+            "`cdr` is deprecated",
+            "`car` is deprecated, use `first` instead"
+        ]
+    );
+    assert_eq!(ctx.run("(fold-left - 10 '(1 2))").unwrap(), SExp::from(7));
+}
+
+#[cfg(feature = "vectors")]
+#[test]
+fn tabulate() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(run("(list-tabulate 4 (lambda (i) (* i i)))"), "(0 1 4 9)");
+    assert_eq!(run("(build-list 3 (lambda (i) (list i)))"), "((0) (1) (2))");
+    assert_eq!(run("(list-tabulate 0 (lambda (i) i))"), "()");
+    assert_eq!(
+        run("(vector-tabulate (lambda (i) (* i 10)) 3)"),
+        "#(0 10 20)"
+    );
+    assert_eq!(run("(vector-tabulate (lambda (i) i) 0)"), "#()");
+
+    // each call runs its own loop through the trampoline
+    assert_eq!(
+        run("(define (count-up i n) (if (= i n) i (count-up (+ i 1) n)))
+             (apply + (list-tabulate 200 (lambda (i) (count-up 0 i))))"),
+        "19900"
+    );
+
+    assert!(ctx.run("(list-tabulate 'x (lambda (i) i))").is_err());
+    assert!(ctx.run("(list-tabulate -5 (lambda (i) i))").is_err());
+    assert!(ctx.run("(list-tabulate 2.5 (lambda (i) i))").is_err());
+    assert!(ctx.run("(vector-tabulate (lambda (i) i) -1)").is_err());
+    assert!(ctx.run("(vector-tabulate 3 (lambda (i) i))").is_err());
+}
+
+#[cfg(feature = "collections")]
+#[test]
+fn heaps_and_deques() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    run("(define h (make-heap))
+         (heap-insert! h 3 'c)
+         (heap-insert! h 1 'a)
+         (heap-insert! h 2.5 'b)
+         (heap-insert! h 1 'a2)
+         (heap-insert! h 0)");
+    assert_eq!(
+        run("(list (heap? h) (heap? '()) (heap-size h))"),
+        "(#t #f 5)"
+    );
+    assert_eq!(run("h"), "#<heap size=5>");
+    assert_eq!(run("(heap->list h)"), "(0 a a2 b c)");
+    assert_eq!(run("(heap-peek h)"), "0");
+    // equal priorities come out in the order they went in
+    assert_eq!(
+        run("(list (heap-pop! h) (heap-pop! h) (heap-pop! h) (heap-pop! h) (heap-pop! h))"),
+        "(0 a a2 b c)"
+    );
+    assert_eq!(run("(heap-empty? h)"), "#t");
+
+    run("(define d (make-deque '(2 3)))
+         (deque-push-front! d 1)
+         (deque-push-back! d 4)");
+    assert_eq!(run("(deque->list d)"), "(1 2 3 4)");
+    assert_eq!(
+        run("(list (deque-front d) (deque-back d) (deque-length d))"),
+        "(1 4 4)"
+    );
+    assert_eq!(
+        run("(list (deque-pop-back! d) (deque-pop-front! d))"),
+        "(4 1)"
+    );
+    assert_eq!(run("(deque->list d)"), "(2 3)");
+    assert_eq!(
+        run("(list (deque? d) (deque? h) (deque-empty? (make-deque)))"),
+        "(#t #f #t)"
+    );
+
+    assert!(ctx.run("(heap-pop! (make-heap))").is_err());
+    assert!(ctx.run("(deque-front (make-deque))").is_err());
+    assert!(ctx.run("(heap-insert! (make-heap) 'x)").is_err());
+    assert!(ctx.run("(deque-push-back! (make-heap) 1)").is_err());
+}
+
+#[cfg(feature = "collections")]
+#[test]
+fn sets() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    run("(define s (list->set '(1 (2 3) \"a\" 1 (2 3))))");
+    assert_eq!(run("s"), "#<set size=3>");
+    assert_eq!(run("(set->list s)"), "(1 (2 3) a)");
+    // membership uses equal?, so a fresh list is found
+    assert_eq!(
+        run("(list (set-member? s (list 2 3)) (set-member? s 2) (set? s) (set? '()))"),
+        "(#t #f #t #f)"
+    );
+
+    run("(set-add! s 4) (set-add! s 1) (set-remove! s \"a\")");
+    assert_eq!(run("(set->list s)"), "(1 (2 3) 4)");
+
+    run("(define t (set 4 5 1))");
+    assert_eq!(run("(set->list (set-union s t))"), "(1 (2 3) 4 5)");
+    assert_eq!(run("(set->list (set-intersection s t))"), "(1 4)");
+    assert_eq!(run("(set->list (set-difference s t (set '(2 3))))"), "()");
+    assert_eq!(
+        run("(list (set-subset? (set 1 4) s) (set-subset? t s) (set=? (set 1 4) (set 4 1)))"),
+        "(#t #f #t)"
+    );
+    assert_eq!(run("(list (set-size (set)) (set-empty? (set)))"), "(0 #t)");
+    // combining a single set still gives a new one
+    assert_eq!(run("(eq? (set-union s) s)"), "#f");
+
+    assert!(ctx.run("(set-add! '(1) 2)").is_err());
+    assert!(ctx.run("(set-union (set) '())").is_err());
+}
+
+#[cfg(all(feature = "collections", feature = "vectors"))]
+#[test]
+fn iteration_with_modification() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    run("(define seen '())
+         (define (see x) (set! seen (cons x seen)))
+         (set-for-each see (set 1 2 3))
+         (deque-for-each see (make-deque '(4 5)))");
+    assert_eq!(run("seen"), "(5 4 3 2 1)");
+
+    // looking at the collection, or changing another one, is fine
+    run("(define s (set 1 2 3))
+         (define t (set))
+         (set-for-each (lambda (x) (set-member? s x) (set-add! t (* x 10))) s)");
+    assert_eq!(run("(set->list t)"), "(10 20 30)");
+    // as is removing something which isn't there
+    run("(set-for-each (lambda (x) (set-remove! s 'absent)) s)");
+
+    run("(define d (make-deque '(1 2 3)))");
+    assert!(matches!(
+        ctx.run("(set-for-each (lambda (x) (set-add! s (+ x 1))) s)"),
+        Err(Error::Modified { kind: "set" })
+    ));
+    assert!(matches!(
+        ctx.run("(deque-for-each (lambda (x) (deque-pop-front! d)) d)"),
+        Err(Error::Modified { kind: "deque" })
+    ));
+    // the error comes after the call which made the change
+    assert_eq!(ctx.run("(set->list s)").unwrap().to_string(), "(1 2 3 4)");
+    assert_eq!(ctx.run("(deque->list d)").unwrap().to_string(), "(2 3)");
+    assert_eq!(
+        ctx.run("(guard (e (#t (error-object-message e))) (deque-for-each (lambda (x) (deque-push-back! d x)) d))")
+            .unwrap()
+            .to_string(),
+        "The deque was modified while iterating over it."
+    );
+    assert!(ctx.run("(set-for-each car d)").is_err());
+
+    // vectors are values, so changing one only rebinds its variable, and
+    // mapping carries on over the vector it started with
+    assert_eq!(
+        ctx.run(
+            "(define v (vector-tabulate (lambda (i) (+ i 1)) 3))
+                 (vector-map (lambda (x) (vector-set! v 2 0) (* x 10)) v)"
+        )
+        .unwrap()
+        .to_string(),
+        "#(10 20 30)"
+    );
+    assert_eq!(ctx.run("v").unwrap().to_string(), "#(1 2 0)");
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn graphs() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    run("(define g '((shirt tie belt) (tie jacket) (pants shoes belt) (belt jacket) (socks shoes)))");
+    assert_eq!(
+        run("(topological-sort g)"),
+        "(shirt pants socks tie belt shoes jacket)"
+    );
+    assert_eq!(run("(topological-sort '((a b) (b c) (c a)))"), "#f");
+
+    assert_eq!(
+        run("(shortest-path g 'shirt 'jacket)"),
+        "(shirt tie jacket)"
+    );
+    assert_eq!(run("(shortest-path g 'socks 'socks)"), "(socks)");
+    assert_eq!(run("(shortest-path g 'jacket 'shirt)"), "#f");
+    assert_eq!(run("(shortest-path g 'shirt 'hat)"), "#f");
+    // nodes are compared by value, not identity
+    assert_eq!(
+        run("(shortest-path '(((0 0) (0 1)) ((0 1) (1 1))) (list 0 0) (list 1 1))"),
+        "((0 0) (0 1) (1 1))"
+    );
+
+    assert_eq!(
+        run("(connected-components '((a b) (c d) (e) (d b) (f g)))"),
+        "((a b c d) (e) (f g))"
+    );
+
+    assert!(ctx.run("(topological-sort '(a b))").is_err());
+}
+
+#[test]
+fn base_contexts_are_independent() {
+    let mut first = Context::base();
+    first.lang.insert("car".to_string(), SExp::from(1));
+    first.lang.remove("cdr");
+    first.run("(define x 2)").unwrap();
+
+    let mut second = Context::base();
+    assert_eq!(second.run("(car '(3 4))").unwrap(), SExp::from(3));
+    assert_eq!(second.run("(cdr '(3 4))").unwrap(), sexp![4]);
+    assert!(second.get("x").is_none());
+    assert_eq!(first.get("car"), Some(SExp::from(1)));
+    assert!(first.get("cdr").is_none());
+}
+
+#[test]
+fn mixed_comparisons() {
+    let mut ctx = Context::base();
+    assert_eq!(
+        ctx.run("(list (< 2.5 1) (< 1 2.5) (> 3 2.5) (> 2.5 3) (< -1.5 -1))")
+            .unwrap(),
+        ctx.run("'(#f #t #t #f #t)").unwrap()
+    );
+}
+
+#[test]
+fn exactness() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run("(list (exact? 2) (exact? 2.0) (inexact? 2.5) (inexact? -7))"),
+        "(#t #f #t #f)"
+    );
+    assert_eq!(
+        run("(list (exact 3.0) (exact? (inexact->exact -4.0)) (inexact? (exact->inexact 5)))"),
+        "(3 #t #t)"
+    );
+    assert_eq!(run("(exact? (inexact 5))"), "#f");
+    assert_eq!(run("(exact 12)"), "12");
+
+    // = compares values, but eqv? also compares exactness
+    assert_eq!(
+        run("(list (= 2 2.0) (eqv? 2 2.0) (eqv? 2 2) (eqv? 2.0 2.0))"),
+        "(#t #f #t #t)"
+    );
+    assert_eq!(
+        run("(list (eq? 2 2.0) (equal? 2 2.0) (equal? '(1 (2)) '(1 (2.0))) (equal? '(1 (2)) '(1 (2))))"),
+        "(#f #f #f #t)"
+    );
+    assert_eq!(run("(case 2.0 ((2) 'exact) (else 'inexact))"), "inexact");
+
+    // there are no exact fractions, so only an integer can be made exact
+    assert!(ctx.run("(inexact->exact 2.5)").is_err());
+    assert!(ctx.run("(exact -1.5)").is_err());
+    assert!(ctx.run("(inexact->exact (/ 1.0 0))").is_err());
+    assert!(ctx.run("(exact? 'x)").is_err());
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn bignums() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    run("(define (fact n) (if (= n 0) 1 (* n (fact (- n 1)))))
+         (define (fib n) (let loop ((a 0) (b 1) (n n)) (if (= n 0) a (loop b (+ a b) (- n 1)))))");
+    assert_eq!(run("(fact 30)"), "265252859812191058636308480000000");
+    assert_eq!(run("(fib 100)"), "354224848179261915075");
+    assert_eq!(run("(exact 1e20)"), "100000000000000000000");
+    assert_eq!(run("#x10000000000000000"), "18446744073709551616");
+    assert_eq!(
+        run("(number->string (- #x10000000000000000) 16)"),
+        "-10000000000000000"
+    );
+
+    // results are exact, and become small again when they fit
+    assert_eq!(run("(/ (fact 30) (fact 28))"), "870");
+    assert_eq!(
+        run("(- (+ 9223372036854775807 1) 1)"),
+        "9223372036854775807"
+    );
+    assert_eq!(run("(- -9223372036854775807 10)"), "-9223372036854775817");
+    assert_eq!(
+        run("(list (- (expt 10 30)) (- -9223372036854775808))"),
+        "(-1000000000000000000000000000000 9223372036854775808)"
+    );
+    assert_eq!(run("(* -1 (pow 2 64))"), "-18446744073709551616");
+    assert_eq!(run("(abs (- 0 (pow 10 20)))"), "100000000000000000000");
+    assert_eq!(run("(modulo (- 0 (pow 10 20)) 7)"), "5");
+    assert_eq!(run("(remainder (- 0 (pow 10 20)) 7)"), "-2");
+    assert_eq!(run("(/ (pow 10 20) (pow 10 18))"), "100");
+    assert_eq!(
+        run("123456789012345678901234567890"),
+        "123456789012345678901234567890"
+    );
+
+    // comparisons work across sizes
+    assert_eq!(
+        run("(list (= (pow 2 70) (* (pow 2 35) (pow 2 35))) (< (pow 2 70) (pow 2 71)) (> (pow 2 70) 5) (< (- 0 (pow 2 70)) 5))"),
+        "(#t #t #t #t)"
+    );
+
+    assert_eq!(run("(gcd (pow 2 80) (pow 6 40))"), "1099511627776");
+    assert_eq!(run("(lcm (pow 2 70) 3)"), "3541774862152233910272");
+    assert_eq!(run("(sqrt (expt 3 100))"), "717897987691852588770249");
+    assert_eq!(
+        run("(list (expt 1 10000000000) (expt -1 10000000001) (expt 2 -1))"),
+        "(1 -1 0.5)"
+    );
+    assert!(matches!(
+        Context::base().run("(expt 2 1000000000)"),
+        Err(Error::Type { .. })
+    ));
+    assert_eq!(
+        run("(call-with-values (lambda () (exact-integer-sqrt (+ (pow 2 80) 5))) list)"),
+        "(1099511627776 5)"
+    );
+
+    // mixing with inexact numbers gives an inexact result
+    assert_eq!(run("(+ (pow 10 20) 0.5)"), "100000000000000000000.0");
+    assert_eq!(run("(/ (+ (pow 10 20) 1) 2)"), "50000000000000000000.0");
+    assert!(ctx.run("(modulo (pow 10 20) 0)").is_err());
+}
+
+#[test]
+fn exit_and_restart() {
+    let mut ctx = Context::base();
+    let mut control = |code| ReplControl::of(&ctx.run(code));
+
+    assert_eq!(control("(exit)"), ReplControl::Exit(0));
+    assert_eq!(control("(exit #t)"), ReplControl::Exit(0));
+    assert_eq!(control("(exit #f)"), ReplControl::Exit(1));
+    assert_eq!(control("(exit 42)"), ReplControl::Exit(42));
+    assert_eq!(control("(restart)"), ReplControl::Restart);
+    assert_eq!(control("(exit \"no\")"), ReplControl::Continue);
+    assert_eq!(control("(+ 1 2)"), ReplControl::Continue);
+
+    // handlers can't stop it, but the way out of a `dynamic-wind` still runs
+    assert_eq!(
+        control("(guard (e (#t 'caught)) (exit 2))"),
+        ReplControl::Exit(2)
+    );
+    assert_eq!(
+        control("(with-exception-handler (lambda (e) 0) (lambda () (restart)))"),
+        ReplControl::Restart
+    );
+    ctx.run("(define left #f)").unwrap();
+    assert!(ctx
+        .run("(dynamic-wind (lambda () #f) (lambda () (exit 3) 'after) (lambda () (set! left #t)))")
+        .is_err());
+    assert_eq!(ctx.run("left").unwrap(), SExp::from(true));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn output_limit() {
+    let mut ctx = Context::base().capturing();
+    ctx.set_output_limit(Some(4));
+
+    // handlers can't catch it and carry on writing
+    assert!(matches!(
+        ctx.run("(let loop () (guard (e (#t (loop))) (display \"ab\") (loop)))"),
+        Err(Error::OutputLimit(4))
+    ));
+    assert_eq!(ctx.get_output().unwrap(), "abab");
+
+    // writing to a string port doesn't count
+    ctx.capture();
+    assert_eq!(
+        ctx.run("(define p (open-output-string)) (display \"abcdef\" p) (get-output-string p)")
+            .unwrap(),
+        SExp::from("abcdef")
+    );
+
+    ctx.set_output_limit(None);
+    assert!(ctx.run("(display \"abcdef\")").is_ok());
+    assert_eq!(ctx.get_output().unwrap(), "abcdef");
+}
+
+#[test]
+fn min_max_gcd_lcm() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run("(list (min 3 1 2) (max 3 1 2) (min 4) (max -1 -5))"),
+        "(1 3 4 -1)"
+    );
+    assert_eq!(
+        run("(list (min 1.5 2) (max 1 2.5) (max -0.5 -3))"),
+        "(1.5 2.5 -0.5)"
+    );
+
+    // an inexact argument makes the result inexact, even if it isn't picked
+    assert_eq!(
+        run("(list (exact? (max 3 1.0)) (exact? (min 1 2)))"),
+        "(#f #t)"
+    );
+    assert_eq!(run("(let ((x (max 1 (/ 0. 0.) 2))) (= x x))"), "#f");
+
+    assert_eq!(
+        run("(list (gcd) (gcd 12) (gcd 12 18) (gcd -12 18 27) (gcd 0 5))"),
+        "(0 12 6 3 5)"
+    );
+    assert_eq!(
+        run("(list (lcm) (lcm 4) (lcm 4 6) (lcm -4 6 10) (lcm 0 5))"),
+        "(1 4 12 60 0)"
+    );
+    assert_eq!(
+        run("(list (gcd 12.0 18) (exact? (gcd 12.0 18)) (lcm 4 6.0) (exact? (lcm 4 6.0)))"),
+        "(6.0 #f 12.0 #f)"
+    );
+
+    assert!(ctx.run("(min)").is_err());
+    assert!(ctx.run("(max 1 'a)").is_err());
+    assert!(ctx.run("(gcd 1.5 3)").is_err());
+    assert!(ctx.run("(lcm 2 \"4\")").is_err());
+}
+
+#[test]
+fn import_values() {
+    let mut source = Context::base();
+    let mut target = Context::base();
+
+    // closures sharing a scope, and closing over themselves, still do
+    let pair = source
+        .run(
+            "(define (make-pair)
+               (define n 0)
+               (define (count) (if (< n 3) (begin (set! n (+ n 1)) (count)) n))
+               (list count (lambda () n)))
+             (make-pair)",
+        )
+        .unwrap();
+    let pair = target.import_value(pair).unwrap();
+    target.define("pair", pair);
+    assert_eq!(
+        target
+            .run("(list ((car pair)) ((car (cdr pair))))")
+            .unwrap(),
+        sexp![3, 3]
+    );
+
+    // macros are rebound too
+    let swap = source
+        .run("(define-syntax swap! (syntax-rules () ((_ a b) (let ((t a)) (set! a b) (set! b t))))) swap!")
+        .unwrap();
+    let swap = target.import_value(swap).unwrap();
+    target.define("swap!", swap);
+    target.run("(define x 1) (define y 2) (swap! x y)").unwrap();
+    assert_eq!(target.run("(list x y)").unwrap(), sexp![2, 1]);
+
+    // mutable values are copied, not shared
+    #[cfg(feature = "collections")]
+    {
+        let sets = source.run("(define s (set 1 2)) (list s)").unwrap();
+        let sets = target.import_value(sets).unwrap();
+        target.define("sets", sets);
+        target.run("(set-add! (car sets) 3)").unwrap();
+        assert_eq!(source.run("(set-size s)").unwrap(), SExp::from(2));
+        assert_eq!(target.run("(set-size (car sets))").unwrap(), SExp::from(3));
+    }
+
+    let param = source.run("(list 1 (make-parameter 2))").unwrap();
+    assert!(target.import_value(param).is_err());
+}
+
+#[test]
+fn rounding() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run("(list (floor -4.3) (ceiling -4.3) (truncate -4.3) (round -4.3))"),
+        "(-5.0 -4.0 -4.0 -4.0)"
+    );
+    assert_eq!(
+        run("(list (floor 3.5) (ceiling 3.5) (truncate 3.5) (round 3.5))"),
+        "(3.0 4.0 3.0 4.0)"
+    );
+    assert_eq!(
+        run("(list (floor 7) (round 7) (exact? (round 7)))"),
+        "(7 7 #t)"
+    );
+
+    // halfway cases round to even, and inexact numbers stay inexact
+    assert_eq!(
+        run("(list (round 2.5) (round -2.5) (round 0.5) (round 1.5))"),
+        "(2.0 -2.0 0.0 2.0)"
+    );
+    assert_eq!(
+        run("(list (exact? (floor 2.5)) (exact? (round 1e300)))"),
+        "(#f #f)"
+    );
+    assert_eq!(run("(= (round 1e300) 1e300)"), "#t");
+
+    assert_eq!(
+        run("(list (numerator 6) (denominator 6) (numerator -4) (denominator 0))"),
+        "(6 1 -4 1)"
+    );
+    assert_eq!(
+        run("(list (numerator 0.75) (denominator 0.75) (denominator -1.5))"),
+        "(3.0 4.0 2.0)"
+    );
+    assert_eq!(
+        run("(list (exact? (denominator 0.75)) (exact? (denominator 4)))"),
+        "(#f #t)"
+    );
+
+    assert!(ctx.run("(round 'x)").is_err());
+}
+
+#[test]
+fn numeric_predicates() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run("(list (number? 1) (number? 2.5) (number? 'x) (number? \"1\") (number? '()))"),
+        "(#t #t #f #f #f)"
+    );
+    assert_eq!(run("(list (real? 1.5) (real? #t))"), "(#t #f)");
+    assert_eq!(
+        run("(list (integer? 3) (integer? 3.0) (integer? 3.5) (integer? 'x))"),
+        "(#t #t #f #f)"
+    );
+    assert_eq!(
+        run("(list (rational? 1.5) (rational? +inf.0) (rational? \"x\"))"),
+        "(#t #f #f)"
+    );
+
+    assert_eq!(
+        run("(list (positive? 2) (positive? 0) (positive? 0.0) (positive? -1.5))"),
+        "(#t #f #f #f)"
+    );
+    assert_eq!(
+        run("(list (negative? -2) (negative? 0) (negative? -0.0) (negative? 1.5))"),
+        "(#t #f #f #f)"
+    );
+
+    assert_eq!(
+        run("(list (even? 0) (even? -4) (even? 3) (even? 6.0))"),
+        "(#t #t #f #t)"
+    );
+    assert_eq!(run("(list (odd? 3) (odd? -3) (odd? 4))"), "(#t #t #f)");
+    assert_eq!(
+        run("(list (nan? +nan.0) (nan? +inf.0) (nan? 1) (nan? 1.5))"),
+        "(#t #f #f #f)"
+    );
+    assert_eq!(
+        run("(list (finite? 1) (finite? 1.5) (finite? -inf.0) (finite? +nan.0))"),
+        "(#t #t #f #f)"
+    );
+    assert_eq!(
+        run("(list (infinite? +inf.0) (infinite? -inf.0) (infinite? +nan.0) (infinite? 7))"),
+        "(#t #t #f #f)"
+    );
+    assert_eq!(
+        run("(list (positive? +nan.0) (negative? +nan.0) (positive? +inf.0))"),
+        "(#f #f #t)"
+    );
+    assert_eq!(run("(rational? -inf.0)"), "#f");
+    assert!(ctx.run("(even? 1.5)").is_err());
+    assert!(ctx.run("(odd? 'x)").is_err());
+    assert!(ctx.run("(nan? 'x)").is_err());
+}
+
+#[test]
+fn transcendental() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(run("(list (sin 0) (cos 0) (tan 0))"), "(0.0 1.0 0.0)");
+    assert_eq!(
+        run("(list (asin 1) (acos 1) (atan 1))"),
+        "(1.5707963267948966 0.0 0.7853981633974483)"
+    );
+    assert_eq!(
+        run("(list (atan 1 -1) (atan -1 0))"),
+        "(2.356194490192345 -1.5707963267948966)"
+    );
+    assert_eq!(
+        run("(list (exp 0) (log 1) (log 100 10) (log 8 2))"),
+        "(1.0 0.0 2.0 3.0)"
+    );
+    assert_eq!(run("(exact? (exp 0))"), "#f");
+
+    assert_eq!(
+        run("(list (sqrt 16) (exact? (sqrt 16)) (sqrt 2.25))"),
+        "(4 #t 1.5)"
+    );
+    assert_eq!(
+        run("(list (sqrt 2) (exact? (sqrt 2)) (exact? (sqrt 16.0)))"),
+        "(1.4142135623730951 #f #f)"
+    );
+    assert_eq!(
+        run("(list (expt 2 10) (expt 2.0 3) (expt 4 0.5) (expt 2 -1))"),
+        "(1024 8.0 2.0 0.5)"
+    );
+    assert!(matches!(
+        Context::base().run("(expt 0 -1)"),
+        Err(Error::DivisionByZero)
+    ));
+    assert_eq!(
+        run("(list (expt 0 0) (expt 0 3) (expt 0.0 -1))"),
+        "(1 0 +inf.0)"
+    );
+
+    assert_eq!(
+        run("(call-with-values (lambda () (exact-integer-sqrt 17)) list)"),
+        "(4 1)"
+    );
+    assert_eq!(
+        run("(call-with-values (lambda () (exact-integer-sqrt 0)) list)"),
+        "(0 0)"
+    );
+    assert!(ctx.run("(exact-integer-sqrt -1)").is_err());
+    assert!(ctx.run("(exact-integer-sqrt 4.0)").is_err());
+    assert!(ctx.run("(atan 'x)").is_err());
+    assert!(ctx.run("(log 1 2 3)").is_err());
+}
+
+#[test]
+fn weak_refs_and_resources() {
+    use std::cell::Cell;
+
+    let closed = Rc::new(Cell::new(0));
+    let resource = |kind| {
+        let closed = Rc::clone(&closed);
+        Resource::with_finalizer(kind, (), move |()| closed.set(closed.get() + 1))
+    };
+
+    let mut ctx = Context::base();
+    ctx.define("file", resource("file").into());
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(run("file"), "#<resource:file>");
+    assert_eq!(
+        run("(list (resource? file) (resource? 5) (resource-closed? file))"),
+        "(#t #f #f)"
+    );
+
+    // values without an identity of their own are kept
+    assert_eq!(run("(weak-ref-deref (make-weak-ref '(1 2)))"), "(1 2)");
+    assert_eq!(
+        run("(let ((r (make-weak-ref file))) (list (weak-ref? r) (weak-ref? file) (eq? (weak-ref-deref r) file)))"),
+        "(#t #f #t)"
+    );
+
+    // a dropped target is gone, and a resource is closed once it is dropped
+    run("(define r (make-weak-ref file)) (define e (make-weak-ref (make-environment)))");
+    assert_eq!(run("(weak-ref-deref e)"), "#f");
+    run("(set! file #f)");
+    assert_eq!(run("(weak-ref-deref r)"), "#f");
+    assert_eq!(closed.get(), 1);
+
+    // closing one is only done once
+    ctx.define("db", resource("db").into());
+    let mut run = |code| ctx.run(code).unwrap().to_string();
+    run("(close-resource db) (close-resource db)");
+    assert_eq!(
+        run("(list db (resource-closed? db))"),
+        "(#<resource:db (closed)> #t)"
+    );
+    assert_eq!(closed.get(), 2);
+    assert!(ctx.run("(close-resource 5)").is_err());
+    assert!(ctx.run("(weak-ref-deref 5)").is_err());
+
+    // registered resources are closed with the context, even when they are
+    // still referenced
+    let kept = resource("kept");
+    let unregistered = resource("unregistered");
+    ctx.register_resource(&kept);
+    ctx.define("kept", kept.clone().into());
+    ctx.define("unregistered", unregistered.clone().into());
+    drop(ctx);
+    assert!(kept.is_closed());
+    assert!(!unregistered.is_closed());
+    assert_eq!(closed.get(), 3);
+}
+
+#[test]
+fn cleanup_on_drop() {
+    use std::cell::{Cell, RefCell};
+
+    // a mock resource which counts how many are open, and records the order
+    // they are closed in
+    let open = Rc::new(Cell::new(0));
+    let closed = Rc::new(RefCell::new(Vec::new()));
+    let resource = |name: &'static str| {
+        open.set(open.get() + 1);
+        let (open, closed) = (Rc::clone(&open), Rc::clone(&closed));
+        Resource::with_finalizer("mock", name, move |name| {
+            open.set(open.get() - 1);
+            closed.borrow_mut().push(name);
+        })
+    };
+
+    let mut ctx = Context::base();
+    let hooks_saw_open = Rc::new(RefCell::new(Vec::new()));
+    for _ in 0..2 {
+        let (open, seen) = (Rc::clone(&open), Rc::clone(&hooks_saw_open));
+        ctx.on_drop(move || seen.borrow_mut().push(open.get()));
+    }
+
+    let (first, second, third) = (resource("first"), resource("second"), resource("third"));
+    for r in [&first, &second, &third] {
+        ctx.register_resource(r);
+    }
+    ctx.define("first", first.into());
+    ctx.define("second", second.into());
+    ctx.run("(define (get-second) second) (close-resource first)")
+        .unwrap();
+    assert_eq!(open.get(), 2);
+    assert_eq!(*closed.borrow(), ["first"]);
+
+    // one is still referenced by the host, and one by a closure in the
+    // context, but both are closed, latest first, before the hooks run
+    drop(ctx);
+    assert_eq!(open.get(), 0);
+    assert_eq!(*closed.borrow(), ["first", "third", "second"]);
+    assert_eq!(*hooks_saw_open.borrow(), [0, 0]);
+    assert!(third.is_closed());
+
+    // replacing a context drops it too
+    let mut ctx = Context::base();
+    let fourth = resource("fourth");
+    ctx.register_resource(&fourth);
+    ctx.define("fourth", fourth.into());
+    ctx.run("(define (get-fourth) fourth)").unwrap();
+    assert_eq!(open.get(), 1);
+    let dropped = Rc::new(Cell::new(false));
+    let dropped_by_hook = Rc::clone(&dropped);
+    ctx.on_drop(move || dropped_by_hook.set(true));
+    ctx = Context::base();
+    assert!(dropped.get());
+    assert_eq!(open.get(), 0);
+    assert!(ctx.run("(+ 1 2)").is_ok());
+}
+
+#[test]
+fn float_printing() {
+    let mut ctx = Context::base();
+    let mut run = |code: &str| ctx.run(code).unwrap().to_string();
+
+    // inexact integers keep their decimal point, so they read back the same
+    assert_eq!(
+        run("(list 1.0 -0.0 2.5 1e3 (exact->inexact 7))"),
+        "(1.0 -0.0 2.5 1000.0 7.0)"
+    );
+    assert_eq!(
+        run("(list (/ 1.0 0) (/ -1.0 0) (- (/ 1.0 0) (/ 1.0 0)))"),
+        "(+inf.0 -inf.0 +nan.0)"
+    );
+    assert_eq!(
+        run("(list +inf.0 -inf.0 +nan.0 -nan.0)"),
+        "(+inf.0 -inf.0 +nan.0 +nan.0)"
+    );
+    assert_eq!(run("(list (exact? 1.0) (exact? +inf.0))"), "(#f #f)");
+    assert_eq!(run("(= 1e300 (car '(1e300)))"), "#t");
+    for code in [
+        "1.0",
+        "-0.0",
+        "0.1",
+        "1e300",
+        "123456.789",
+        "+inf.0",
+        "-inf.0",
+        "+nan.0",
+    ] {
+        let printed = run(code);
+        assert_eq!(run(&printed), printed);
+    }
+
+    assert_eq!(run("(number->string 2.0)"), "2.0");
+    assert_eq!(run("(number->string 2.0 10 'fixed)"), "2.0");
+    assert_eq!(run("(number->string +inf.0 10 'scientific)"), "+inf.0");
+
+    // the names Rust would read as special values are just symbols
+    assert_eq!(run("(list 'inf 'nan)"), "(inf nan)");
+    assert!(ctx.run("inf").is_err());
+}
+
+#[cfg(all(feature = "strings", feature = "math-extra"))]
+#[test]
+fn import_libraries() {
+    let mut ctx = Context::base();
+    assert!(ctx.run("(length '(1 2 3))").is_err());
+    assert!(ctx
+        .run("(import (parsley nonexistent) (parsley list))")
+        .is_err());
+    // nothing is loaded unless every library can be
+    assert!(ctx.run("(length '(1 2 3))").is_err());
+
+    let mut run = |code: &str| ctx.run(code).unwrap().to_string();
+
+    assert_eq!(
+        run("(available-libraries)"),
+        "((parsley list) (parsley string) (parsley math))"
+    );
+
+    // libraries are defined globally, wherever they are imported
+    run("(define (load) (import (parsley list) (parsley string)) 'loaded)");
+    assert_eq!(run("(load)"), "loaded");
+    assert_eq!(run("(length (iota 5))"), "5");
+    assert_eq!(run("(reverse (list 1 2 3))"), "(3 2 1)");
+    assert_eq!(
+        run("(append (list 1) (list) (list 2 3) (list 4))"),
+        "(1 2 3 4)"
+    );
+    assert_eq!(
+        run("(list (list-ref (list 1 2 3) 1) (last (list 1 2 3)))"),
+        "(2 3)"
+    );
+    assert_eq!(run("(iota 4 1 2)"), "(1 3 5 7)");
+    assert_eq!(run("(member 2 (list 1 2 3))"), "(2 3)");
+    assert_eq!(run("(assv 2 (list (cons 1 10) (cons 2 20)))"), "(2 . 20)");
+    assert_eq!(run("(fold-right cons (list) (list 1 2 3))"), "(1 2 3)");
+    assert_eq!(
+        run("(list (any even? (list 1 3)) (every odd? (list 1 3)))"),
+        "(#f #t)"
+    );
+    assert_eq!(run("(delete-duplicates (list 1 2 1 3 2))"), "(1 2 3)");
+    assert_eq!(
+        run("(call-with-values (lambda () (partition even? (iota 5))) list)"),
+        "((0 2 4) (1 3))"
+    );
+
+    assert_eq!(
+        run(r#"(list (string-null? "") (string-null? "a"))"#),
+        "(#t #f)"
+    );
+    assert_eq!(
+        run(
+            r#"(list (string-prefix? "he" "hello") (string-suffix? "lo" "hello") (string-prefix? "hello!" "hello"))"#
+        ),
+        "(#t #t #f)"
+    );
+
+    // importing again doesn't replace anything
+    run("(define (length lst) 'mine)");
+    run("(import (parsley list))");
+    assert_eq!(run("(length (list 1))"), "mine");
+
+    run("(import (parsley math))");
+    assert_eq!(run("(hypot 3 4)"), "5.0");
+}
@@ -86,7 +86,7 @@ fn vector_len(v: SExp) -> Result<SExp, Error> {
 fn vector_ref(v: SExp, i: SExp) -> Result<SExp, Error> {
     match (v, i) {
         (Atom(Vector(vec)), Atom(Number(n))) => vec
-            .get(usize::from(n))
+            .get(usize::from(n.clone()))
             .map(ToOwned::to_owned)
             .ok_or(Error::Index { i: n.into() }),
         (Atom(Vector(_)), i) => Err(Error::Type {
@@ -134,14 +134,17 @@ fn vector_set(ctx: &mut Context, expr: SExp) -> Result<SExp, Error> {
             expected: "vector",
             given: val.type_of().to_string(),
         }),
-        None => Err(Error::UndefinedSymbol { sym }),
+        None => Err(Error::undefined(sym)),
     }
 }
 
+/// `(vector-map proc vector)`: a vector of the results of calling `proc` with
+/// each element. Vectors are values, so if `proc` changes the vector with
+/// `vector-set!`, it is called with the elements from before the change.
 fn vector_map(ctx: &mut Context, expr: SExp) -> Result<SExp, Error> {
     let (proc, tail) = expr.split_car()?;
 
-    let vec = match tail.car()? {
+    let vec = match ctx.eval(tail.car()?)? {
         Atom(Vector(v)) => v,
         e => {
             return Err(Error::Type {
@@ -228,6 +231,13 @@ fn vector_tail(v: SExp, start: SExp) -> Result<SExp, Error> {
     }
 }
 
+/// `(vector-tabulate proc n)`: a vector of the results of calling `proc` with
+/// each index from 0 to `n - 1`.
+fn vector_tabulate(ctx: &mut Context, expr: SExp) -> Result<SExp, Error> {
+    let (proc, rest) = ctx.eval_args(expr)?.split_car()?;
+    Ok(Atom(Vector(ctx.tabulate(rest.car()?, &proc)?)))
+}
+
 impl Context {
     pub(super) fn vector(&mut self) {
         define!(self, "make-vector", make_vector, (1, 2));
@@ -237,6 +247,7 @@ impl Context {
         define_with!(self, "vector-ref", vector_ref, make_binary_expr);
         define_ctx!(self, "vector-set!", vector_set, 3);
         define_ctx!(self, "vector-map", vector_map, 2);
+        define_ctx!(self, "vector-tabulate", vector_tabulate, 2);
         define_with!(self, "subvector", subvector, make_ternary_expr);
         define_with!(self, "vector-head", vector_head, make_binary_expr);
         define_with!(self, "vector-tail", vector_tail, make_binary_expr);
@@ -0,0 +1,139 @@
+//! A small object system in the style of CLOS, built from record types and
+//! generic procedures. Enabled by the `clos` feature.
+
+use std::rc::Rc;
+
+use super::super::Primitive::{Symbol, Undefined};
+use super::super::SExp::{self, Atom, Null, Pair};
+use super::super::{Error, RecordType, Result};
+use super::core::symbol_name;
+use super::Context;
+
+/// A class declared by `define-class`.
+pub(super) struct Class {
+    rtd: Rc<RecordType>,
+    /// The accessor (a generic procedure) for each slot, including inherited
+    /// ones, with the name of the slot.
+    accessors: Vec<(String, String)>,
+}
+
+/// The type tag of a class, which is its name without any angle brackets.
+fn class_tag(name: &str) -> String {
+    name.strip_prefix('<')
+        .and_then(|n| n.strip_suffix('>'))
+        .unwrap_or(name)
+        .to_string()
+}
+
+/// The name of the setter for the slot with the given accessor.
+fn setter_name(accessor: &str) -> String {
+    format!("set-{accessor}!")
+}
+
+impl Context {
+    fn class(&self, tag: &str) -> std::result::Result<&Class, Error> {
+        self.classes
+            .get(tag)
+            .ok_or_else(|| Error::undefined(format!("<{tag}>")))
+    }
+
+    /// `(define-class <name> (superclass) (slot...))`: define a class, which
+    /// has the slots of its superclass (if any) followed by its own. Each slot
+    /// gets an accessor named `name-slot` and a setter named `set-name-slot!`,
+    /// after the class which declared it, and those of inherited slots also
+    /// apply to the new class.
+    pub(super) fn eval_define_class(&mut self, expr: SExp) -> Result {
+        let (name, rest) = expr.split_car()?;
+        let (supers, rest) = rest.split_car()?;
+        let name = symbol_name(name)?;
+        let tag = class_tag(&name);
+
+        let parent = match supers {
+            Null => None,
+            Pair { head, tail } if *tail == Null => Some(class_tag(&symbol_name(*head)?)),
+            other => {
+                return Err(Error::Type {
+                    expected: "at most one superclass",
+                    given: other.type_of().to_string(),
+                })
+            }
+        };
+
+        let mut accessors = match &parent {
+            Some(parent) => self.class(parent)?.accessors.clone(),
+            None => Vec::new(),
+        };
+        for slot in rest.car()? {
+            let slot = symbol_name(slot)?;
+            let accessor = format!("{tag}-{slot}");
+            self.eval_define_generic(Null.cons(SExp::sym(&accessor)))?;
+            self.eval_define_generic(Null.cons(SExp::sym(&setter_name(&accessor))))?;
+            accessors.push((accessor, slot));
+        }
+
+        let rtd = RecordType::new(
+            &tag,
+            accessors.iter().map(|(_, slot)| slot.clone()).collect(),
+        );
+        for (accessor, slot) in &accessors {
+            let setter = setter_name(accessor);
+            let methods = [
+                (accessor.clone(), rtd.accessor(accessor, slot)?),
+                (setter.clone(), rtd.modifier(&setter, slot)?),
+            ];
+            for (generic, method) in methods {
+                if let Some(methods) = self.generics.get_mut(&generic) {
+                    methods.insert(tag.clone(), method.into());
+                }
+            }
+        }
+
+        match parent {
+            Some(parent) => self.supertypes.insert(tag.clone(), parent),
+            None => self.supertypes.remove(&tag),
+        };
+        self.classes.insert(tag.clone(), Class { rtd, accessors });
+        self.define(&name, Atom(Symbol(tag)));
+
+        Ok(Atom(Undefined))
+    }
+
+    /// `(make <class> 'slot value ...)`: make an instance of a class. Slots
+    /// which are not given are left undefined.
+    pub(super) fn make_instance(&mut self, expr: SExp) -> Result {
+        let (class, initargs) = self.eval_args(expr)?.split_car()?;
+        let tag = symbol_name(class)?;
+
+        let mut slots = Vec::new();
+        let mut values = Vec::new();
+        let mut initargs = initargs.into_iter();
+        while let Some(slot) = initargs.next() {
+            slots.push(symbol_name(slot)?);
+            values.push(initargs.next().ok_or(Error::ArityMin {
+                expected: slots.len() * 2 + 1,
+                given: slots.len() * 2,
+            })?);
+        }
+
+        let constructor = self.class(&tag)?.rtd.constructor("make", &slots)?;
+        constructor.apply(values.into_iter().collect(), self)
+    }
+
+    /// `(is-a? obj <class>)`: whether `obj` is an instance of the class or
+    /// of one of its subclasses.
+    pub(super) fn is_a(&mut self, expr: SExp) -> Result {
+        let (obj, rest) = self.eval_args(expr)?.split_car()?;
+        let tag = symbol_name(rest.car()?)?;
+        self.class(&tag)?;
+
+        let mut obj_tag = Some(obj.type_of().to_string());
+        while let Some(t) = obj_tag {
+            if t == tag {
+                return Ok(true.into());
+            }
+            obj_tag = self.supertypes.get(&t).cloned();
+        }
+
+        Ok(false.into())
+    }
+}
@@ -0,0 +1,103 @@
+use std::convert::TryFrom;
+
+use super::super::Primitive::{Boolean, Number};
+use super::super::SExp::{self, Atom};
+use super::super::{Error, Num, Result};
+use super::Context;
+
+/// What the host running some code should do next: carry on, stop with an
+/// exit code (after `(exit [code])`), or start again with a fresh context
+/// (after `(restart)`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplControl {
+    Continue,
+    Exit(i32),
+    Restart,
+}
+
+impl ReplControl {
+    /// What to do after evaluation produced this result: `Exit` or `Restart`
+    /// if it was stopped by `exit` or `restart`, and `Continue` otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// use parsley::ReplControl;
+    /// let mut ctx = Context::base();
+    ///
+    /// assert_eq!(ReplControl::of(&ctx.run("(+ 1 2)")), ReplControl::Continue);
+    /// assert_eq!(ReplControl::of(&ctx.run("(car 5)")), ReplControl::Continue);
+    /// assert_eq!(ReplControl::of(&ctx.run("(exit 3)")), ReplControl::Exit(3));
+    /// assert_eq!(ReplControl::of(&ctx.run("(restart)")), ReplControl::Restart);
+    /// ```
+    #[must_use]
+    pub fn of(result: &Result) -> Self {
+        match result {
+            Err(Error::Control(control)) => *control,
+            _ => ReplControl::Continue,
+        }
+    }
+}
+
+/// The exit code for the argument of `exit`: `#t` (or nothing) for success,
+/// `#f` for failure, or an exact integer.
+fn exit_code(arg: Option<SExp>) -> std::result::Result<i32, Error> {
+    match arg {
+        None | Some(Atom(Boolean(true))) => Ok(0),
+        Some(Atom(Boolean(false))) => Ok(1),
+        Some(Atom(Number(Num::Int(i)))) => i32::try_from(i).map_err(|_| Error::Type {
+            expected: "boolean or exit code",
+            given: i.to_string(),
+        }),
+        Some(other) => Err(Error::Type {
+            expected: "boolean or exit code",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+impl Context {
+    /// Call a function whenever `exit` or `restart` is evaluated, before
+    /// evaluation unwinds. This replaces any function set before.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use parsley::prelude::*;
+    /// use parsley::ReplControl;
+    ///
+    /// let seen = Rc::new(Cell::new(ReplControl::Continue));
+    /// let mut ctx = Context::base();
+    /// let seen_by_hook = Rc::clone(&seen);
+    /// ctx.on_exit(move |control| seen_by_hook.set(control));
+    ///
+    /// assert!(ctx.run("(define (quit) (exit #f) 5) (quit)").is_err());
+    /// assert_eq!(seen.get(), ReplControl::Exit(1));
+    /// ```
+    pub fn on_exit(&mut self, hook: impl FnMut(ReplControl) + 'static) {
+        self.exit_hook = Some(Box::new(hook));
+    }
+
+    /// Tell the host what to do next, then unwind all the way out of
+    /// evaluation. `dynamic-wind` exits still run on the way out, but
+    /// exception handlers can't stop it.
+    fn stop(&mut self, control: ReplControl) -> Result {
+        if let Some(hook) = &mut self.exit_hook {
+            hook(control);
+        }
+        Err(Error::Control(control))
+    }
+
+    /// `(exit [code])`: stop evaluating, and have the host exit.
+    pub(super) fn exit(&mut self, expr: SExp) -> Result {
+        let code = exit_code(self.eval_args(expr)?.car().ok())?;
+        self.stop(ReplControl::Exit(code))
+    }
+
+    /// `(restart)`: stop evaluating, and have the host start over with a
+    /// fresh context.
+    pub(super) fn restart(&mut self, _: SExp) -> Result {
+        self.stop(ReplControl::Restart)
+    }
+}
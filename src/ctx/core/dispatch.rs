@@ -0,0 +1,308 @@
+//! Tables for dispatching on the clauses of a `case` form without trying each
+//! clause in turn.
+//!
+//! When a procedure is made, each large `case` in its body, and each `cond`
+//! which starts with a run of tests comparing a variable to constants, is
+//! lowered into a call to a procedure which keeps a table of its clauses. The
+//! table is built once, rather than each time the form is evaluated.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::super::proc::{Func, Proc};
+use super::super::super::SExp::{self, Atom, Null, Pair};
+use super::super::super::{Error, Num, Primitive, Result};
+use super::super::Context;
+use super::case_matches;
+
+/// The fewest clauses a `case` needs for its data to be hashed, or a `cond`
+/// for its tests to be: with fewer, trying them in turn is cheaper.
+pub(super) const INDEX_AT: usize = 8;
+
+/// A datum which is the same as a key by `eqv?` exactly when they are equal,
+/// so it can be looked up by hashing.
+#[derive(PartialEq, Eq, Hash)]
+enum CaseKey {
+    Null,
+    Boolean(bool),
+    Character(char),
+    Symbol(String),
+    Fixnum(isize),
+}
+
+impl CaseKey {
+    fn of(datum: &SExp) -> Option<Self> {
+        match datum {
+            Null => Some(Self::Null),
+            Atom(Primitive::Boolean(b)) => Some(Self::Boolean(*b)),
+            Atom(Primitive::Character(c)) => Some(Self::Character(*c)),
+            Atom(Primitive::Symbol(s)) => Some(Self::Symbol(s.clone())),
+            Atom(Primitive::Number(Num::Int(i))) => Some(Self::Fixnum(*i)),
+            _ => None,
+        }
+    }
+}
+
+/// The clauses of a `case` form, by the data they match.
+pub(super) struct CaseTable {
+    /// The body of each clause, in order.
+    bodies: Vec<SExp>,
+    /// The first clause with each datum which can be hashed.
+    index: HashMap<CaseKey, usize>,
+    /// Every other datum, with its clause, to be compared in turn. A key which
+    /// can be hashed is never the same as one of these, nor the reverse.
+    others: Vec<(SExp, usize)>,
+    /// The `else` clause, if there is one.
+    fallback: Option<usize>,
+}
+
+impl CaseTable {
+    /// Sort the clauses of a `case` form by their data. Only a form with at
+    /// least [`INDEX_AT`] clauses has its data hashed.
+    pub(super) fn new(clauses: SExp) -> std::result::Result<Self, Error> {
+        let indexed = clauses.len() >= INDEX_AT;
+        let else_ = SExp::sym("else");
+        let mut table = Self {
+            bodies: Vec::new(),
+            index: HashMap::new(),
+            others: Vec::new(),
+            fallback: None,
+        };
+
+        for (i, clause) in clauses.into_iter().enumerate() {
+            let (data, body) = clause.split_car()?;
+            table.bodies.push(body);
+
+            // any clauses after `else` can never be reached
+            if data == else_ {
+                table.fallback = Some(i);
+                break;
+            }
+
+            for datum in data {
+                match CaseKey::of(&datum).filter(|_| indexed) {
+                    Some(key) => {
+                        table.index.entry(key).or_insert(i);
+                    }
+                    None => table.others.push((datum, i)),
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// The body of the first clause which matches the key, if any does.
+    pub(super) fn lookup(&self, key: &SExp) -> Option<&SExp> {
+        let found = match CaseKey::of(key) {
+            Some(hashed) if !self.index.is_empty() => self.index.get(&hashed).copied(),
+            _ => self
+                .others
+                .iter()
+                .find(|(datum, _)| case_matches(datum, key))
+                .map(|(_, i)| *i),
+        };
+
+        found.or(self.fallback).map(|i| &self.bodies[i])
+    }
+}
+
+/// A `cond` whose first clauses each compare the same variable to a constant
+/// with `=`, `eqv?` or `eq?`, as in `((= n 1) ...)` or `((eq? x 'a) ...)`.
+struct CondTable {
+    /// The procedure each test calls.
+    op: String,
+    /// The variable each test compares.
+    var: String,
+    /// The first clause which compares with each constant.
+    index: HashMap<CaseKey, usize>,
+    /// The consequents of those clauses, in order.
+    consequents: Vec<SExp>,
+    /// The clauses after them, to be tried in turn.
+    rest: SExp,
+}
+
+impl CondTable {
+    /// Collect the leading run of clauses which compare a variable to a
+    /// constant, if there are at least [`INDEX_AT`] of them.
+    fn new(clauses: &SExp) -> Option<Self> {
+        let mut table: Option<Self> = None;
+        let mut rest = clauses;
+
+        while let Pair { head: clause, tail } = rest {
+            let Some((op, var, key, consequent)) = comparison(clause) else {
+                break;
+            };
+            let table = table.get_or_insert_with(|| Self {
+                op: op.clone(),
+                var: var.clone(),
+                index: HashMap::new(),
+                consequents: Vec::new(),
+                rest: Null,
+            });
+            if op != table.op || var != table.var {
+                break;
+            }
+
+            table.index.entry(key).or_insert(table.consequents.len());
+            table.consequents.push(consequent);
+            rest = tail;
+        }
+
+        table
+            .filter(|table| table.consequents.len() >= INDEX_AT)
+            .map(|table| Self {
+                rest: rest.clone(),
+                ..table
+            })
+    }
+}
+
+/// The parts of a `cond` clause which compares a variable to a constant: the
+/// procedure, the variable, the constant and the consequent.
+fn comparison(clause: &SExp) -> Option<(String, String, CaseKey, SExp)> {
+    let (test, consequent) = clause.clone().split_car().ok()?;
+    let mut parts = test.into_iter();
+    let (
+        Some(Atom(Primitive::Symbol(op))),
+        Some(Atom(Primitive::Symbol(var))),
+        Some(constant),
+        None,
+    ) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+
+    let key = match (op.as_str(), constant) {
+        ("=", Atom(Primitive::Number(Num::Int(i)))) => CaseKey::Fixnum(i),
+        ("eqv?" | "eq?", Pair { head, tail }) if *head == SExp::sym("quote") => {
+            CaseKey::of(&tail.car().ok()?)?
+        }
+        // an unquoted symbol is a variable, not a constant
+        ("eqv?" | "eq?", Atom(Primitive::Symbol(_))) => return None,
+        ("eqv?" | "eq?", constant @ Atom(_)) => CaseKey::of(&constant)?,
+        _ => return None,
+    };
+
+    Some((op, var, key, consequent))
+}
+
+/// The table of a lowered `case` or `cond`.
+enum Lowered {
+    Case { key: SExp, table: CaseTable },
+    Cond(CondTable),
+}
+
+impl Context {
+    /// Lower the large `case` and `cond` forms in the body of a procedure.
+    pub(super) fn lower_dispatch(&self, body: SExp) -> SExp {
+        self.lower_elements(body)
+    }
+
+    fn lower_elements(&self, list: SExp) -> SExp {
+        match list {
+            Pair { head, tail } => self.lower_elements(*tail).cons(self.lower(*head)),
+            other => other,
+        }
+    }
+
+    fn lower(&self, expr: SExp) -> SExp {
+        let (head, tail) = match expr {
+            Pair { head, tail } => (*head, *tail),
+            other => return other,
+        };
+        let name = match &head {
+            Atom(Primitive::Symbol(name)) => name.as_str(),
+            _ => return self.lower_elements(tail).cons(self.lower(head)),
+        };
+
+        match name {
+            // data and macro uses are left as they are
+            "quote" | "quasiquote" => return tail.cons(head),
+            _ if matches!(self.get(name), Some(Atom(Primitive::Macro(_)))) => {
+                return tail.cons(head);
+            }
+            _ => (),
+        }
+
+        let tail = self.lower_elements(tail);
+        let lowered = match name {
+            "case" if self.is_core(name) => tail
+                .clone()
+                .split_car()
+                .ok()
+                .filter(|(_, clauses)| clauses.len() >= INDEX_AT)
+                .and_then(|(key, clauses)| {
+                    let table = CaseTable::new(clauses).ok()?;
+                    Some(Lowered::Case { key, table })
+                }),
+            "cond" if self.is_core(name) => CondTable::new(&tail).map(Lowered::Cond),
+            _ => None,
+        };
+
+        let form = tail.cons(head);
+        match lowered {
+            Some(lowered) => {
+                let name = lowered.name();
+                let lowered = Rc::new((lowered, form));
+                let dispatch = move |ctx: &mut Self, _| ctx.dispatch(&lowered.0, &lowered.1);
+                Null.cons(Proc::new(Func::Ctx(Rc::new(dispatch)), 0, Some(name)).into())
+            }
+            None => form,
+        }
+    }
+
+    /// Whether a name refers to the core form of that name, and not a local
+    /// binding which shadows it.
+    fn is_core(&self, name: &str) -> bool {
+        self.get(name).as_ref() == self.core.get(name)
+    }
+
+    /// Evaluate a lowered form, or the form it was lowered from if any name it
+    /// relies on has since been bound to something else.
+    fn dispatch(&mut self, lowered: &Lowered, form: &SExp) -> Result {
+        match lowered {
+            Lowered::Case { key, table } if self.is_core("case") => {
+                let key = self.eval(key.clone())?;
+                self.eval_case_clause(table, key)
+            }
+            Lowered::Cond(table) if self.is_core("cond") && self.is_lang(&table.op) => {
+                let value = self.eval(SExp::sym(&table.var))?;
+                let key = match (table.op.as_str(), CaseKey::of(&value)) {
+                    // `=` compares numbers of any kind, and rejects anything else
+                    ("=", Some(key @ CaseKey::Fixnum(_))) => key,
+                    ("=", _) => return Ok(self.defer(form.clone())),
+                    (_, Some(key)) => key,
+                    // nothing else is the same as any of the constants
+                    (_, None) => return self.eval_rest(&table.rest),
+                };
+
+                match table.index.get(&key) {
+                    Some(&i) => self.eval_consequent(true.into(), table.consequents[i].clone()),
+                    None => self.eval_rest(&table.rest),
+                }
+            }
+            _ => Ok(self.defer(form.clone())),
+        }
+    }
+
+    /// Whether a name refers to the language-level definition of that name.
+    fn is_lang(&self, name: &str) -> bool {
+        matches!(self.lang.get(name), Some(def) if self.get(name).as_ref() == Some(def))
+    }
+
+    fn eval_rest(&mut self, clauses: &SExp) -> Result {
+        Ok(self
+            .eval_clauses(clauses.clone())?
+            .unwrap_or(Atom(Primitive::Void)))
+    }
+}
+
+impl Lowered {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Case { .. } => "case",
+            Self::Cond(_) => "cond",
+        }
+    }
+}
@@ -1,114 +1,159 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use super::super::proc::{Func, Proc};
+use super::super::proc::{Builtin, Formals, Func, Proc};
 use super::super::SExp::{self, Atom, Null, Pair};
-use super::super::{Error, Ns, Primitive, Result, SyntaxError};
-use super::Context;
+use super::super::{Error, Macro, Ns, Primitive, Promise, RecordType, Result, SyntaxError};
+use super::{quoted, Context};
 
+use self::dispatch::CaseTable;
+
+mod dispatch;
 mod tests;
 
-macro_rules! tup_ctx_env {
-    ( $name:expr, $proc:expr, $arity:expr ) => {
-        (
-            $name.to_string(),
-            $crate::SExp::from($crate::Proc::new(
-                $crate::Func::Ctx(::std::rc::Rc::new($proc)),
-                $arity,
-                Some($name),
-            )),
-        )
-    };
-}
+/// The special forms, which every context shares.
+static CORE_FORMS: &[Builtin] = &[
+    Builtin::new("eval", Context::eval_eval, 1, Some(2)),
+    Builtin::new("apply", Context::do_apply, 2, Some(2)),
+    Builtin::new(":", Context::eval_annotate, 2, Some(2)),
+    Builtin::new("and", Context::eval_and, 0, None),
+    Builtin::new("begin", |c, e| Context::eval_defer(c, &e), 0, None),
+    Builtin::new("case", Context::eval_case, 2, None),
+    Builtin::new("cond", Context::eval_cond, 0, None),
+    Builtin::new("delay", |c, e| Context::eval_delay(c, e, false), 1, Some(1)),
+    Builtin::new(
+        "delay-force",
+        |c, e| Context::eval_delay(c, e, true),
+        1,
+        Some(1),
+    ),
+    Builtin::new("do", Context::eval_do, 2, None),
+    Builtin::new("define", Context::eval_define, 1, None),
+    Builtin::new("define/contract", Context::eval_define_contract, 3, None),
+    Builtin::new("guard", Context::eval_guard, 2, None),
+    Builtin::new("define-generic", Context::eval_define_generic, 1, Some(1)),
+    Builtin::new("define-method", Context::eval_define_method, 2, None),
+    Builtin::new(
+        "define-record-type",
+        Context::eval_define_record_type,
+        3,
+        None,
+    ),
+    Builtin::new("define-syntax", Context::eval_define_syntax, 2, Some(2)),
+    Builtin::new("define-values", Context::eval_define_values, 2, Some(2)),
+    Builtin::new("fluid-let", Context::eval_fluid_let, 2, None),
+    Builtin::new("if", Context::eval_if, 3, Some(3)),
+    Builtin::new("lambda", |e, c| Context::eval_lambda(e, c, false), 2, None),
+    Builtin::new("let", Context::eval_let, 2, None),
+    Builtin::new("let*", Context::eval_let_star, 2, None),
+    Builtin::new(
+        "let-syntax",
+        |c, e| Context::eval_let_syntax(c, e, false),
+        2,
+        None,
+    ),
+    Builtin::new(
+        "let-values",
+        |c, e| Context::eval_let_values(c, e, false),
+        2,
+        None,
+    ),
+    Builtin::new(
+        "let*-values",
+        |c, e| Context::eval_let_values(c, e, true),
+        2,
+        None,
+    ),
+    Builtin::new("letrec", |c, e| Context::eval_letrec(c, e, false), 2, None),
+    Builtin::new("letrec*", |c, e| Context::eval_letrec(c, e, true), 2, None),
+    Builtin::new(
+        "letrec-syntax",
+        |c, e| Context::eval_let_syntax(c, e, true),
+        2,
+        None,
+    ),
+    Builtin::new(
+        "named-lambda",
+        |e, c| Context::eval_lambda(e, c, true),
+        2,
+        None,
+    ),
+    Builtin::new("or", Context::eval_or, 0, None),
+    Builtin::new("parameterize", Context::eval_parameterize, 2, None),
+    Builtin::new("quasiquote", Context::eval_quasiquote, 1, Some(1)),
+    Builtin::new("quote", Context::eval_quote, 1, Some(1)),
+    Builtin::new("receive", Context::eval_receive, 3, None),
+    Builtin::new("set!", Context::eval_set, 2, Some(2)),
+    Builtin::new("syntax-rules", Context::eval_syntax_rules, 1, None),
+    Builtin::new("the-environment", Context::the_environment, 0, Some(0)),
+    Builtin::new("unless", |c, e| Context::eval_when(c, e, false), 1, None),
+    Builtin::new("when", |c, e| Context::eval_when(c, e, true), 1, None),
+];
 
 impl Context {
     pub(super) fn core() -> Ns {
-        [
-            tup_ctx_env!(
-                "eval",
-                |c: &mut Self, e: SExp| {
-                    let first_layer = c.eval(e.car()?)?;
-                    c.eval(first_layer)
-                },
-                1
-            ),
-            tup_ctx_env!("apply", Self::do_apply, 2),
-            tup_ctx_env!("and", Self::eval_and, (0,)),
-            tup_ctx_env!("begin", Self::eval_begin, (0,)),
-            tup_ctx_env!("case", Self::eval_case, (2,)),
-            tup_ctx_env!("cond", Self::eval_cond, (0,)),
-            tup_ctx_env!("do", Self::eval_do, (2,)),
-            tup_ctx_env!("define", Self::eval_define, (1,)),
-            tup_ctx_env!("if", Self::eval_if, 3),
-            tup_ctx_env!("lambda", |e, c| Self::eval_lambda(e, c, false), (2,)),
-            tup_ctx_env!("let", Self::eval_let, (2,)),
-            tup_ctx_env!("let*", Self::eval_let_star, (2,)),
-            tup_ctx_env!("letrec", Self::eval_let_star, (2,)),
-            tup_ctx_env!("named-lambda", |e, c| Self::eval_lambda(e, c, true), (2,)),
-            tup_ctx_env!("or", Self::eval_or, (0,)),
-            tup_ctx_env!("quasiquote", Self::eval_quasiquote, 1),
-            tup_ctx_env!("quote", Self::eval_quote, 1),
-            tup_ctx_env!("set!", Self::eval_set, 2),
-        ]
-        .iter()
-        .cloned()
-        .collect()
+        CORE_FORMS.iter().map(Builtin::entry).collect()
     }
 
     fn eval_and(&mut self, expr: SExp) -> Result {
-        let mut state = SExp::from(true);
+        let mut elements = expr.into_iter().peekable();
 
-        for element in expr {
-            state = self.eval(element)?;
+        while let Some(element) = elements.next() {
+            // the last element is in tail position
+            if elements.peek().is_none() {
+                return Ok(self.defer(element));
+            }
 
-            if let Atom(Primitive::Boolean(false)) = state {
-                break;
+            if let Atom(Primitive::Boolean(false)) = self.eval(element)? {
+                return Ok(false.into());
             }
         }
 
-        Ok(state)
+        Ok(true.into())
     }
 
-    fn eval_begin(&mut self, expr: SExp) -> Result {
-        let mut ret = Atom(Primitive::Undefined);
-        for exp in expr {
-            ret = self.eval(exp)?;
-        }
-        Ok(ret)
+    fn eval_case(&mut self, expr: SExp) -> Result {
+        let (key, clauses) = expr.split_car()?;
+        let key = self.eval(key)?;
+        let table = CaseTable::new(clauses)?;
+        self.eval_case_clause(&table, key)
     }
 
-    fn eval_case(&mut self, expr: SExp) -> Result {
-        match expr {
-            Pair { head, tail } => {
-                let else_ = SExp::sym("else");
-                let hvl = self.eval(*head)?;
-
-                for case in *tail {
-                    if let Pair {
-                        head: objs,
-                        tail: body,
-                    } = case
-                    {
-                        if *objs == else_ || objs.iter().any(|e| *e == hvl) {
-                            return self.eval_defer(&*body);
-                        }
-                    }
-                }
+    /// Evaluate the first clause of a `case` which matches the key, if any
+    /// does.
+    fn eval_case_clause(&mut self, table: &CaseTable, key: SExp) -> Result {
+        match table.lookup(&key) {
+            Some(body) => self.eval_consequent(key, body.clone()),
+            None => Ok(Atom(Primitive::Undefined)),
+        }
+    }
 
-                self.eval_case(hvl)
+    /// Evaluate the body of a `case` or `cond` clause which was chosen by
+    /// `value`: the key, or the result of the test.
+    fn eval_consequent(&mut self, value: SExp, consequent: SExp) -> Result {
+        match consequent {
+            // receiver clause: `(test => proc)`
+            Pair { head, tail } if *head == SExp::sym("=>") => {
+                let receiver = tail.car()?;
+                Ok(self.defer(Null.cons(quoted(value)).cons(receiver)))
             }
-            Atom(_) => Ok(Atom(Primitive::Undefined)),
-            Null => Err(Error::ArityMin {
-                expected: 1,
-                given: 0,
-            }),
+            consequent => self.eval_defer(&consequent),
         }
     }
 
     fn eval_cond(&mut self, expr: SExp) -> Result {
+        // falls through if no valid predicates found
+        Ok(self.eval_clauses(expr)?.unwrap_or(Atom(Primitive::Void)))
+    }
+
+    /// Evaluate the first `cond` clause whose test passes, if any does.
+    pub(super) fn eval_clauses(
+        &mut self,
+        clauses: SExp,
+    ) -> std::result::Result<Option<SExp>, Error> {
         let else_ = SExp::sym("else");
 
-        for case in expr {
+        for case in clauses {
             match case {
                 Pair {
                     head: predicate,
@@ -116,14 +161,14 @@ impl Context {
                 } => {
                     // TODO: check if `else` clause is actually last
                     if *predicate == else_ {
-                        return self.eval_defer(&*consequent);
+                        return self.eval_defer(&consequent).map(Some);
                     }
 
                     match self.eval(*predicate)? {
                         Atom(Primitive::Boolean(false)) => {
                             continue;
                         }
-                        _ => return self.eval_defer(&*consequent),
+                        value => return self.eval_consequent(value, *consequent).map(Some),
                     }
                 }
                 exp => {
@@ -132,8 +177,16 @@ impl Context {
             }
         }
 
-        // falls through if no valid predicates found
-        Ok(Atom(Primitive::Void))
+        Ok(None)
+    }
+
+    /// Evaluate the init of a variable, so that any variable it uses before
+    /// initialization can be reported along with the one being defined.
+    fn eval_init(&mut self, name: &str, init: SExp) -> Result {
+        self.initializing.push(name.to_string());
+        let value = self.eval(init);
+        self.initializing.pop();
+        value
     }
 
     fn eval_define(&mut self, expr: SExp) -> Result {
@@ -163,7 +216,10 @@ impl Context {
 
                 match defn {
                     Null => (sym, Atom(Primitive::Undefined)),
-                    p @ Pair { .. } => (sym, self.eval(p.car()?)?),
+                    p @ Pair { .. } => {
+                        let value = self.eval_init(&sym, p.car()?)?;
+                        (sym, value)
+                    }
                     other => (sym, self.eval(other)?),
                 }
             }
@@ -180,6 +236,207 @@ impl Context {
         Ok(Atom(Primitive::Undefined))
     }
 
+    /// `(define/contract (name params...) (-> arg-pred ... result-pred) body...)`
+    ///
+    /// Defines a procedure wrapped in one which checks each argument and the
+    /// result against the predicates of the contract. The wrapper has to
+    /// finish the call to check the result, so it is not a tail call.
+    fn eval_define_contract(&mut self, expr: SExp) -> Result {
+        let (signature, tail) = expr.split_car()?;
+        let (contract, body) = tail.split_car()?;
+
+        let (name, n_params) = match &signature {
+            Pair { head, tail } => (symbol_name(*head.clone())?, tail.len()),
+            other => {
+                return Err(Error::Type {
+                    expected: "list",
+                    given: other.type_of().to_string(),
+                })
+            }
+        };
+
+        let mut preds = match contract.clone() {
+            Pair { head, tail } if *head == SExp::sym("->") && tail.len() == n_params + 1 => tail
+                .into_iter()
+                .map(|p| Ok((p.to_string(), self.eval(p)?)))
+                .collect::<std::result::Result<Vec<_>, Error>>()?,
+            _ => return Err(SyntaxError::InvalidContract(contract).into()),
+        };
+        let result_pred = preds.pop().ok_or(SyntaxError::InvalidContract(contract))?;
+
+        let inner = self.eval_lambda(body.cons(signature), true)?;
+        let wrapper_name = name.clone();
+        let quote = |v: SExp| Null.cons(v).cons(SExp::sym("quote"));
+        let check = move |ctx: &mut Self, (text, pred): &(String, SExp), value: &SExp, arg| {
+            match ctx.eval(Null.cons(quote(value.clone())).cons(pred.clone()))? {
+                Atom(Primitive::Boolean(false)) => Err(Error::Contract {
+                    name: wrapper_name.clone(),
+                    arg,
+                    expected: text.clone(),
+                    given: format!("{value:?}"),
+                    // arguments are the caller's responsibility
+                    blame: match arg {
+                        Some(_) => "caller".to_string(),
+                        None => wrapper_name.clone(),
+                    },
+                }),
+                _ => Ok(()),
+            }
+        };
+
+        let wrapper = Proc::new(
+            Func::Ctx(Rc::new(move |ctx: &mut Self, args: SExp| {
+                let args = ctx.eval_args(args)?;
+                for (i, (pred, arg)) in preds.iter().zip(args.iter()).enumerate() {
+                    check(ctx, pred, arg, Some(i + 1))?;
+                }
+
+                let result = ctx.eval(
+                    args.into_iter()
+                        .map(quote)
+                        .collect::<SExp>()
+                        .cons(inner.clone()),
+                )?;
+                check(ctx, &result_pred, &result, None)?;
+                Ok(result)
+            })),
+            n_params,
+            Some(&name),
+        );
+
+        self.define(&name, wrapper.into());
+        Ok(Atom(Primitive::Undefined))
+    }
+
+    fn eval_define_values(&mut self, expr: SExp) -> Result {
+        let bindings = self.eval_values_binding(expr)?;
+        self.define_all(bindings);
+        Ok(Atom(Primitive::Undefined))
+    }
+
+    fn eval_define_record_type(&mut self, expr: SExp) -> Result {
+        let (type_name, tail) = expr.split_car()?;
+        let (ctor, tail) = tail.split_car()?;
+        let (pred, field_specs) = tail.split_car()?;
+
+        let field_specs = field_specs
+            .into_iter()
+            .map(|spec| match spec {
+                Pair { .. } => spec.into_iter().map(symbol_name).collect(),
+                other => Ok(vec![symbol_name(other)?]),
+            })
+            .collect::<std::result::Result<Vec<Vec<_>>, _>>()?;
+
+        let rtd = RecordType::new(
+            &symbol_name(type_name)?,
+            field_specs.iter().map(|spec| spec[0].clone()).collect(),
+        );
+
+        // the constructor may be given as a bare name, taking every field
+        let (ctor_name, ctor_args) = match ctor {
+            Pair { head, tail } => (
+                symbol_name(*head)?,
+                tail.into_iter()
+                    .map(symbol_name)
+                    .collect::<std::result::Result<Vec<_>, _>>()?,
+            ),
+            other => (
+                symbol_name(other)?,
+                field_specs.iter().map(|spec| spec[0].clone()).collect(),
+            ),
+        };
+
+        let mut defns = vec![(ctor_name.clone(), rtd.constructor(&ctor_name, &ctor_args)?)];
+
+        let pred_name = symbol_name(pred)?;
+        defns.push((pred_name.clone(), rtd.predicate(&pred_name)));
+
+        for spec in &field_specs {
+            match spec.as_slice() {
+                [_] => (),
+                [field, accessor] => defns.push((accessor.clone(), rtd.accessor(accessor, field)?)),
+                [field, accessor, modifier] => {
+                    defns.push((accessor.clone(), rtd.accessor(accessor, field)?));
+                    defns.push((modifier.clone(), rtd.modifier(modifier, field)?));
+                }
+                _ => return Err(SyntaxError::InvalidRecordField(spec.join(" ")).into()),
+            }
+        }
+
+        for (name, proc) in defns {
+            self.define(&name, proc.into());
+        }
+
+        Ok(Atom(Primitive::Undefined))
+    }
+
+    fn eval_define_syntax(&mut self, expr: SExp) -> Result {
+        let (sym, m) = self.eval_syntax_binding(expr)?;
+        self.define(&sym, m);
+        Ok(Atom(Primitive::Undefined))
+    }
+
+    /// Evaluate a `(keyword transformer)` pair into a named macro.
+    fn eval_syntax_binding(&mut self, binding: SExp) -> std::result::Result<(String, SExp), Error> {
+        let (name, tail) = binding.split_car()?;
+
+        let sym = match name {
+            Atom(Primitive::Symbol(sym)) => sym,
+            other => {
+                return Err(Error::Type {
+                    expected: "symbol",
+                    given: other.type_of().to_string(),
+                });
+            }
+        };
+
+        match self.eval(tail.car()?)? {
+            Atom(Primitive::Macro(m)) => {
+                let m = m.with_name(&sym).into();
+                Ok((sym, m))
+            }
+            other => Err(Error::Type {
+                expected: "macro",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    fn eval_let_syntax(&mut self, expr: SExp, is_rec: bool) -> Result {
+        let (bindings, body) = expr.split_car()?;
+
+        // recursive bindings are created in (and closed over) the new scope
+        if is_rec {
+            self.push();
+        }
+
+        let macros = bindings
+            .into_iter()
+            .map(|b| self.eval_syntax_binding(b))
+            .collect::<std::result::Result<Ns, Error>>();
+
+        if !is_rec {
+            self.push();
+        }
+
+        let result = macros.and_then(|macros| {
+            self.define_all(macros);
+            self.eval_defer(&body)
+        });
+        self.pop();
+        result
+    }
+
+    fn eval_syntax_rules(&mut self, expr: SExp) -> Result {
+        Ok(Macro::new(expr, self.cont.borrow().env())?.into())
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    fn eval_delay(&mut self, expr: SExp, chained: bool) -> Result {
+        let thunk = self.make_proc(None, Formals::default(), expr);
+        Ok(Promise::delayed(thunk, chained).into())
+    }
+
     fn eval_do(&mut self, expr: SExp) -> Result {
         let (vars, rest) = expr.split_car()?;
         let (term, body) = rest.split_car()?;
@@ -189,7 +446,10 @@ impl Context {
         let mut var_updates = HashMap::new();
 
         for var in vars {
-            match var.split_car()? {
+            let invalid = || SyntaxError::InvalidDoClause(var.clone());
+
+            match var.clone().split_car().map_err(|_| invalid())? {
+                // a variable without a step keeps its value
                 (Atom(Primitive::Symbol(s)), rest) => match rest.len() {
                     1 => {
                         var_inits.insert(s, self.eval(rest.car()?)?);
@@ -199,13 +459,7 @@ impl Context {
                         var_inits.insert(s.clone(), self.eval(defn)?);
                         var_updates.insert(s, tail.car()?);
                     }
-                    0 => {
-                        return Err(Error::ArityMin {
-                            expected: 1,
-                            given: 0,
-                        });
-                    }
-                    given => return Err(Error::ArityMax { expected: 2, given }),
+                    _ => return Err(invalid().into()),
                 },
                 (other, _) => {
                     return Err(Error::Type {
@@ -217,17 +471,22 @@ impl Context {
         }
 
         // termination condition and return value
-        let (cond, return_expr) = term.split_car()?;
+        let (cond, return_expr) = match term {
+            Pair { head, tail } => (*head, *tail),
+            other => return Err(SyntaxError::InvalidDoClause(other).into()),
+        };
 
         // add definitions to environment
         self.push();
-        self.cont.borrow().env().extend(var_inits);
+        self.define_all(var_inits);
 
         let result = 'eval: loop {
             // check termination condition
             match self.eval(cond.clone()) {
                 Ok(Atom(Primitive::Boolean(false))) => (),
-                Ok(_) => break 'eval self.eval_begin(return_expr),
+                // the result is unspecified if there are no expressions for it
+                Ok(_) if return_expr == Null => break 'eval Ok(Atom(Primitive::Void)),
+                Ok(_) => break 'eval self.eval_defer(&return_expr),
                 err => break 'eval err,
             }
 
@@ -250,7 +509,7 @@ impl Context {
                 };
                 new_map.insert(key.to_string(), new_val);
             }
-            self.cont.borrow().env().extend(new_map);
+            self.define_all(new_map);
         };
 
         self.pop();
@@ -270,48 +529,78 @@ impl Context {
         }))
     }
 
+    fn eval_when(&mut self, expr: SExp, expected: bool) -> Result {
+        let (condition, body) = expr.split_car()?;
+
+        let truthy = !matches!(self.eval(condition)?, Atom(Primitive::Boolean(false)));
+        if truthy == expected {
+            self.eval_defer(&body)
+        } else {
+            Ok(Atom(Primitive::Void))
+        }
+    }
+
     fn eval_lambda(&mut self, expr: SExp, is_named: bool) -> Result {
         let (signature, fn_body) = expr.split_car()?;
+        let sig_type = signature.type_of().to_string();
+        let mut formals = parse_formals(signature)?;
 
-        if let other @ Atom(_) = signature {
-            return Err(Error::Type {
-                expected: "list",
-                given: other.type_of().to_string(),
-            });
+        if is_named {
+            if formals.params.is_empty() {
+                return Err(Error::Type {
+                    expected: "list",
+                    given: sig_type,
+                });
+            }
+
+            let name = formals.params.remove(0);
+            Ok(self.make_proc(Some(&name), formals, fn_body))
+        } else {
+            Ok(self.make_proc(None, formals, fn_body))
         }
+    }
+
+    fn make_proc(&self, name: Option<&str>, mut formals: Formals, fn_body: SExp) -> SExp {
+        // a leading string is documentation, unless it is the whole body
+        let (doc, fn_body) = match fn_body {
+            Pair { head, tail } if matches!(*head, Atom(Primitive::String(_))) && *tail != Null => {
+                (Some(head.to_string()), *tail)
+            }
+            body => (None, body),
+        };
+
+        // tables for large `case` and `cond` forms are built once, here
+        let fn_body = self.lower_dispatch(fn_body);
 
-        let str_sig = signature
+        // parameters are bound already, so they are not hoisted
+        formals.internal = internal_defines(&fn_body)
             .into_iter()
-            .map(|e| {
-                if let Atom(Primitive::Symbol(sym)) = e {
-                    Ok(sym)
-                } else {
-                    Err(Error::Type {
-                        expected: "symbol",
-                        given: e.type_of().to_string(),
-                    })
-                }
+            .filter(|name| {
+                !formals.params.contains(name)
+                    && !formals.optional.iter().any(|(p, _)| p == name)
+                    && formals.rest.as_ref() != Some(name)
             })
-            .collect::<std::result::Result<Vec<_>, Error>>()?;
+            .collect();
 
-        if is_named {
-            Ok(self.make_proc(Some(&str_sig[0]), str_sig[1..].to_vec(), fn_body))
+        let min = formals.params.len();
+        let max = min + formals.optional.len();
+        let is_variadic = formals.rest.is_some();
+        let func = Func::Lambda {
+            body: Rc::new(fn_body),
+            envt: self.cont.borrow().env(),
+            formals: Rc::new(formals),
+        };
+
+        let proc = if is_variadic {
+            Proc::new(func, (min,), name)
         } else {
-            Ok(self.make_proc(None, str_sig, fn_body))
-        }
-    }
+            Proc::new(func, (min, max), name)
+        };
 
-    fn make_proc(&self, name: Option<&str>, params: Vec<String>, fn_body: SExp) -> SExp {
-        let expected = params.len();
-        SExp::from(Proc::new(
-            Func::Lambda {
-                body: Rc::new(fn_body),
-                envt: self.cont.borrow().env(),
-                params,
-            },
-            expected,
-            name,
-        ))
+        SExp::from(match doc {
+            Some(doc) => proc.with_doc(doc),
+            None => proc,
+        })
     }
 
     pub(super) fn defer(&self, expr: SExp) -> SExp {
@@ -350,13 +639,15 @@ impl Context {
                 .into_iter()
                 .unzip();
 
-            self.push();
-            let proc = self.make_proc(Some(&let_name), params, statements);
-            self.define(&let_name, proc);
-            let applic = SExp::from(inits).cons(Atom(Primitive::Symbol(let_name)));
-            let result = self.eval(applic);
-            self.pop();
-            result
+            // the loop is bound in the scope of each of its calls, so the
+            // inits can't see it
+            let formals = Formals {
+                params,
+                recursive: Some(let_name.clone()),
+                ..Formals::default()
+            };
+            let proc = self.make_proc(Some(&let_name), formals, statements);
+            Ok(self.defer(SExp::from(inits).cons(proc)))
         } else {
             let mut var_inits = Ns::new();
 
@@ -374,7 +665,8 @@ impl Context {
             }
 
             self.push();
-            self.cont.borrow().env().extend(var_inits);
+            self.define_all(var_inits);
+            self.hoist_defines(&statements);
             let result = self.eval_defer(&statements);
             self.pop();
             result
@@ -395,18 +687,195 @@ impl Context {
             }
         }
 
+        self.hoist_defines(&statements);
+        let result = self.eval_defer(&statements);
+        self.pop();
+        result
+    }
+
+    /// Bind the names of the `define`s at the start of a body in the current
+    /// scope before any of it is evaluated, so that (as in `letrec*`) they can
+    /// all refer to each other and shadow outer bindings from the start.
+    /// Names already bound in this scope are left alone.
+    fn hoist_defines(&mut self, body: &SExp) {
+        let envt = self.cont.borrow().env();
+
+        for name in internal_defines(body) {
+            if !envt.contains_local(&name) {
+                self.define(&name, Atom(Primitive::Undefined));
+            }
+        }
+    }
+
+    /// Evaluate `letrec` (or `letrec*`, if sequential). Every binding is made
+    /// in one new frame before any init is evaluated, so closures in the inits
+    /// can refer to each other; referring to the value of a binding before it
+    /// is initialized is an error.
+    fn eval_letrec(&mut self, expr: SExp, is_sequential: bool) -> Result {
+        let (defn_list, statements) = expr.split_car()?;
+
+        let defns = defn_list
+            .into_iter()
+            .map(|defn| {
+                let (name, init) = defn.split_car()?;
+                Ok((symbol_name(name)?, init.car()?))
+            })
+            .collect::<std::result::Result<Vec<_>, Error>>()?;
+
+        self.push();
+        for (name, _) in &defns {
+            self.define(name, Atom(Primitive::Undefined));
+        }
+
+        let mut values = Vec::new();
+        for (name, init) in defns {
+            match self.eval_init(&name, init) {
+                Ok(value) if is_sequential => self.define(&name, value),
+                Ok(value) => values.push((name, value)),
+                Err(err) => {
+                    self.pop();
+                    return Err(err);
+                }
+            }
+        }
+        for (name, value) in values {
+            self.define(&name, value);
+        }
+
+        self.hoist_defines(&statements);
+        let result = self.eval_defer(&statements);
+        self.pop();
+        result
+    }
+
+    fn eval_let_values(&mut self, expr: SExp, is_sequential: bool) -> Result {
+        let (defn_list, statements) = expr.split_car()?;
+        let mut var_inits = Ns::new();
+
+        // sequential bindings can see the ones before them, so they need to
+        // go into the new scope right away
+        if is_sequential {
+            self.push();
+        }
+
+        for defn in defn_list {
+            match self.eval_values_binding(defn) {
+                Ok(bindings) if is_sequential => self.define_all(bindings),
+                Ok(bindings) => var_inits.extend(bindings),
+                Err(err) => {
+                    if is_sequential {
+                        self.pop();
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        if !is_sequential {
+            self.push();
+            self.define_all(var_inits);
+        }
+
+        self.hoist_defines(&statements);
         let result = self.eval_defer(&statements);
         self.pop();
         result
     }
 
+    /// Evaluate `(receive formals expression body...)` (SRFI 8): bind the
+    /// values returned by the expression to the formals, then evaluate the
+    /// body with them in scope.
+    fn eval_receive(&mut self, expr: SExp) -> Result {
+        let (formals, tail) = expr.split_car()?;
+        let (init, statements) = tail.split_car()?;
+
+        let values = self.eval(init)?;
+        let bindings = self.bind_formals(formals, values.into_values())?;
+
+        self.push();
+        self.define_all(bindings);
+        self.hoist_defines(&statements);
+        let result = self.eval_defer(&statements);
+        self.pop();
+        result
+    }
+
+    /// Evaluate a `(formals expression)` pair, and bind the values returned
+    /// by the expression to the formals.
+    fn eval_values_binding(&mut self, defn: SExp) -> std::result::Result<Ns, Error> {
+        let (formals, init) = defn.split_car()?;
+        let values = self.eval(init.car()?)?;
+        self.bind_formals(formals, values.into_values())
+    }
+
+    /// Match a list of values against formals, where any rest parameter
+    /// collects the remaining values and missing optional parameters take
+    /// their defaults.
+    fn bind_formals(&mut self, formals: SExp, values: Vec<SExp>) -> std::result::Result<Ns, Error> {
+        let Formals {
+            params,
+            optional,
+            rest,
+            ..
+        } = parse_formals(formals)?;
+
+        let (min, given) = (params.len(), values.len());
+        let max = min + optional.len();
+        if given < min {
+            return Err(if rest.is_none() && optional.is_empty() {
+                Error::Arity {
+                    expected: min,
+                    given,
+                }
+            } else {
+                Error::ArityMin {
+                    expected: min,
+                    given,
+                }
+            });
+        }
+        if rest.is_none() && given > max {
+            return Err(if optional.is_empty() {
+                Error::Arity {
+                    expected: max,
+                    given,
+                }
+            } else {
+                Error::ArityMax {
+                    expected: max,
+                    given,
+                }
+            });
+        }
+
+        let mut values = values.into_iter();
+        let mut bindings: Ns = params.into_iter().zip(values.by_ref()).collect();
+        for (name, default) in optional {
+            let value = match values.next() {
+                Some(value) => value,
+                None => self.eval(default)?,
+            };
+            bindings.insert(name, value);
+        }
+        if let Some(rest) = rest {
+            bindings.insert(rest, values.collect());
+        }
+
+        Ok(bindings)
+    }
+
     fn eval_or(&mut self, expr: SExp) -> Result {
-        for element in expr {
+        let mut elements = expr.into_iter().peekable();
+
+        while let Some(element) = elements.next() {
+            // the last element is in tail position
+            if elements.peek().is_none() {
+                return Ok(self.defer(element));
+            }
+
             match self.eval(element)? {
                 Atom(Primitive::Boolean(false)) => continue,
-                exp => {
-                    return Ok(exp);
-                }
+                exp => return Ok(exp),
             }
         }
 
@@ -417,18 +886,29 @@ impl Context {
         match expr.car()? {
             p @ Pair { .. } => p
                 .into_iter()
-                .map(|sub_expr| match sub_expr {
-                    Pair { head, tail } => match *head {
-                        Atom(Primitive::Symbol(ref s)) if s == "unquote" => self.eval(tail.car()?),
-                        _ => Ok(tail.cons(*head)),
-                    },
-                    _ => Ok(sub_expr),
-                })
+                .map(|sub_expr| self.quasiquote_element(sub_expr))
                 .collect::<Result>(),
+            // vector templates are filled in the same way as lists
+            Atom(Primitive::Vector(items)) => Ok(Atom(Primitive::Vector(
+                items
+                    .into_iter()
+                    .map(|sub_expr| self.quasiquote_element(sub_expr))
+                    .collect::<std::result::Result<_, _>>()?,
+            ))),
             other => Ok(other),
         }
     }
 
+    fn quasiquote_element(&mut self, sub_expr: SExp) -> Result {
+        match sub_expr {
+            Pair { head, tail } => match *head {
+                Atom(Primitive::Symbol(ref s)) if s == "unquote" => self.eval(tail.car()?),
+                _ => Ok(tail.cons(*head)),
+            },
+            _ => Ok(sub_expr),
+        }
+    }
+
     #[allow(clippy::unused_self)]
     fn eval_quote(&mut self, expr: SExp) -> Result {
         match expr {
@@ -461,7 +941,155 @@ impl Context {
     fn do_apply(&mut self, expr: SExp) -> Result {
         let (op, tail) = expr.split_car()?;
 
+        // the arguments are already evaluated, and the call is in tail position
         let args = self.eval(tail.car()?)?;
-        self.eval(args.cons(op))
+        let args = args.into_iter().map(quoted).collect::<SExp>();
+        Ok(self.defer(args.cons(op)))
+    }
+}
+
+/// Whether two values are the same by `eqv?`: atoms other than strings and
+/// vectors are compared by value, procedures by identity, and lists are never
+/// the same unless they are both empty.
+pub(super) fn eqv(a: &SExp, b: &SExp) -> bool {
+    match (a, b) {
+        (Null, Null) => true,
+        (Atom(Primitive::Boolean(b0)), Atom(Primitive::Boolean(b1))) => b0 == b1,
+        (Atom(Primitive::Character(c0)), Atom(Primitive::Character(c1))) => c0 == c1,
+        (Atom(Primitive::Symbol(s0)), Atom(Primitive::Symbol(s1))) => s0 == s1,
+        (Atom(Primitive::Number(n0)), Atom(Primitive::Number(n1))) => {
+            n0.is_exact() == n1.is_exact() && n0 == n1
+        }
+        (Atom(Primitive::Procedure(p0)), Atom(Primitive::Procedure(p1))) => p0 == p1,
+        _ => false,
+    }
+}
+
+/// `equal?`: whether two values have the same structure, with numbers inside
+/// them compared by `eqv?`, so that `(equal? '(2) '(2.0))` is false.
+pub(super) fn equal(a: &SExp, b: &SExp) -> bool {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a, b) {
+            (Pair { head: h0, tail: t0 }, Pair { head: h1, tail: t1 }) => {
+                if !equal(h0, h1) {
+                    return false;
+                }
+                a = t0;
+                b = t1;
+            }
+            (Atom(Primitive::Vector(v0)), Atom(Primitive::Vector(v1))) => {
+                return v0.len() == v1.len() && v0.iter().zip(v1).all(|(a, b)| equal(a, b));
+            }
+            (Atom(Primitive::Number(_)), Atom(Primitive::Number(_))) => return eqv(a, b),
+            _ => return a == b,
+        }
+    }
+}
+
+/// Whether the key of a `case` matches one of the data of a clause. Lists and
+/// vectors are compared with `equal?`, since they could never be `eqv?` to a
+/// key; anything else with `eqv?`.
+fn case_matches(datum: &SExp, key: &SExp) -> bool {
+    match datum {
+        Pair { .. } | Atom(Primitive::Vector(_)) => equal(datum, key),
+        _ => eqv(datum, key),
+    }
+}
+
+pub(super) fn symbol_name(exp: SExp) -> std::result::Result<String, Error> {
+    match exp {
+        Atom(Primitive::Symbol(sym)) => Ok(sym),
+        other => Err(Error::Type {
+            expected: "symbol",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+/// The names bound by the `define` forms at the start of a body, including
+/// any spliced in by `begin`.
+fn internal_defines(body: &SExp) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_defines(body, &mut names);
+    names
+}
+
+/// Add the names bound by the leading `define` forms of a body, and return
+/// whether every form in it was a definition.
+fn collect_defines(body: &SExp, names: &mut Vec<String>) -> bool {
+    let define = SExp::sym("define");
+    let begin = SExp::sym("begin");
+
+    for expr in body.iter() {
+        match expr {
+            Pair { head, tail } if **head == define => {
+                // the name may be in a (possibly curried) signature
+                let mut signature = match &**tail {
+                    Pair { head, .. } => &**head,
+                    _ => return false,
+                };
+                while let Pair { head, .. } = signature {
+                    signature = head;
+                }
+
+                match signature {
+                    Atom(Primitive::Symbol(name)) => names.push(name.clone()),
+                    _ => return false,
+                }
+            }
+            Pair { head, tail } if **head == begin => {
+                if !collect_defines(tail, names) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Split formals of the form `(a b)`, `args`, `(a b . rest)` or
+/// `(a #!optional (b default) c #!rest rest)` into their parts. Optional
+/// parameters without a default expression default to `#!default`.
+fn parse_formals(mut formals: SExp) -> std::result::Result<Formals, Error> {
+    let mut parsed = Formals::default();
+    let mut is_optional = false;
+
+    loop {
+        match formals {
+            Null => return Ok(parsed),
+            Pair { head, tail } => {
+                match *head {
+                    Atom(Primitive::Symbol(s)) if s == "#!optional" => is_optional = true,
+                    Atom(Primitive::Symbol(s)) if s == "#!rest" => {
+                        let (rest, after) = tail.split_car()?;
+                        if after != Null {
+                            return Err(Error::Type {
+                                expected: "null",
+                                given: after.type_of().to_string(),
+                            });
+                        }
+
+                        parsed.rest = Some(symbol_name(rest)?);
+                        return Ok(parsed);
+                    }
+                    param @ Pair { .. } if is_optional => {
+                        let (name, default) = param.split_car()?;
+                        parsed.optional.push((symbol_name(name)?, default.car()?));
+                    }
+                    param if is_optional => parsed
+                        .optional
+                        .push((symbol_name(param)?, Atom(Primitive::Default))),
+                    param => parsed.params.push(symbol_name(param)?),
+                }
+                formals = *tail;
+            }
+            other @ Atom(_) => {
+                parsed.rest = Some(symbol_name(other)?);
+                return Ok(parsed);
+            }
+        }
     }
 }
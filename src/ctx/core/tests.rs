@@ -70,6 +70,28 @@ fn r#if() {
     assert!(eval(sexp![s("if"), false, s("potato"), "hooray"]).is_ok());
 }
 
+#[test]
+fn when() {
+    assert!(eval(sexp![s("when")]).is_err());
+    assert_eval_eq!(sexp![s("when"), true, 1, 2], 2);
+    assert_eval_eq!(sexp![s("when"), 0, 'a'], 'a');
+    assert_eval_eq!(sexp![s("when"), false, 1, 2], Primitive::Void);
+    // ensure the body is only evaluated when the test passes
+    assert!(eval(sexp![s("when"), false, s("potato")]).is_ok());
+    assert!(eval(sexp![s("when"), true, s("potato")]).is_err());
+}
+
+#[test]
+fn unless() {
+    assert!(eval(sexp![s("unless")]).is_err());
+    assert_eval_eq!(sexp![s("unless"), false, 1, 2], 2);
+    assert_eval_eq!(sexp![s("unless"), true, 1, 2], Primitive::Void);
+    assert_eval_eq!(sexp![s("unless"), 0, 1, 2], Primitive::Void);
+    // ensure the body is only evaluated when the test fails
+    assert!(eval(sexp![s("unless"), true, s("potato")]).is_ok());
+    assert!(eval(sexp![s("unless"), false, s("potato")]).is_err());
+}
+
 #[test]
 fn and() {
     // validate return value
@@ -145,6 +167,27 @@ fn cond() {
         ],
         true
     );
+    // receiver clauses
+    assert_eval_eq!(
+        sexp![
+            s("cond"),
+            sexp![false, s("=>"), s("car")],
+            sexp![sexp![s("list"), 4, 5], s("=>"), s("cdr")],
+            sexp![s("else"), 'a']
+        ],
+        sexp![5]
+    );
+    assert_eval_eq!(
+        sexp![
+            s("cond"),
+            sexp![
+                sexp![s("+"), 2, 3],
+                s("=>"),
+                sexp![s("lambda"), sexp![s("x")], sexp![s("*"), s("x"), s("x")]]
+            ]
+        ],
+        25
+    );
     // ensure that evaluation stops at first non-#f predicate
     assert!(eval(sexp![
         s("cond"),
@@ -294,3 +337,1087 @@ fn lambda() {
         121
     );
 }
+
+#[test]
+fn let_values() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run("(let-values (((q r) (floor/ 7 2))) (+ q r))").unwrap(),
+        SExp::from(4)
+    );
+    assert_eq!(
+        run("(let-values (((a) 1) (all (values 2 3)) (none (values))) (list a all none))").unwrap(),
+        sexp![1, sexp![2, 3], Null]
+    );
+    // the bindings of `let*-values` can see the ones before them
+    assert_eq!(
+        run("(let*-values (((a b) (values 1 2)) ((c) (+ a b))) c)").unwrap(),
+        SExp::from(3)
+    );
+    assert!(run("(let-values (((a b) (values 1 2)) ((c) (+ a b))) c)").is_err());
+    // the number of values must match
+    assert!(run("(let-values (((a b) (values 1 2 3))) a)").is_err());
+    assert!(run("(let-values (((a b) 1)) a)").is_err());
+
+    run("(define-values (x y) (values 'one 'two))").unwrap();
+    assert_eq!(run("(list y x)").unwrap(), sexp![s("two"), s("one")]);
+}
+
+#[test]
+fn parameterize() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    run("(define radix (make-parameter 10))").unwrap();
+    run("(define (show) (list (radix)))").unwrap();
+    assert_eq!(run("(radix)").unwrap(), SExp::from(10));
+    assert_eq!(run("(parameterize ((radix 2)) (show))").unwrap(), sexp![2]);
+    assert_eq!(
+        run("(parameterize ((radix 2)) (parameterize ((radix 16)) (radix)))").unwrap(),
+        SExp::from(16)
+    );
+    assert_eq!(run("(show)").unwrap(), sexp![10]);
+
+    // the old value is restored even if the body fails
+    assert!(run("(parameterize ((radix 8)) (car '()))").is_err());
+    assert_eq!(run("(radix)").unwrap(), SExp::from(10));
+
+    // the converter applies to the initial value and to rebinding
+    run("(define width (make-parameter 1 (lambda (x) (* x 10))))").unwrap();
+    assert_eq!(run("(width)").unwrap(), SExp::from(10));
+    assert_eq!(
+        run("(parameterize ((width 5)) (width))").unwrap(),
+        SExp::from(50)
+    );
+
+    assert!(run("(parameterize ((show 1)) 2)").is_err());
+}
+
+#[test]
+fn receive() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run("(receive (q r) (floor/ 7 2) (list q r))").unwrap(),
+        sexp![3, 1]
+    );
+    assert_eq!(
+        run("(receive (a . rest) (values 1 2 3) (list a rest))").unwrap(),
+        sexp![1, sexp![2, 3]]
+    );
+    assert_eq!(
+        run("(receive all (values 'x 'y) all)").unwrap(),
+        sexp![SExp::sym("x"), SExp::sym("y")]
+    );
+    assert_eq!(run("(receive (x) 5 (* x x))").unwrap(), SExp::from(25));
+    assert!(run("(receive (a b) (values 1) a)").is_err());
+    assert!(run("(receive (a) (values 1) a) a").is_err());
+}
+
+#[test]
+fn environments() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(run("(eval '(+ 1 2))").unwrap(), SExp::from(3));
+
+    run("(define (counter) (define n 0) (the-environment))").unwrap();
+    run("(define c (counter))").unwrap();
+    assert_eq!(run("(environment? c)").unwrap(), SExp::from(true));
+    assert_eq!(
+        run("(eval '(set! n (+ n 5)) c) (eval 'n c)").unwrap(),
+        SExp::from(5)
+    );
+    assert_eq!(run("(environment-bound? c 'n)").unwrap(), SExp::from(true));
+    assert_eq!(
+        run("(environment-bound? c 'car)").unwrap(),
+        SExp::from(true)
+    );
+    assert!(run("n").is_err());
+
+    // definitions go into the environment they're evaluated in
+    run("(define e (make-environment))").unwrap();
+    run("(eval '(define secret 42) e)").unwrap();
+    assert_eq!(run("(eval 'secret e)").unwrap(), SExp::from(42));
+    assert_eq!(run("(environment-bound? e 'c)").unwrap(), SExp::from(false));
+    assert!(run("secret").is_err());
+
+    // a child environment sees its parent's bindings
+    run("(define child (make-environment e))").unwrap();
+    assert_eq!(run("(eval '(* secret 2) child)").unwrap(), SExp::from(84));
+
+    run("(let ((x 1)) (eval '(define top 7) (interaction-environment)))").unwrap();
+    assert_eq!(run("top").unwrap(), SExp::from(7));
+
+    assert!(run("(eval 'x 5)").is_err());
+    assert_eq!(run("(eq? c c)").unwrap(), SExp::from(true));
+    assert_eq!(run("(eq? c (counter))").unwrap(), SExp::from(false));
+
+    // a closure's captured environment can be looked into and changed
+    run("(define (make-adder n) (lambda (x) (+ x n)))").unwrap();
+    run("(define add5 (make-adder 5))").unwrap();
+    assert_eq!(
+        run("(eval '(+ n 1) (procedure-environment add5))").unwrap(),
+        SExp::from(6)
+    );
+    run("(eval '(set! n 10) (procedure-environment add5))").unwrap();
+    assert_eq!(run("(add5 1)").unwrap(), SExp::from(11));
+    assert_eq!(
+        run("(environment-bound? (procedure-environment add5) 'x)").unwrap(),
+        SExp::from(false)
+    );
+    assert!(run("(procedure-environment car)").is_err());
+    assert!(run("(procedure-environment 5)").is_err());
+}
+
+#[test]
+fn do_loops() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run("(do ((i 0 (+ i 1)) (acc '() (cons i acc))) ((= i 3) acc))").unwrap(),
+        sexp![2, 1, 0]
+    );
+    // a variable with no step keeps its value
+    assert_eq!(
+        run("(do ((i 0 (+ i 1)) (k 10)) ((= i 3) (+ i k)))").unwrap(),
+        SExp::from(13)
+    );
+    // the result expressions are evaluated in order, and the last is returned
+    assert_eq!(
+        run("(define log '()) (do ((i 0 (+ i 1))) ((= i 2) (set! log (cons 'a log)) (cons 'b log)))")
+            .unwrap(),
+        sexp![SExp::sym("b"), SExp::sym("a")]
+    );
+    // with no result expressions, the result is unspecified
+    assert_eq!(run("(do ((i 0 (+ i 1))) ((= i 2)))").unwrap(), SExp::unit());
+    assert_eq!(
+        run("(define v (do ((i 0 (+ i 1))) ((= i 2)))) (list v)").unwrap(),
+        sexp![SExp::unit()]
+    );
+
+    for code in [
+        "(do ((i)) (#t))",
+        "(do ((i 0 1 2)) (#t))",
+        "(do (i) (#t))",
+        "(do ((i 0)) #t)",
+    ] {
+        match run(code) {
+            Err(Error::Syntax(SyntaxError::InvalidDoClause(_))) => (),
+            other => panic!("{}: {:?}", code, other),
+        }
+    }
+    assert!(run("(do ((5 0)) (#t))").is_err());
+}
+
+#[test]
+fn fluid_let() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    run("(define depth 0) (define (show) depth)").unwrap();
+    assert_eq!(
+        run("(fluid-let ((depth 1)) (show))").unwrap(),
+        SExp::from(1)
+    );
+    assert_eq!(
+        run("(fluid-let ((depth 1)) (fluid-let ((depth (+ depth 1))) (show)))").unwrap(),
+        SExp::from(2)
+    );
+    assert_eq!(run("depth").unwrap(), SExp::from(0));
+
+    // the old value is restored even if the body fails
+    assert!(run("(fluid-let ((depth 8)) (car '()))").is_err());
+    assert_eq!(run("depth").unwrap(), SExp::from(0));
+
+    // only existing variables can be rebound
+    assert!(run("(fluid-let ((nowhere 1)) 2)").is_err());
+}
+
+#[test]
+fn define_contract() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    run("(define (small? x) (< x 10))").unwrap();
+    run("(define/contract (inc x) (-> small? small?) (+ x 1))").unwrap();
+    assert_eq!(run("(inc 3)").unwrap(), SExp::from(4));
+
+    // bad arguments blame the caller
+    match run("(inc 20)") {
+        Err(Error::Contract { arg, blame, .. }) => {
+            assert_eq!(arg, Some(1));
+            assert_eq!(blame, "caller");
+        }
+        other => panic!("expected a contract violation, got {:?}", other),
+    }
+
+    // bad results blame the procedure
+    match run("(inc 9)") {
+        Err(Error::Contract {
+            arg, blame, given, ..
+        }) => {
+            assert_eq!(arg, None);
+            assert_eq!(blame, "inc");
+            assert_eq!(given, "10");
+        }
+        other => panic!("expected a contract violation, got {:?}", other),
+    }
+
+    // the contract needs a predicate for each parameter and the result
+    assert!(run("(define/contract (f x y) (-> small? small?) x)").is_err());
+    assert!(run("(define/contract (f x) (small? small?) x)").is_err());
+}
+
+#[test]
+fn variadic_lambda() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(run("((lambda args args) 1 2 3)").unwrap(), sexp![1, 2, 3]);
+    assert_eq!(run("((lambda args args))").unwrap(), Null);
+    assert_eq!(
+        run("((lambda (a b . rest) (list a b rest)) 1 2 3 4)").unwrap(),
+        sexp![1, 2, sexp![3, 4]]
+    );
+    assert_eq!(
+        run("((lambda (a . rest) (list a rest)) 1)").unwrap(),
+        sexp![1, Null]
+    );
+    assert!(run("((lambda (a b . rest) a) 1)").is_err());
+
+    run("(define (sum first . more) (if (null? more) first (+ first (apply sum more))))").unwrap();
+    assert_eq!(run("(sum 1 2 3 4)").unwrap(), SExp::from(10));
+    assert_eq!(run("(sum 5)").unwrap(), SExp::from(5));
+    assert_eq!(
+        run("(procedure-arity sum)").unwrap(),
+        SExp::from((1, false))
+    );
+
+    // other formals accept a rest parameter too
+    assert_eq!(
+        run("(let-values (((a . b) (values 1 2 3))) b)").unwrap(),
+        sexp![2, 3]
+    );
+}
+
+#[test]
+fn thunk_lambda() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(run("((lambda () 42))").unwrap(), SExp::from(42));
+    assert_eq!(run("(thunk? (lambda () 42))").unwrap(), SExp::from(true));
+    assert_eq!(
+        run("(procedure-arity (lambda () 42))").unwrap(),
+        SExp::from((0, 0))
+    );
+    assert!(run("((lambda () 42) 1)").is_err());
+
+    run("(define (seven) 7)").unwrap();
+    assert_eq!(run("(seven)").unwrap(), SExp::from(7));
+    assert_eq!(run("((named-lambda (three) 3))").unwrap(), SExp::from(3));
+}
+
+#[test]
+fn type_annotations() {
+    let mut ctx = Context::base();
+
+    // annotations are only recorded until the check is turned on
+    ctx.run("(: greet (-> string string))").unwrap();
+    assert_eq!(
+        ctx.type_annotation("greet"),
+        Some(sexp![s("->"), s("string"), s("string")])
+    );
+    ctx.run("(define (greet name) name)").unwrap();
+    assert_eq!(ctx.run("(greet 5)").unwrap(), SExp::from(5));
+
+    ctx.set_type_check(true);
+    match ctx.run("(list (greet \"you\") (greet 5))") {
+        Err(Error::Annotation {
+            name,
+            arg,
+            expected,
+            given,
+        }) => {
+            assert_eq!((name.as_str(), arg), ("greet", 1));
+            assert_eq!(expected, "string");
+            assert_eq!(given, "number 5");
+        }
+        other => panic!("expected a type error, got {:?}", other),
+    }
+
+    // annotations in the same code are seen before anything is evaluated
+    assert!(ctx
+        .run("(: flag (-> boolean symbol bool)) (define (flag b s) b) (flag #t \"s\")")
+        .is_err());
+    // ...and none of it is evaluated if the check fails
+    assert!(ctx.run("(flag #f 'off)").is_err());
+    // only literals are checked, and quoted data is left alone
+    assert!(ctx.run("(define n 5) (greet n)").is_ok());
+    assert!(ctx.run("'(greet 5)").is_ok());
+    // unknown types are not checked
+    assert!(ctx
+        .run("(: id (-> thing thing)) (define (id x) x) (id 1)")
+        .is_ok());
+}
+
+#[test]
+fn generic_dispatch() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    run("(define-generic describe)").unwrap();
+    run("(define-method (describe (n number)) (* n 2))").unwrap();
+    run("(define-method (describe (s string) suffix) (list s suffix))").unwrap();
+    assert_eq!(run("(describe 21)").unwrap(), SExp::from(42));
+    assert_eq!(
+        run("(describe \"ab\" 'cd)").unwrap(),
+        run("'(\"ab\" cd)").unwrap()
+    );
+
+    // record types dispatch on their name, with or without angle brackets
+    run("(define-record-type <point> (point x y) point? (x point-x) (y point-y))").unwrap();
+    run("(define-method (describe (p <point>)) (point-x p))").unwrap();
+    assert_eq!(run("(describe (point 3 4))").unwrap(), SExp::from(3));
+
+    match run("(describe #t)") {
+        Err(Error::NoMethod { name, given }) => {
+            assert_eq!((name.as_str(), given.as_str()), ("describe", "bool"));
+        }
+        other => panic!("expected a dispatch error, got {:?}", other),
+    }
+
+    // methods on `any` catch everything else
+    run("(define-method (describe (x any)) 'other)").unwrap();
+    assert_eq!(run("(describe #t)").unwrap(), SExp::sym("other"));
+    assert_eq!(run("(describe 1)").unwrap(), SExp::from(2));
+
+    assert!(run("(define-method (undeclared (x any)) x)").is_err());
+}
+
+#[test]
+fn optional_parameters() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    run("(define (f a #!optional b) (if (default-object? b) (list a) (list a b)))").unwrap();
+    assert_eq!(run("(f 1)").unwrap(), run("'(1)").unwrap());
+    assert_eq!(run("(f 1 2)").unwrap(), run("'(1 2)").unwrap());
+    assert!(run("(f)").is_err());
+    assert!(run("(f 1 2 3)").is_err());
+    assert_eq!(
+        run("(procedure-arity f)").unwrap(),
+        run("(procedure-arity (lambda (a #!optional b) a))").unwrap()
+    );
+
+    // default expressions can refer to earlier parameters
+    run("(define (g a #!optional (b (* a 2)) #!rest more) (list a b more))").unwrap();
+    assert_eq!(run("(g 3)").unwrap(), run("'(3 6 ())").unwrap());
+    assert_eq!(run("(g 3 4 5 6)").unwrap(), run("'(3 4 (5 6))").unwrap());
+
+    assert_eq!(run("#!default").unwrap(), Atom(Primitive::Default));
+    assert_eq!(
+        run("(let-values (((a #!optional (b 0)) (values 1))) (+ a b))").unwrap(),
+        SExp::from(1)
+    );
+}
+
+#[test]
+fn exceptions() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run("(guard (e ((eq? e 'a) 1) ((eq? e 'b) 2)) (raise 'b))").unwrap(),
+        SExp::from(2)
+    );
+    assert_eq!(
+        run("(guard (e ((and (eq? (car e) 'a) e) => cdr) (else 'other)) (raise (cons 'a 42)))")
+            .unwrap(),
+        SExp::from(42)
+    );
+    // errors from the interpreter are caught as conditions
+    assert_eq!(
+        run("(guard (e (#t e)) (car 5))").unwrap().type_of(),
+        "condition"
+    );
+    // ...and raised again as they were if no clause applies
+    assert!(matches!(
+        run("(guard (e ((eq? e 'a) 1)) (car 5))"),
+        Err(Error::NotAList { .. })
+    ));
+    match run("(guard (e ((eq? e 'a) 1)) (raise 'b))") {
+        Err(Error::Raised { value }) => assert_eq!(value, SExp::sym("b")),
+        other => panic!("expected a raised value, got {:?}", other),
+    }
+
+    // handlers are called where the value is raised
+    assert_eq!(
+        run("(with-exception-handler (lambda (e) 42) (lambda () (+ (raise-continuable 'oops) 1)))")
+            .unwrap(),
+        SExp::from(43)
+    );
+    run("(define seen '())").unwrap();
+    assert!(
+        run("(with-exception-handler (lambda (e) (set! seen e)) (lambda () (raise 'oops)))")
+            .is_err()
+    );
+    assert_eq!(run("seen").unwrap(), SExp::sym("oops"));
+    assert!(
+        run("(with-exception-handler (lambda (e) (set! seen 'error)) (lambda () (car 5)))")
+            .is_err()
+    );
+    assert_eq!(run("seen").unwrap(), SExp::sym("error"));
+
+    // a guard comes between a handler and the body
+    assert_eq!(
+        run("(with-exception-handler (lambda (e) 0) (lambda () (guard (e (#t 'guarded)) (raise-continuable 'x))))")
+            .unwrap(),
+        SExp::sym("guarded")
+    );
+    assert_eq!(
+        run("(with-exception-handler (lambda (e) 10) (lambda () (guard (e (#f 0)) (raise-continuable 'x))))")
+            .unwrap(),
+        SExp::from(10)
+    );
+    // escaping continuations are not caught
+    assert_eq!(
+        run("(call/cc (lambda (k) (guard (e (#t 'caught)) (k 'escaped))))").unwrap(),
+        SExp::sym("escaped")
+    );
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn async_host() {
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::task::{Context as TaskContext, Poll, Waker};
+
+    use super::super::super::Resource;
+    use super::super::AsyncHost;
+
+    /// A future which is pending the first time it is polled.
+    struct Later(Option<Result>, bool);
+
+    impl Future for Later {
+        type Output = Result;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Result> {
+            if self.1 {
+                Poll::Ready(self.0.take().unwrap())
+            } else {
+                self.1 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    struct Host(Vec<String>);
+
+    impl AsyncHost for Host {
+        fn start(&mut self, request: SExp) -> Pin<Box<dyn Future<Output = Result>>> {
+            self.0.push(request.to_string());
+            let result = match request {
+                Atom(Primitive::Number(n)) => Ok(SExp::from(n * 10.)),
+                Pair { .. } => Ok(Atom(Primitive::Void)),
+                other => Err(Error::Type {
+                    expected: "number",
+                    given: other.type_of().to_string(),
+                }),
+            };
+            Box::pin(Later(Some(result), false))
+        }
+    }
+
+    let block_on = |ctx: &mut Context, host: &mut Host, code: &str| {
+        let mut eval = pin!(ctx.eval_with_async_host(code.parse().unwrap(), host));
+        let mut cx = TaskContext::from_waker(Waker::noop());
+        let mut pending = 0;
+        loop {
+            match eval.as_mut().poll(&mut cx) {
+                Poll::Ready(result) => break (result, pending),
+                Poll::Pending => pending += 1,
+            }
+        }
+    };
+
+    let mut ctx = Context::base().capturing();
+    let mut host = Host(Vec::new());
+    let (result, pending) = block_on(
+        &mut ctx,
+        &mut host,
+        "(define a (await 1)) (display a) (define b (await (+ a 1))) (list a b)",
+    );
+    assert_eq!(result.unwrap(), sexp![10, 110]);
+    assert_eq!(pending, 2);
+    // each request is only started once, and the output is not repeated
+    assert_eq!(host.0, vec!["1", "11.0"]);
+    assert_eq!(ctx.get_output().unwrap(), "10.0");
+    assert_eq!(ctx.run("b").unwrap(), SExp::from(110));
+
+    // errors from the host are raised from the `await`
+    let (result, _) = block_on(&mut ctx, &mut host, "(guard (e (#t 'failed)) (await 'x))");
+    assert_eq!(result.unwrap(), SExp::sym("failed"));
+    assert!(block_on(&mut ctx, &mut host, "(await 'x)").0.is_err());
+
+    assert!(matches!(ctx.run("(await 1)"), Err(Error::Suspended)));
+
+    // the host does the waiting for `sleep`, and timers are not repeated
+    ctx.capture();
+    let (result, pending) = block_on(
+        &mut ctx,
+        &mut host,
+        "(after 5 (lambda () (display \"tick\"))) (sleep 5) (await 2)",
+    );
+    assert_eq!(result.unwrap(), SExp::from(20));
+    assert_eq!(pending, 2);
+    assert_eq!(&host.0[host.0.len() - 2..], ["(sleep 5)", "2"]);
+    assert_eq!(ctx.get_output().unwrap(), "tick");
+    assert_eq!(ctx.next_timer(), None);
+
+    // side effects happen exactly once across two awaits: those which can be
+    // rolled back are, and evaluation stops at an await after any other
+    ctx.capture();
+    ctx.run("(define n 0) (define s (set))").unwrap();
+    ctx.run("(define tick (let ((k 0)) (lambda () (set! k (+ k 1)) k)))")
+        .unwrap();
+    let (result, _) = block_on(
+        &mut ctx,
+        &mut host,
+        "(set! n (+ n 1)) (display n) (await 1) (set! n (+ n 1)) (display n) (await 2) \
+         (set-add! s n) (tick)",
+    );
+    assert_eq!(result.unwrap(), SExp::from(1));
+    assert_eq!(ctx.get_output().unwrap(), "12");
+    assert_eq!(
+        ctx.run("(list n (set->list s))").unwrap(),
+        sexp![2, sexp![2]]
+    );
+
+    for effect in ["(set-add! s 3)", "(tick)", "(close-resource r)"] {
+        ctx.define("r", Resource::new("file", ()).into());
+        let code = format!("(await 1) {effect} (await 2)");
+        let (result, pending) = block_on(&mut ctx, &mut host, &code);
+        assert!(
+            matches!(result, Err(Error::SideEffect)),
+            "{}: {:?}",
+            effect,
+            result
+        );
+        assert_eq!(pending, 1);
+    }
+    let state = "(list (set-size s) (tick) (resource-closed? r))";
+    assert_eq!(ctx.run(state).unwrap(), sexp![2, 3, true]);
+
+    // nothing is tracked once the evaluation is over
+    assert_eq!(super::super::super::effect::new_scope(), 0);
+}
+
+#[test]
+fn timers() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code).unwrap();
+
+    run("(define log '())");
+    run("(define (logger x) (lambda () (set! log (cons x log))))");
+    run("(after 10 (logger 'a)) (after 5 (logger 'b)) (after 10 (logger 'c))");
+    run("(after 20 (lambda () (after 0 (logger 'd))))");
+
+    ctx.advance_clock(9).unwrap();
+    assert_eq!(ctx.run("log").unwrap(), sexp![SExp::sym("b")]);
+    ctx.advance_clock(11).unwrap();
+    // newest first
+    assert_eq!(ctx.run("log").unwrap(), ctx.run("'(d c a b)").unwrap());
+    assert_eq!(ctx.next_timer(), None);
+
+    // sleeping advances the clock
+    ctx.run("(set! log '()) (after 1 (logger 'e)) (sleep 2)")
+        .unwrap();
+    assert_eq!(ctx.run("log").unwrap(), sexp![SExp::sym("e")]);
+    assert!(ctx.run("(sleep -1)").is_err());
+}
+
+#[test]
+fn letrec() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run("(letrec ((ev? (lambda (n) (if (= n 0) #t (od? (- n 1)))))
+                      (od? (lambda (n) (if (= n 0) #f (ev? (- n 1))))))
+                (list (ev? 10) (od? 7) (ev? 3)))")
+        .unwrap(),
+        sexp![true, true, false]
+    );
+    // closures see bindings made after them in the same frame
+    assert_eq!(
+        run("(define x 'outer) (letrec ((f (lambda () x)) (x 'inner)) (f))").unwrap(),
+        SExp::sym("inner")
+    );
+    assert_eq!(
+        run("(letrec* ((a 1) (b (+ a 1))) (list a b))").unwrap(),
+        sexp![1, 2]
+    );
+    // in `letrec`, no binding is initialized until all the inits are done
+    assert!(run("(letrec ((a 1) (b (+ a 1))) b)").is_err());
+    assert!(run("(letrec* ((b (+ a 1)) (a 1)) b)").is_err());
+    // the frame is gone afterwards
+    assert!(run("(letrec ((y 1)) y) y").is_err());
+}
+
+#[test]
+fn shared_mutable_frames() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    // each counter has its own frame, which persists between calls
+    assert_eq!(
+        run(
+            "(define (make-counter) (let ((n 0)) (lambda () (set! n (+ n 1)) n)))
+             (define c1 (make-counter))
+             (define c2 (make-counter))
+             (c1) (c1) (c2)
+             (list (c1) (c2))"
+        )
+        .unwrap(),
+        sexp![3, 2]
+    );
+    // closures made in the same frame see each other's assignments
+    assert_eq!(
+        run("(define (make-account balance)
+               (list (lambda (n) (set! balance (+ balance n)))
+                     (lambda () balance)))
+             (define acct (make-account 10))
+             ((car acct) 5)
+             ((car (cdr acct)))")
+        .unwrap(),
+        SExp::from(15)
+    );
+    assert_eq!(
+        run(
+            "(letrec ((bump! (lambda () (set! total (+ total 1)))) (total 0))
+               (bump!) (bump!) total)"
+        )
+        .unwrap(),
+        SExp::from(2)
+    );
+    // as do closures over the top-level
+    assert_eq!(
+        run("(define y 1) (define (get-y) y) (set! y 2) (get-y)").unwrap(),
+        SExp::from(2)
+    );
+}
+
+#[test]
+fn internal_defines() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(
+        run("(define (f n)
+               (define (ev? n) (if (= n 0) #t (od? (- n 1))))
+               (define (od? n) (if (= n 0) #f (ev? (- n 1))))
+               (list (ev? n) (od? n)))
+             (f 7)")
+        .unwrap(),
+        sexp![false, true]
+    );
+    // an internal define shadows the outer binding for the whole body
+    assert!(run("(define x 'outer) (define (g) (define y x) (define x 'inner) y) (g)").is_err());
+    assert_eq!(
+        run("(define (h) (define (get) x) (define x 'inner) (get)) (h)").unwrap(),
+        SExp::sym("inner")
+    );
+    // parameters are not hoisted over
+    assert_eq!(
+        run("((lambda (x) (define x (+ x 1)) x) 1)").unwrap(),
+        SExp::from(2)
+    );
+    assert_eq!(
+        run("(let ((a 1)) (define (b) c) (define c (+ a 1)) (b))").unwrap(),
+        SExp::from(2)
+    );
+    // the hoisted names don't leak
+    assert!(run("(let () (define z 1) z) z").is_err());
+}
+
+#[test]
+fn use_before_initialization() {
+    let mut ctx = Context::base();
+    let mut message = |code| ctx.run(code).unwrap_err().to_string();
+
+    assert_eq!(
+        message("(define (g) (define y x) (define x 'inner) y) (g)"),
+        "Variable `x` used before initialization, in the definition of `y`"
+    );
+    // including by a procedure called from the init
+    assert_eq!(
+        message(
+            "(define (k)
+               (define (get) b)
+               (define a (get))
+               (define b 1)
+               a)
+             (k)"
+        ),
+        "Variable `b` used before initialization, in the definition of `a`"
+    );
+    assert_eq!(
+        message("(letrec* ((b (+ a 1)) (a 1)) b)"),
+        "Variable `a` used before initialization, in the definition of `b`"
+    );
+    assert_eq!(
+        message("(let () (define c) c)"),
+        "Variable `c` used before initialization"
+    );
+    // names which aren't bound at all are still undefined
+    assert_eq!(
+        message("(define (m) (define a nowhere) a) (m)"),
+        "Undefined symbol: nowhere"
+    );
+
+    assert!(matches!(
+        ctx.run("(define (n) (define p q) (define q 1) p) (n)"),
+        Err(Error::Uninitialized { sym, defining: Some(defining) })
+            if sym == "q" && defining == "p"
+    ));
+    assert_eq!(
+        ctx.run("(guard (e (#t (error-object-message e))) (letrec ((r r)) r))")
+            .unwrap(),
+        SExp::from("Variable `r` used before initialization, in the definition of `r`")
+    );
+}
+
+#[test]
+fn shadowing_special_forms() {
+    let mut ctx = Context::base();
+
+    assert_eq!(
+        ctx.run("(let ((if list)) (if 1 2 3))").unwrap(),
+        sexp![1, 2, 3]
+    );
+    assert_eq!(
+        ctx.run("((lambda (quote) (quote 4)) (lambda (x) (* x x)))")
+            .unwrap(),
+        SExp::from(16)
+    );
+    assert_eq!(
+        ctx.run("(define (f) (define (and . xs) xs) (and #f #f)) (f)")
+            .unwrap(),
+        sexp![false, false]
+    );
+    // the special form is back outside of the scope
+    assert_eq!(ctx.run("(if 1 2 3)").unwrap(), SExp::from(2));
+    // top-level definitions do not shadow special forms
+    ctx.run("(define or list)").unwrap();
+    assert_eq!(ctx.run("(or #f 5)").unwrap(), SExp::from(5));
+}
+
+#[test]
+fn undefined_suggestions() {
+    let mut ctx = Context::base();
+    ctx.run("(define (fibonacci n) n) (define counter 0)")
+        .unwrap();
+
+    let suggestions = |ctx: &mut Context, code| ctx.run(code).unwrap_err().suggestions().to_vec();
+    assert_eq!(suggestions(&mut ctx, "(fibonaci 3)"), ["fibonacci"]);
+    assert_eq!(suggestions(&mut ctx, "(set! countr 1)"), ["counter"]);
+    assert_eq!(suggestions(&mut ctx, "(lamda (x) x)"), ["lambda"]);
+    // names too short to guess at, or nothing close
+    assert!(suggestions(&mut ctx, "q").is_empty());
+    assert!(suggestions(&mut ctx, "(potato)").is_empty());
+
+    assert_eq!(
+        ctx.run("(lett ((x 1)) x)").unwrap_err().to_string(),
+        "Undefined symbol: lett (did you mean `let` or `let*`?)"
+    );
+}
+
+#[test]
+fn begin_splicing() {
+    let mut ctx = Context::base();
+
+    ctx.run("(begin (define a 1) (begin (define b 2)))")
+        .unwrap();
+    assert_eq!(ctx.get("a"), Some(SExp::from(1)));
+    assert_eq!(ctx.run("(+ a b)").unwrap(), SExp::from(3));
+    assert_eq!(ctx.changes_since(0), vec!["a", "b"]);
+
+    // in a body, `begin` splices into the body's scope
+    assert_eq!(
+        ctx.run("(let () (begin (define c 3) (define d 4)) (+ c d))")
+            .unwrap(),
+        SExp::from(7)
+    );
+    assert!(ctx.run("c").is_err());
+    // and its definitions are hoisted with the others
+    assert!(ctx
+        .run("(define (f) (define y a) (begin (define a 'inner)) y) (f)")
+        .is_err());
+}
+
+#[test]
+fn tail_positions() {
+    let mut ctx = Context::base();
+
+    // each of these would overflow the stack if the recursive call were not
+    // in tail position
+    let n = 10_000;
+    for (name, body) in [
+        ("and", "(and #t (f (- n 1)))"),
+        ("or", "(or #f (f (- n 1)))"),
+        ("when", "(when #t (f (- n 1)))"),
+        ("unless", "(unless #f (f (- n 1)))"),
+        ("begin", "(begin 1 (f (- n 1)))"),
+        ("cond", "(cond (#f 1) (else (f (- n 1))))"),
+        ("case", "(case 1 ((1) (f (- n 1))))"),
+        (
+            "lowered-case",
+            "(case 1 ((1) (f (- n 1))) ((2) 2) ((3) 3) ((4) 4) ((5) 5) ((6) 6) ((7) 7) ((8) 8))",
+        ),
+        (
+            "lowered-cond",
+            "(let ((k 1))
+               (cond ((= k 1) (f (- n 1))) ((= k 2) 2) ((= k 3) 3) ((= k 4) 4)
+                     ((= k 5) 5) ((= k 6) 6) ((= k 7) 7) ((= k 8) 8)))",
+        ),
+        ("let", "(let ((m (- n 1))) (f m))"),
+        ("let*", "(let* ((m (- n 1))) (f m))"),
+        ("letrec", "(letrec ((m (- n 1))) (f m))"),
+        ("named-let", "(let loop ((m (- n 1))) (f m))"),
+        ("do", "(do ((i 0 (+ i 1))) ((= i 1) (f (- n 1))))"),
+    ] {
+        let code = format!("(define (f n) (if (= n 0) '{name} {body})) (f {n})");
+        let result = ctx.run(&code);
+        assert!(result.is_ok(), "{}: {:?}", name, result);
+    }
+
+    let mut run = |code| ctx.run(code);
+
+    assert_eq!(run("(and)").unwrap(), SExp::from(true));
+    assert_eq!(run("(and 1 #f 2)").unwrap(), SExp::from(false));
+    assert_eq!(run("(and 1 2)").unwrap(), SExp::from(2));
+    assert_eq!(run("(or)").unwrap(), SExp::from(false));
+    assert_eq!(run("(or #f 3 (car '()))").unwrap(), SExp::from(3));
+    assert_eq!(run("(or #f #f)").unwrap(), SExp::from(false));
+    assert_eq!(
+        run("(let loop ((i 0) (acc '())) (if (= i 3) acc (loop (+ i 1) (cons i acc))))").unwrap(),
+        sexp![2, 1, 0]
+    );
+}
+
+#[test]
+fn tail_call_frame_reuse() {
+    let mut ctx = Context::base();
+
+    // a loop which calls itself keeps using the same frame
+    let (result, report) =
+        ctx.run_metered("(let loop ((i 0) (acc 0)) (if (= i 1000) acc (loop (+ i 1) (+ acc i))))");
+    assert_eq!(result.unwrap(), SExp::from(499_500));
+    assert!(report.allocations < 5, "{} frames", report.allocations);
+
+    ctx.run("(define (count-down n) (if (= n 0) 'done (count-down (- n 1))))")
+        .unwrap();
+    let (result, report) = ctx.run_metered("(count-down 1000)");
+    assert_eq!(result.unwrap(), SExp::sym("done"));
+    assert!(report.allocations < 5, "{} frames", report.allocations);
+
+    // but not a frame which a closure has captured
+    assert_eq!(
+        ctx.run(
+            "(define thunks
+               (let loop ((i 0) (acc '()))
+                 (if (= i 3) acc (loop (+ i 1) (cons (lambda () i) acc)))))
+             (map (lambda (f) (f)) thunks)"
+        )
+        .unwrap(),
+        sexp![2, 1, 0]
+    );
+    // nor one which is still needed after the call
+    assert_eq!(
+        ctx.run("(define (f x) (list x (g (+ x 1)) x)) (define (g y) y) (f 1)")
+            .unwrap(),
+        sexp![1, 2, 1]
+    );
+}
+
+#[test]
+fn named_let_scope() {
+    let mut ctx = Context::base();
+
+    // the scope a named let appears in is freed once the loop is done with
+    // it, rather than being kept alive by the loop procedure
+    ctx.run(
+        "(define scope #f)
+         (define (f n)
+           (set! scope (make-weak-ref (the-environment)))
+           (let lp ((i 0)) (if (< i n) (lp (+ i 1)) i)))",
+    )
+    .unwrap();
+    assert_eq!(ctx.run("(f 10)").unwrap(), SExp::from(10));
+    assert_eq!(
+        ctx.run("(weak-ref-deref scope)").unwrap(),
+        SExp::from(false)
+    );
+
+    // the inits are evaluated outside of the loop, and the variables can
+    // shadow its name
+    assert_eq!(
+        ctx.run("(define lp 5) (let lp ((x lp)) x)").unwrap(),
+        SExp::from(5)
+    );
+    assert_eq!(ctx.run("(let lp ((lp 1)) lp)").unwrap(), SExp::from(1));
+}
+
+#[cfg(feature = "vectors")]
+#[test]
+fn vector_literals() {
+    let mut ctx = Context::base();
+    let mut run = |code| ctx.run(code);
+
+    // vectors are constants whether or not they are quoted
+    let abc = SExp::from(Primitive::Vector(vec![s("a"), s("b"), s("c")]));
+    assert_eq!(run("#(a b c)").unwrap(), abc);
+    assert_eq!(run("'#(a b c)").unwrap(), abc);
+    assert_eq!(run("(quote #(a b c))").unwrap(), abc);
+    assert_eq!(
+        run("#((+ 1 2))").unwrap(),
+        SExp::from(Primitive::Vector(vec![sexp![s("+"), 1, 2]]))
+    );
+    assert_eq!(run("(equal? #(1 2) '#(1 2))").unwrap(), SExp::from(true));
+    assert_eq!(run("(vector-ref #(a b c) 1)").unwrap(), s("b"));
+
+    // quasiquote fills in vector templates
+    assert_eq!(
+        run("(define x 5) `#(x ,x)").unwrap(),
+        SExp::from(Primitive::Vector(vec![s("x"), SExp::from(5)]))
+    );
+
+    // vectors cannot be applied
+    assert!(matches!(
+        run("(#(1 2) 0)"),
+        Err(Error::NotAProcedure { ref exp }) if exp == "#(1 2)"
+    ));
+}
+
+#[test]
+fn case_clauses() {
+    let mut ctx = Context::base();
+    let mut run = |code: &str| ctx.run(code);
+
+    assert_eq!(
+        run("(case (* 2 3) ((2 3 5 7) 'prime) ((1 4 6 8 9) 'composite))").unwrap(),
+        s("composite")
+    );
+    // lists and vectors are compared with `equal?`, and anything else with
+    // `eqv?`, so strings never match
+    assert_eq!(
+        run("(case (list 'a) (((a)) 'list) (else 'other))").unwrap(),
+        s("list")
+    );
+    assert_eq!(
+        run("(case (list 1 2) ((1 2) 'atoms) ((#(1 2) (1 3)) 'list) ((#(1 2) (1 2)) 'pair))")
+            .unwrap(),
+        s("pair")
+    );
+    assert_eq!(
+        run("(case '#(1 2) (((1 2)) 'list) ((#(1 2)) 'vector))").unwrap(),
+        s("vector")
+    );
+    assert_eq!(
+        run("(case '(a) (((b) (a b)) 'list) (else 'other))").unwrap(),
+        s("other")
+    );
+    assert_eq!(
+        run("(case \"a\" ((\"a\") 'string) (else 'other))").unwrap(),
+        s("other")
+    );
+    assert_eq!(run("(case #\\a ((#\\a) 'char))").unwrap(), s("char"));
+    // no match, including for a key which is a list
+    assert_eq!(
+        run("(case '(1 2) ((1) 'one))").unwrap(),
+        SExp::from(Primitive::Undefined)
+    );
+
+    // receiver clauses are called with the key
+    assert_eq!(
+        run("(case 5 ((1 2 3) 'small) ((4 5 6) => (lambda (n) (* n n))))").unwrap(),
+        SExp::from(25)
+    );
+    assert_eq!(
+        run("(case 'x ((a) 1) (else => (lambda (k) (list k 'unknown))))").unwrap(),
+        sexp![s("x"), s("unknown")]
+    );
+
+    // with enough clauses, symbols, characters and fixnums are looked up by
+    // hashing, and anything else is still compared in turn, in clause order
+    run("(define (kind x)
+           (case x
+             ((a b) 'symbol) ((#\\a) 'char) ((1 2) 'fixnum) ((2.5) 'flonum)
+             (((1 2) #(1 2)) 'compound) ((\"s\") 'string) ((()) 'null)
+             ((#t) 'true) ((b (1 2) 1) 'shadowed) (else 'other)))")
+    .unwrap();
+    for (x, kind) in [
+        ("'a", "symbol"),
+        ("'b", "symbol"),
+        ("#\\a", "char"),
+        ("2", "fixnum"),
+        ("2.0", "other"),
+        ("2.5", "flonum"),
+        ("'(1 2)", "compound"),
+        ("'#(1 2)", "compound"),
+        ("'(1 3)", "other"),
+        ("\"s\"", "other"),
+        ("'()", "null"),
+        ("#t", "true"),
+        ("#f", "other"),
+        ("'c", "other"),
+    ] {
+        assert_eq!(run(&format!("(kind {x})")).unwrap(), s(kind), "{x}");
+    }
+}
+
+#[test]
+fn lowered_dispatch() {
+    let mut ctx = Context::base();
+    let mut run = |code: &str| ctx.run(code);
+
+    // a `cond` whose first clauses compare a variable to constants is
+    // dispatched by a table, and any clauses after them are tried in turn
+    run("(define (digit n)
+           (cond ((= n 0) 'zero) ((= n 1) 'one) ((= n 2) 'two) ((= n 3) 'three)
+                 ((= n 4) 'four) ((= n 5) 'five) ((= n 6) 'six) ((= n 1) 'again)
+                 ((< n 0) 'negative)
+                 (else (list n 'other))))")
+    .unwrap();
+    assert_eq!(run("(digit 1)").unwrap(), s("one"));
+    assert_eq!(run("(digit 6)").unwrap(), s("six"));
+    assert_eq!(run("(digit -3)").unwrap(), s("negative"));
+    assert_eq!(run("(digit 9)").unwrap(), sexp![9, s("other")]);
+    // `=` still compares inexact numbers, and rejects anything else
+    assert_eq!(run("(digit 2.0)").unwrap(), s("two"));
+    assert!(run("(digit 'x)").is_err());
+
+    run("(define (sound x)
+           (cond ((eq? x 'cat) 'meow) ((eq? x 'dog) 'woof) ((eq? x #\\c) 'char)
+                 ((eq? x 1) 'one) ((eq? x '()) 'nothing) ((eq? x #t) 'yes)
+                 ((eq? x 'cow) 'moo) ((eq? x 'owl) => (lambda (t) t))))")
+    .unwrap();
+    assert_eq!(run("(sound 'dog)").unwrap(), s("woof"));
+    assert_eq!(run("(sound #\\c)").unwrap(), s("char"));
+    assert_eq!(run("(sound '())").unwrap(), s("nothing"));
+    assert_eq!(run("(sound 'owl)").unwrap(), SExp::from(true));
+    assert_eq!(run("(sound 1.0)").unwrap(), SExp::from(Primitive::Void));
+    assert_eq!(run("(sound \"cat\")").unwrap(), SExp::from(Primitive::Void));
+
+    // redefining the comparison is seen by a procedure made before
+    run("(define (= a b) (eqv? b 3))").unwrap();
+    assert_eq!(run("(digit 1)").unwrap(), s("three"));
+}
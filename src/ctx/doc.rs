@@ -0,0 +1,191 @@
+use std::fmt::Write;
+
+use super::super::Primitive::{Boolean, Procedure, Symbol, Undefined};
+use super::super::SExp::{self, Atom};
+use super::super::{Ns, Result};
+use super::Context;
+
+/// A summary of a binding available in a context, generated from the
+/// registration metadata of its value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindingDoc {
+    pub name: String,
+    /// `special form` for core syntax, otherwise the type of the bound value.
+    pub kind: String,
+    /// The minimum and (if limited) maximum number of arguments accepted, if
+    /// the value is a procedure.
+    pub arity: Option<(usize, Option<usize>)>,
+    /// The parameters a lambda was defined with, e.g. `(lambda (x . rest) ...)`.
+    pub signature: Option<String>,
+    /// The documentation string of a procedure, if it was given one.
+    pub doc: Option<String>,
+}
+
+impl BindingDoc {
+    pub(super) fn new(name: &str, value: &SExp, is_core: bool) -> Self {
+        let (arity, signature, doc) = match value {
+            Atom(Procedure(p)) => (
+                Some(p.arity_bounds()),
+                p.signature().map(|s| s.to_string()),
+                p.doc().map(String::from),
+            ),
+            _ => (None, None, None),
+        };
+
+        Self {
+            name: name.to_string(),
+            kind: if is_core {
+                "special form".to_string()
+            } else {
+                value.type_of().to_string()
+            },
+            arity,
+            signature,
+            doc,
+        }
+    }
+
+    /// A few lines describing the binding: its name and kind, then its
+    /// signature (or failing that its arity), then its documentation.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut text = format!("{}: {}\n", self.name, self.kind);
+
+        if let Some(signature) = &self.signature {
+            let _ = writeln!(text, "  {signature}");
+        } else if let Some(arity) = self.arity_text() {
+            let _ = writeln!(text, "  takes {arity} argument(s)");
+        }
+        if let Some(doc) = &self.doc {
+            let _ = writeln!(text, "  {doc}");
+        }
+
+        text
+    }
+
+    /// Describe the arity in words, e.g. "1", "1 to 3" or "at least 2".
+    #[must_use]
+    pub fn arity_text(&self) -> Option<String> {
+        self.arity.map(|arity| match arity {
+            (min, Some(max)) if min == max => min.to_string(),
+            (min, Some(max)) => format!("{min} to {max}"),
+            (min, None) => format!("at least {min}"),
+        })
+    }
+}
+
+impl From<BindingDoc> for SExp {
+    fn from(doc: BindingDoc) -> Self {
+        let arity = match doc.arity {
+            Some((min, Some(max))) => (min, max).into(),
+            Some((min, None)) => (min, false).into(),
+            None => Atom(Boolean(false)),
+        };
+
+        sexp![Atom(Symbol(doc.name)), doc.kind, arity]
+    }
+}
+
+fn collect(docs: &mut Vec<BindingDoc>, ns: &Ns, is_core: bool) {
+    for (name, value) in ns {
+        if docs.iter().all(|d| &d.name != name) {
+            docs.push(BindingDoc::new(name, value, is_core));
+        }
+    }
+}
+
+impl Context {
+    /// List every binding visible from the current scope, sorted by name.
+    ///
+    /// Shadowed definitions are omitted, following the lookup order described
+    /// in [`get`](#method.get).
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let ctx = Context::base();
+    ///
+    /// let car = ctx.bindings().into_iter().find(|b| b.name == "car").unwrap();
+    /// assert_eq!(car.kind, "procedure");
+    /// assert_eq!(car.arity, Some((1, Some(1))));
+    /// ```
+    #[must_use]
+    pub fn bindings(&self) -> Vec<BindingDoc> {
+        let mut docs = Vec::new();
+
+        let envt = self.cont.borrow().env();
+        let (local, global): (Vec<_>, Vec<_>) = envt.iter().partition(|e| e.parent().is_some());
+
+        for envt in local {
+            collect(&mut docs, &envt.local(), false);
+        }
+        collect(&mut docs, &self.core, true);
+        for envt in global {
+            collect(&mut docs, &envt.local(), false);
+        }
+        collect(&mut docs, &self.lang, false);
+
+        docs.sort_by(|a, b| a.name.cmp(&b.name));
+        docs
+    }
+
+    /// Describe the binding visible from the current scope with the given
+    /// name, as shown by `help`.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    /// ctx.run(r#"(define (twice x) "Double a number." (* x 2))"#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     ctx.describe("twice").unwrap(),
+    ///     "twice: procedure\n  (lambda (x) ...)\n  Double a number.\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn describe(&self, name: &str) -> Option<String> {
+        self.bindings()
+            .into_iter()
+            .find(|b| b.name == name)
+            .map(|b| b.describe())
+    }
+
+    /// `(help name)` or `(help expr)`: print the description of a binding, or
+    /// of a value (like an anonymous procedure) if the argument is not a
+    /// symbol. Special forms can be described by name without quoting them.
+    pub(super) fn eval_help(&mut self, expr: SExp) -> Result {
+        let value = match expr.car()? {
+            Atom(Symbol(name)) => Atom(Symbol(name)),
+            other => self.eval(other)?,
+        };
+
+        let text = match value {
+            Atom(Symbol(name)) => match self.describe(&name) {
+                Some(text) => text,
+                None => return Err(self.undefined(&name)),
+            },
+            value => BindingDoc::new(&value.to_string(), &value, false).describe(),
+        };
+        self.write_output(&text)?;
+
+        Ok(Atom(Undefined))
+    }
+
+    /// Render the [bindings](#method.bindings) of this context as a markdown
+    /// table.
+    #[must_use]
+    pub fn bindings_markdown(&self) -> String {
+        let mut out = String::from("| Name | Kind | Arity |\n| --- | --- | --- |\n");
+
+        for doc in self.bindings() {
+            // names like `<=` and `string->list` read fine as inline code,
+            // but `|` would end the cell
+            let name = doc.name.replace('|', "\\|");
+            let arity = doc.arity_text().unwrap_or_default();
+            let _ = writeln!(out, "| `{name}` | {} | {arity} |", doc.kind);
+        }
+
+        out
+    }
+}
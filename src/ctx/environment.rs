@@ -0,0 +1,136 @@
+use std::rc::Rc;
+
+use super::super::Primitive::{Env as EnvPrim, Procedure};
+use super::super::SExp::{self, Atom, Null};
+use super::super::{Env, Error, Result};
+use super::core::symbol_name;
+use super::{BindingDoc, Context};
+
+fn environment(exp: &SExp) -> std::result::Result<Rc<Env>, Error> {
+    match exp {
+        Atom(EnvPrim(envt)) => Ok(envt.clone()),
+        other => Err(Error::Type {
+            expected: "environment",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+impl Context {
+    /// Evaluate an expression in a first-class environment (such as the value
+    /// of `(the-environment)`) instead of the current one. Core and
+    /// language-level definitions are visible from every environment.
+    ///
+    /// # Errors
+    /// Returns `Err` if `envt` is not an environment, or if evaluating the
+    /// expression fails.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// let envt = ctx.run("(let ((x 2)) (the-environment))").unwrap();
+    /// let expr = sexp![SExp::sym("*"), SExp::sym("x"), 21];
+    /// assert_eq!(ctx.eval_in_env(expr, &envt).unwrap(), SExp::from(42));
+    ///
+    /// // `x` was only bound in the `let`
+    /// assert!(ctx.run("x").is_err());
+    /// assert!(ctx.eval_in_env(SExp::sym("x"), &SExp::from(5)).is_err());
+    /// ```
+    pub fn eval_in_env(&mut self, expr: SExp, envt: &SExp) -> Result {
+        let envt = environment(envt)?;
+
+        self.push_cont();
+        self.use_env(envt);
+        let result = self.eval(expr);
+        self.pop_cont();
+
+        result
+    }
+
+    /// `(eval expr [environment])`: evaluate the value of `expr`, in the
+    /// current environment if no other is given.
+    pub(super) fn eval_eval(&mut self, expr: SExp) -> Result {
+        let (expr, rest) = self.eval_args(expr)?.split_car()?;
+
+        match rest.car() {
+            Ok(envt) => self.eval_in_env(expr, &envt),
+            Err(_) => self.eval(expr),
+        }
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(super) fn the_environment(&mut self, _: SExp) -> Result {
+        Ok(Atom(EnvPrim(self.cont.borrow().env())))
+    }
+
+    /// `(interaction-environment)`: the top level, where the REPL and `run`
+    /// make their definitions.
+    #[allow(clippy::unnecessary_wraps)]
+    pub(super) fn interaction_environment(&mut self, _: SExp) -> Result {
+        Ok(Atom(EnvPrim(self.global_env())))
+    }
+
+    /// `(make-environment [parent])`: a new, empty environment, which
+    /// inherits the bindings of `parent` if it is given.
+    pub(super) fn make_environment(&mut self, expr: SExp) -> Result {
+        let parent = match self.eval_args(expr)?.car() {
+            Ok(parent) => Some(environment(&parent)?),
+            Err(_) => None,
+        };
+
+        Ok(Atom(EnvPrim(Env::new(parent).into_rc())))
+    }
+
+    /// `(environment-tree [environment])`: the scopes of an environment (the
+    /// current one by default) as nested association lists, outermost first:
+    /// `((bindings (name kind arity) ...) (inner bindings ...))`. Bindings are
+    /// summarized as by `bindings`, rather than including their values.
+    pub(super) fn environment_tree(&mut self, expr: SExp) -> Result {
+        let envt = match self.eval_args(expr)?.car() {
+            Ok(envt) => environment(&envt)?,
+            Err(_) => self.cont.borrow().env(),
+        };
+
+        // start from the innermost scope and wrap each one in its parent
+        Ok(envt.iter().fold(Null, |inner, scope| {
+            let mut docs = scope
+                .local()
+                .iter()
+                .map(|(name, value)| BindingDoc::new(name, value, false))
+                .collect::<Vec<_>>();
+            docs.sort_by(|a, b| a.name.cmp(&b.name));
+
+            let bindings = docs.into_iter().map(SExp::from).collect::<SExp>();
+            sexp![
+                bindings.cons(SExp::sym("bindings")),
+                inner.cons(SExp::sym("inner"))
+            ]
+        }))
+    }
+
+    /// `(procedure-environment procedure)`: the environment a procedure
+    /// defined in Scheme closes over, to look into with `eval`.
+    pub(super) fn procedure_environment(&mut self, expr: SExp) -> Result {
+        let value = self.eval(expr.car()?)?;
+
+        match &value {
+            Atom(Procedure(p)) => p.env().cloned(),
+            _ => None,
+        }
+        .map(|envt| Atom(EnvPrim(envt)))
+        .ok_or_else(|| Error::Type {
+            expected: "compound procedure",
+            given: value.type_of().to_string(),
+        })
+    }
+
+    /// `(environment-bound? environment 'name)`
+    pub(super) fn environment_bound(&mut self, expr: SExp) -> Result {
+        let (envt, rest) = self.eval_args(expr)?.split_car()?;
+        let name = symbol_name(rest.car()?)?;
+
+        Ok(self.is_bound_in(&name, &*environment(&envt)?).into())
+    }
+}
@@ -0,0 +1,86 @@
+use super::super::SExp::{self, Atom};
+use super::super::{effect, Error, Primitive, Result};
+use super::{quoted, Context};
+
+impl Context {
+    /// Register a Scheme procedure to handle a named event, after any
+    /// handlers already registered for it.
+    ///
+    /// # Errors
+    /// Returns `Err` if the handler is not a procedure.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define clicks 0)").unwrap();
+    /// let handler = ctx.run("(lambda (n) (set! clicks (+ clicks n)))").unwrap();
+    /// ctx.on("click", handler).unwrap();
+    ///
+    /// ctx.emit("click", sexp![2]).unwrap();
+    /// ctx.emit("click", sexp![3]).unwrap();
+    /// assert_eq!(ctx.run("clicks").unwrap(), SExp::from(5));
+    ///
+    /// assert!(ctx.on("click", SExp::from(5)).is_err());
+    /// ```
+    pub fn on(&mut self, event: &str, handler: SExp) -> std::result::Result<(), Error> {
+        if !matches!(handler, Atom(Primitive::Procedure(_))) {
+            return Err(Error::Type {
+                expected: "procedure",
+                given: handler.type_of().to_string(),
+            });
+        }
+
+        effect::record();
+        self.events
+            .entry(event.to_string())
+            .or_default()
+            .push(handler);
+        Ok(())
+    }
+
+    /// Remove every handler for a named event.
+    pub fn off(&mut self, event: &str) {
+        effect::record();
+        self.events.remove(event);
+    }
+
+    /// Call each handler of a named event with a list of arguments, in the
+    /// order they were registered. Returns a list of their results, which is
+    /// empty if the event has no handlers.
+    ///
+    /// # Errors
+    /// Returns `Err` as soon as a handler fails. Any handlers after it are
+    /// not called.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// let double = ctx.run("(lambda (x) (* x 2))").unwrap();
+    /// let square = ctx.run("(lambda (x) (* x x))").unwrap();
+    /// ctx.on("tick", double).unwrap();
+    /// ctx.on("tick", square).unwrap();
+    ///
+    /// assert_eq!(ctx.emit("tick", sexp![5]).unwrap(), sexp![10, 25]);
+    /// assert_eq!(ctx.emit("tock", sexp![5]).unwrap(), SExp::Null);
+    ///
+    /// ctx.off("tick");
+    /// assert_eq!(ctx.emit("tick", sexp![5]).unwrap(), SExp::Null);
+    /// ```
+    pub fn emit(&mut self, event: &str, args: SExp) -> Result {
+        // handlers may register more handlers, which wait for the next event
+        let handlers = self.events.get(event).cloned().unwrap_or_default();
+        let args = args.into_iter().map(quoted).collect::<Vec<_>>();
+
+        handlers
+            .into_iter()
+            .map(|handler| {
+                let args = args.iter().cloned().collect::<SExp>();
+                self.eval(args.cons(handler))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+use super::super::SExp::{self, Atom, Null};
+use super::super::{Condition, Error, Primitive, Result};
+use super::core::symbol_name;
+use super::{quoted, Context};
+
+/// The value a handler or `guard` sees for an error: whatever was raised, or
+/// else a condition wrapping the error.
+fn error_value(err: Error) -> SExp {
+    match err {
+        Error::Raised { value } => value,
+        err => Condition::new(err).into(),
+    }
+}
+
+/// The error which unwinds the stack when a value is raised and not handled.
+fn value_error(value: SExp) -> Error {
+    match value {
+        Atom(Primitive::Condition(c)) => c.into_error(),
+        value => Error::Raised { value },
+    }
+}
+
+impl Context {
+    /// Pass a raised value to the innermost handler, which runs with only the
+    /// handlers outside it installed. If the handler returns, its result is
+    /// the result of `raise-continuable`; after `raise`, the value unwinds
+    /// instead. If the innermost handler belongs to a `guard`, or there are
+    /// none, the value unwinds straight away.
+    pub(super) fn raise(&mut self, value: SExp, continuable: bool) -> Result {
+        let handler = match self.handlers.last() {
+            Some(Some(handler)) => handler.clone(),
+            _ => return Err(value_error(value)),
+        };
+
+        self.handlers.pop();
+        let result = self.eval(Null.cons(quoted(value.clone())).cons(handler.clone()));
+        self.handlers.push(Some(handler));
+
+        if continuable {
+            result
+        } else {
+            result.and(Err(value_error(value)))
+        }
+    }
+
+    /// `(with-exception-handler handler thunk)`: call the thunk with the
+    /// handler installed. Errors from the interpreter itself are passed to the
+    /// handler as conditions, as though they had been raised.
+    pub(super) fn with_exception_handler(&mut self, expr: SExp) -> Result {
+        let (handler, rest) = self.eval_args(expr)?.split_car()?;
+        let thunk = rest.car()?;
+
+        self.handlers.push(Some(handler));
+        let result = match self.eval(Null.cons(thunk)) {
+            Err(err)
+                if !matches!(
+                    err,
+                    Error::Raised { .. }
+                        | Error::Continuation { .. }
+                        | Error::Suspended
+                        | Error::Control(_)
+                        | Error::OutputLimit(_)
+                ) =>
+            {
+                self.raise(error_value(err), false)
+            }
+            result => result,
+        };
+        self.handlers.pop();
+
+        result
+    }
+
+    /// `(guard (var clause...) body...)`: evaluate the body, and if anything
+    /// is raised in it, bind it to `var` and evaluate the first clause (as in
+    /// `cond`) which applies. If none do, it is raised again from the `guard`.
+    pub(super) fn eval_guard(&mut self, expr: SExp) -> Result {
+        let (spec, body) = expr.split_car()?;
+        let (var, clauses) = spec.split_car()?;
+        let var = symbol_name(var)?;
+
+        // the body has to be finished while the guard is installed, so it
+        // can't be left as a tail call
+        self.handlers.push(None);
+        self.push();
+        let result = self.eval_defer(&body).and_then(|res| self.eval(res));
+        self.pop();
+        self.handlers.pop();
+
+        let value = match result {
+            Err(
+                err @ (Error::Continuation { .. }
+                | Error::Suspended
+                | Error::Control(_)
+                | Error::OutputLimit(_)),
+            ) => return Err(err),
+            Err(err) => error_value(err),
+            result => return result,
+        };
+
+        self.push();
+        self.define(&var, value.clone());
+        let result = self.eval_clauses(clauses);
+        self.pop();
+
+        match result? {
+            Some(result) => Ok(result),
+            None => self.raise(value, true),
+        }
+    }
+}
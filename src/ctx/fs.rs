@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::super::Primitive::{String as LispString, Undefined};
+use super::super::SExp::{self, Atom};
+use super::super::{Error, Result};
+use super::Context;
+
+impl Context {
+    /// Confine every file the context reads (with `require`, for example) to
+    /// the directory at `path`. Relative paths are resolved against it, and
+    /// absolute paths are treated as if it were the filesystem root. Paths
+    /// which lead out of it, whether by `..` or by a symbolic link, are
+    /// rejected.
+    ///
+    /// # Errors
+    /// Returns `Err` if the directory does not exist.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// use std::fs;
+    ///
+    /// let root = std::env::temp_dir().join("parsley-fs-root-doc");
+    /// fs::create_dir_all(root.join("lib")).unwrap();
+    /// fs::write(root.join("lib/util.scm"), "(define (twice x) (* 2 x))").unwrap();
+    ///
+    /// let mut ctx = Context::base();
+    /// ctx.set_fs_root(&root).unwrap();
+    ///
+    /// assert!(ctx.run(r#"(require "lib/util.scm")"#).is_ok());
+    /// assert!(ctx.run(r#"(require "/lib/../lib/util.scm")"#).is_ok());
+    /// assert_eq!(ctx.run("(twice 4)").unwrap(), SExp::from(8));
+    ///
+    /// assert!(ctx.run(r#"(require "../parsley-fs-root-doc/lib/util.scm")"#).is_err());
+    /// ```
+    pub fn set_fs_root(&mut self, path: impl AsRef<Path>) -> std::result::Result<(), Error> {
+        self.fs_root = Some(path.as_ref().canonicalize()?);
+        Ok(())
+    }
+
+    /// Resolve a path given by a script, confining it to the filesystem root
+    /// if there is one. Relative paths are relative to the file being
+    /// included, if any.
+    pub(super) fn resolve_path(&self, path: &str) -> std::result::Result<PathBuf, Error> {
+        let base = self.includes.last().and_then(|file| file.parent());
+        let Some(root) = &self.fs_root else {
+            return Ok(match base {
+                Some(dir) => dir.join(path),
+                None => PathBuf::from(path),
+            });
+        };
+        let escaped = || Error::IO(format!("{path} is outside of the filesystem root"));
+
+        let mut resolved = match base {
+            Some(dir) if Path::new(path).is_relative() => dir.to_path_buf(),
+            _ => root.clone(),
+        };
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::ParentDir if resolved == *root => return Err(escaped()),
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::RootDir | Component::CurDir | Component::Prefix(_) => (),
+            }
+        }
+
+        // a symbolic link inside the root may still point outside of it
+        match resolved.canonicalize() {
+            Ok(real) if !real.starts_with(root) => Err(escaped()),
+            _ => Ok(resolved),
+        }
+    }
+
+    /// `(include "file" ...)`: read the forms in each file and evaluate them
+    /// in place, as if they were written in a `begin` instead. Paths in an
+    /// included file are relative to that file.
+    pub(super) fn eval_include(&mut self, expr: SExp) -> Result {
+        let mut result = Ok(Atom(Undefined));
+
+        for file in expr {
+            let path = match file {
+                Atom(LispString(path)) => self.resolve_path(&path)?,
+                other => {
+                    return Err(Error::Type {
+                        expected: "string",
+                        given: other.type_of().to_string(),
+                    })
+                }
+            };
+
+            let code =
+                SExp::parse_with_max_depth(&fs::read_to_string(&path)?, self.max_parse_depth)?;
+
+            // the included code has to be finished before the path is popped
+            self.includes.push(path);
+            result = self.eval(code);
+            self.includes.pop();
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::Primitive::Undefined;
+use super::super::SExp::{self, Atom, Null, Pair};
+use super::super::{Error, Func, Proc, Result};
+use super::core::symbol_name;
+use super::Context;
+
+/// The type tag a method is defined on when it applies to any type.
+const ANY_TYPE: &str = "any";
+
+/// The methods of each generic procedure, by the type tag they apply to.
+pub(super) type MethodTable = HashMap<String, SExp>;
+
+impl Context {
+    /// `(define-generic name)`: define a procedure which dispatches on the
+    /// type of its first argument to the methods added by `define-method`.
+    pub(super) fn eval_define_generic(&mut self, expr: SExp) -> Result {
+        let name = symbol_name(expr.car()?)?;
+        self.generics.entry(name.clone()).or_default();
+
+        let generic = name.clone();
+        let dispatch = move |ctx: &mut Self, args: SExp| {
+            let args = ctx.eval_args(args)?;
+            let type_tag = args.iter().next().map_or("null", SExp::type_of);
+
+            // look for a method on the type, then on each of its supertypes
+            let methods = &ctx.generics[&generic];
+            let mut tag = Some(type_tag);
+            let mut method = None;
+            while let (Some(t), None) = (tag, method) {
+                method = methods.get(t);
+                tag = ctx.supertypes.get(t).map(String::as_str);
+            }
+
+            let method = method
+                .or_else(|| methods.get(ANY_TYPE))
+                .cloned()
+                .ok_or_else(|| Error::NoMethod {
+                    name: generic.clone(),
+                    given: type_tag.to_string(),
+                })?;
+
+            let args = args
+                .into_iter()
+                .map(|a| Null.cons(a).cons(SExp::sym("quote")))
+                .collect::<SExp>();
+            ctx.eval(args.cons(method))
+        };
+
+        self.define(
+            &name,
+            Proc::new(Func::Ctx(Rc::new(dispatch)), (1,), Some(&name)).into(),
+        );
+        Ok(Atom(Undefined))
+    }
+
+    /// `(define-method (name (param type) params...) body...)`: add a method to
+    /// a generic procedure, for when its first argument has the given type
+    /// tag. A type of `any` makes the method the fallback for other types.
+    pub(super) fn eval_define_method(&mut self, expr: SExp) -> Result {
+        let (signature, body) = expr.split_car()?;
+        let (name, params) = signature.split_car()?;
+        let (first, params) = params.split_car()?;
+        let name = symbol_name(name)?;
+
+        let (param, type_tag) = match first {
+            Pair { head, tail } => (*head, symbol_name(tail.car()?)?),
+            other => {
+                return Err(Error::Type {
+                    expected: "list",
+                    given: other.type_of().to_string(),
+                })
+            }
+        };
+
+        // record types are conventionally written `<point>`, but their tag
+        // is `point`
+        let type_tag = type_tag
+            .strip_prefix('<')
+            .and_then(|t| t.strip_suffix('>'))
+            .map_or(type_tag.clone(), str::to_string);
+
+        let method = self.eval(body.cons(params.cons(param)).cons(SExp::sym("lambda")))?;
+
+        self.generics
+            .get_mut(&name)
+            .ok_or(Error::undefined(name))?
+            .insert(type_tag, method);
+        Ok(Atom(Undefined))
+    }
+}
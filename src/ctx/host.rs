@@ -0,0 +1,165 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use super::super::{effect, Condition, Error, Result, SExp};
+use super::Context;
+
+/// The host side of [`Context::eval_with_async_host`]: starts the asynchronous
+/// operations that Scheme code waits for with `(await request)`.
+///
+/// [`Context::eval_with_async_host`]: struct.Context.html#method.eval_with_async_host
+pub trait AsyncHost {
+    /// Start the operation described by the value passed to `await`. If the
+    /// future resolves to an error, it is raised from the `await`.
+    fn start(&mut self, request: SExp) -> Pin<Box<dyn Future<Output = Result>>>;
+
+    /// Start a timer for `(sleep ms)`. By default, this starts the request
+    /// `(sleep ms)` as though it had been awaited.
+    fn sleep(&mut self, ms: usize) -> Pin<Box<dyn Future<Output = Result>>> {
+        self.start(SExp::Null.cons(SExp::from(ms)).cons(SExp::sym("sleep")))
+    }
+}
+
+/// Something evaluation can stop to wait for.
+pub(super) enum Request {
+    Await(SExp),
+    Sleep(usize),
+}
+
+/// The results of the futures awaited so far in the current evaluation.
+#[derive(Default)]
+pub(super) struct Awaits {
+    /// Each result, in the order it was awaited. Errors are kept as
+    /// conditions.
+    results: Vec<std::result::Result<SExp, SExp>>,
+    /// How many of the results have been used since evaluation last started.
+    next: usize,
+    /// The request which evaluation stopped to wait for.
+    pending: Option<Request>,
+}
+
+impl Context {
+    /// `(await request)`: the result of the host's future for the request,
+    /// if it has resolved. Otherwise evaluation stops so that the future can
+    /// be awaited.
+    pub(super) fn await_request(&mut self, expr: SExp) -> Result {
+        let request = self.eval(expr.car()?)?;
+        self.suspend(Request::Await(request))
+    }
+
+    /// The result of a request, if it has already been made. Otherwise
+    /// evaluation stops so that it can be.
+    pub(super) fn suspend(&mut self, request: Request) -> Result {
+        let awaits = self.awaits.as_mut().ok_or(Error::Suspended)?;
+
+        if let Some(result) = awaits.results.get(awaits.next) {
+            awaits.next += 1;
+            result
+                .clone()
+                .map_err(|condition| Error::Raised { value: condition })
+        } else {
+            awaits.pending = Some(request);
+            Err(Error::Suspended)
+        }
+    }
+
+    /// Evaluate an expression which may `await` the results of futures
+    /// started by the host. While a future is pending, this yields to the
+    /// executor instead of blocking.
+    ///
+    /// # Note
+    /// Evaluation can't be suspended in the middle, so after each future
+    /// resolves, the user environment is rolled back (as with
+    /// [`restore`](#method.restore)) and the expression is evaluated again
+    /// from the start, with each `await` already answered giving the same
+    /// result as before. Captured output and timers are rolled back too.
+    /// Other side effects, such as changing a set or a record, closing a
+    /// resource, printing output which is not captured, or changing a scope
+    /// which a closure made beforehand still refers to, can't be rolled back,
+    /// so evaluation stops at the next `await` after any of them instead of
+    /// repeating them.
+    ///
+    /// # Errors
+    /// Returns `Err` if evaluation fails, if a future resolves to an error
+    /// that is not caught, or if evaluation reaches an `await` after a side
+    /// effect which can't be rolled back.
+    ///
+    /// # Example
+    /// ```
+    /// use std::future::{ready, Future};
+    /// use std::pin::{pin, Pin};
+    /// use std::task::{Context as TaskContext, Poll, Waker};
+    /// use parsley::prelude::*;
+    /// use parsley::AsyncHost;
+    ///
+    /// struct Doubler;
+    ///
+    /// impl AsyncHost for Doubler {
+    ///     fn start(&mut self, request: SExp) -> Pin<Box<dyn Future<Output = parsley::Result>>> {
+    ///         let n = request.to_string().parse::<i32>().unwrap();
+    ///         Box::pin(ready(Ok(SExp::from(n * 2))))
+    ///     }
+    /// }
+    ///
+    /// let mut ctx = Context::base();
+    /// let code = "(+ (await 1) (await 20))".parse().unwrap();
+    /// let mut host = Doubler;
+    /// let mut eval = pin!(ctx.eval_with_async_host(code, &mut host));
+    ///
+    /// let mut cx = TaskContext::from_waker(Waker::noop());
+    /// assert!(matches!(
+    ///     eval.as_mut().poll(&mut cx),
+    ///     Poll::Ready(Ok(n)) if n == SExp::from(42)
+    /// ));
+    /// ```
+    pub async fn eval_with_async_host<H: AsyncHost>(&mut self, expr: SExp, host: &mut H) -> Result {
+        let snapshot = self.snapshot();
+        let output_len = self.out.as_ref().map(String::len);
+        let (clock, timers) = (self.clock, self.timers.clone());
+        self.awaits = Some(Awaits::default());
+
+        let result = loop {
+            if let Some(awaits) = &mut self.awaits {
+                awaits.next = 0;
+            }
+
+            // effects are only tracked for as long as evaluation runs, not
+            // while the host's future is awaited
+            let watch = effect::watch();
+            let result = self.eval(expr.clone());
+            let effects = watch.any();
+            drop(watch);
+
+            let pending = match result {
+                Err(Error::Suspended) => self.awaits.as_mut().and_then(|a| a.pending.take()),
+                result => break result,
+            };
+            let Some(request) = pending else {
+                break Err(Error::Suspended);
+            };
+            // evaluating again would repeat the side effects
+            if effects {
+                break Err(Error::SideEffect);
+            }
+
+            let future = match request {
+                Request::Await(request) => host.start(request),
+                Request::Sleep(ms) => host.sleep(ms),
+            };
+            let result = future.await.map_err(|err| Condition::new(err).into());
+            if let Some(awaits) = &mut self.awaits {
+                awaits.results.push(result);
+            }
+
+            self.restore(&snapshot);
+            self.clock = clock;
+            self.timers.clone_from(&timers);
+            if let (Some(out), Some(len)) = (&mut self.out, output_len) {
+                out.truncate(len);
+            }
+        };
+
+        self.awaits = None;
+        result
+    }
+}
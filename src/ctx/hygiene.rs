@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use super::super::{Env, Error, Result, SExp};
+use super::Context;
+
+/// A name made up by a macro expansion to refer to a binding which is visible
+/// where the macro was defined, but shadowed where it is used.
+struct Alias {
+    name: String,
+    /// The scope of the binding, or `None` for a keyword or builtin.
+    scope: Option<Weak<Env>>,
+}
+
+impl Alias {
+    fn is_live(&self) -> bool {
+        self.scope
+            .as_ref()
+            .is_none_or(|scope| scope.strong_count() > 0)
+    }
+}
+
+/// The aliases made by macro expansions, so that each binding gets the same
+/// alias every time.
+#[derive(Default)]
+pub(super) struct Aliases {
+    by_alias: HashMap<String, Alias>,
+    /// The alias of each name, by the address of the scope it is bound in
+    /// (or zero for a keyword or builtin).
+    by_binding: HashMap<(String, usize), String>,
+    /// Aliases for scopes which have been dropped are removed once there are
+    /// this many.
+    sweep_at: usize,
+}
+
+impl Context {
+    /// The environment code is currently being evaluated in.
+    pub(crate) fn current_env(&self) -> Rc<Env> {
+        self.cont.borrow().env()
+    }
+
+    /// The scope in which a name is bound, as seen from an environment, or
+    /// `None` if it refers to a keyword or builtin (or to nothing at all).
+    pub(crate) fn binding_scope(&self, key: &str, envt: &Rc<Env>) -> Option<Rc<Env>> {
+        let scope = envt.scope_of(key)?;
+        // keywords take precedence over top-level definitions
+        if scope.parent().is_none() && self.core.contains_key(key) {
+            None
+        } else {
+            Some(scope)
+        }
+    }
+
+    /// A name which refers to `key` as it is bound in `scope` (as given by
+    /// [`binding_scope`](#method.binding_scope)), wherever it is used.
+    pub(crate) fn alias_binding(&mut self, key: &str, scope: Option<&Rc<Env>>) -> String {
+        let id = (key.to_string(), scope.map_or(0, |s| Rc::as_ptr(s) as usize));
+
+        // the address may belong to a scope which was dropped since
+        if let Some(alias) = self.aliases.by_binding.get(&id) {
+            if self.aliases.by_alias.get(alias).is_some_and(Alias::is_live) {
+                return alias.clone();
+            }
+        }
+
+        if self.aliases.by_alias.len() >= self.aliases.sweep_at {
+            self.aliases.by_alias.retain(|_, alias| alias.is_live());
+            let by_alias = &self.aliases.by_alias;
+            self.aliases
+                .by_binding
+                .retain(|_, alias| by_alias.contains_key(alias));
+            self.aliases.sweep_at = (by_alias.len() * 2).max(64);
+        }
+
+        let alias = self.fresh_symbol(key);
+        self.aliases.by_alias.insert(
+            alias.clone(),
+            Alias {
+                name: key.to_string(),
+                scope: scope.map(Rc::downgrade),
+            },
+        );
+        self.aliases.by_binding.insert(id, alias.clone());
+        alias
+    }
+
+    /// Whether a name is an alias made by a macro expansion.
+    pub(super) fn is_alias(&self, key: &str) -> bool {
+        self.aliases.by_alias.contains_key(key)
+    }
+
+    /// The value of the binding an alias refers to.
+    pub(super) fn get_alias(&self, key: &str) -> Option<SExp> {
+        let Alias { name, scope } = self.aliases.by_alias.get(key)?;
+        match scope {
+            Some(scope) => scope.upgrade()?.get(name),
+            None => self.core.get(name).or_else(|| self.lang.get(name)).cloned(),
+        }
+    }
+
+    /// Re-bind the binding an alias refers to, for `set!`. Keywords and
+    /// builtins can't be re-bound.
+    pub(super) fn set_alias(&self, key: &str, value: SExp) -> Result {
+        match self.aliases.by_alias.get(key) {
+            Some(Alias {
+                name,
+                scope: Some(scope),
+            }) => match scope.upgrade() {
+                Some(scope) => scope.set(name, value),
+                None => Err(Error::undefined(name.as_str())),
+            },
+            Some(Alias { name, .. }) => Err(Error::undefined(name.as_str())),
+            None => Err(Error::undefined(key)),
+        }
+    }
+}
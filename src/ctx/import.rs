@@ -0,0 +1,134 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::super::primitives::Port;
+use super::super::proc::Func;
+use super::super::Primitive::{
+    Deque as DequePrim, Env as EnvPrim, Heap as HeapPrim, Macro as MacroPrim, Port as PortPrim,
+    Procedure, Promise as PromisePrim, Record as RecordPrim, Set as SetPrim, Values, Vector,
+};
+use super::super::SExp::{self, Atom, Null, Pair};
+use super::super::{Env, Error, Result};
+use super::Context;
+
+/// The state of one call to `Context::import_value`.
+struct Importer {
+    /// The global scope that imported closures are rebound to.
+    global: Rc<Env>,
+    /// The copy made of each scope so far, so that closures sharing a scope
+    /// (or closing over themselves) still do once they are imported.
+    scopes: HashMap<*const Env, Rc<Env>>,
+}
+
+impl Importer {
+    fn value(&mut self, value: SExp) -> Result {
+        Ok(match value {
+            Pair { head, tail } => self.value(*tail)?.cons(self.value(*head)?),
+            Atom(Vector(v)) => Atom(Vector(self.values(v)?)),
+            Atom(Values(v)) => Atom(Values(self.values(v)?)),
+            Atom(Procedure(p)) => match (&p.func, p.env()) {
+                (Func::Parameter(_), _) => {
+                    return Err(Error::Type {
+                        expected: "value which can be imported",
+                        given: "parameter object".to_string(),
+                    })
+                }
+                (_, Some(envt)) => Atom(Procedure(p.with_env(self.scope(envt)?))),
+                // native procedures don't belong to any one context
+                (_, None) => Atom(Procedure(p)),
+            },
+            Atom(MacroPrim(m)) => Atom(MacroPrim(m.with_env(self.scope(m.env())?))),
+            Atom(EnvPrim(envt)) => Atom(EnvPrim(self.scope(&envt)?)),
+            Atom(RecordPrim(r)) => Atom(RecordPrim(r.try_map(|v| self.value(v))?)),
+            Atom(PromisePrim(p)) => Atom(PromisePrim(p.try_map(|v| self.value(v))?)),
+            Atom(HeapPrim(h)) => Atom(HeapPrim(h.try_map(|v| self.value(v))?)),
+            Atom(DequePrim(d)) => Atom(DequePrim(self.values(d.to_vec())?.into_iter().collect())),
+            Atom(SetPrim(s)) => Atom(SetPrim(self.values(s.to_vec())?.into_iter().collect())),
+            Atom(PortPrim(Port::String(s))) => Atom(PortPrim(Port::String(Rc::new(RefCell::new(
+                s.borrow().clone(),
+            ))))),
+            // everything else is immutable, and the same in every context
+            other @ (Null | Atom(_)) => other,
+        })
+    }
+
+    fn values(&mut self, values: Vec<SExp>) -> std::result::Result<Vec<SExp>, Error> {
+        values.into_iter().map(|v| self.value(v)).collect()
+    }
+
+    /// A copy of a scope and each scope around it, except for the outermost
+    /// one, which is replaced by the global scope of this context.
+    fn scope(&mut self, envt: &Rc<Env>) -> std::result::Result<Rc<Env>, Error> {
+        let Some(parent) = envt.parent() else {
+            return Ok(self.global.clone());
+        };
+
+        let key = Rc::as_ptr(envt);
+        if let Some(copy) = self.scopes.get(&key) {
+            return Ok(copy.clone());
+        }
+
+        // the copy is remembered before its definitions are imported, since
+        // they may close over it
+        let copy = Env::new(Some(self.scope(&parent)?)).into_rc();
+        self.scopes.insert(key, copy.clone());
+        for (name, value) in envt.local() {
+            copy.define(&name, self.value(value)?);
+        }
+
+        Ok(copy)
+    }
+}
+
+impl Context {
+    /// Copy a value from another context (or from one which has been
+    /// dropped) so that it can be used in this one without sharing any state
+    /// with its source.
+    ///
+    /// - Lists, vectors and records are copied, along with their contents.
+    /// - Mutable values (heaps, deques, sets, promises and string ports) are
+    ///   copied too, so changes made in one context aren't seen in the other.
+    /// - Procedures and macros defined in Scheme are rebound: the local
+    ///   scopes they close over are copied, and the global scope of their
+    ///   source is replaced by the one of this context, so any global names
+    ///   they use are looked up here instead. First-class environments are
+    ///   rebound in the same way.
+    /// - Native procedures, and any other value which can't change, are used
+    ///   as they are.
+    ///
+    /// # Errors
+    /// Parameter objects can't be imported, since their values are kept by
+    /// the context which made them. An `Err` is returned if the value is or
+    /// contains one.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut source = Context::base();
+    /// let mut target = Context::base();
+    ///
+    /// let counter = source
+    ///     .run("(define scale 10) (let ((n 0)) (lambda () (set! n (+ n 1)) (* n scale)))")
+    ///     .unwrap();
+    /// target.run("(define scale 100)").unwrap();
+    /// let imported = target.import_value(counter.clone()).unwrap();
+    /// target.define("counter", imported);
+    ///
+    /// // the imported closure has its own `n`, and sees this context's `scale`
+    /// assert_eq!(target.run("(counter)").unwrap(), SExp::from(100));
+    /// assert_eq!(target.run("(counter)").unwrap(), SExp::from(200));
+    /// source.define("counter", counter);
+    /// assert_eq!(source.run("(counter)").unwrap(), SExp::from(10));
+    ///
+    /// let param = source.run("(make-parameter 1)").unwrap();
+    /// assert!(target.import_value(param).is_err());
+    /// ```
+    pub fn import_value(&mut self, value: SExp) -> Result {
+        let mut importer = Importer {
+            global: self.global_env(),
+            scopes: HashMap::new(),
+        };
+        importer.value(value)
+    }
+}
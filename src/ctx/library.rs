@@ -0,0 +1,95 @@
+use super::super::Primitive::Undefined;
+use super::super::SExp::{self, Atom};
+use super::super::{Error, Result};
+use super::Context;
+
+/// How a library defines its names: in Rust, or in Scheme embedded in the
+/// crate.
+enum Source {
+    #[cfg_attr(not(feature = "math-extra"), allow(dead_code))]
+    Native(fn(&mut Context)),
+    Scheme(&'static str),
+}
+
+/// Every library which `import` can load, by name. The names are kept
+/// separate, rather than each library being loaded by `Context::base()`, so
+/// that a context only pays for what it uses.
+fn libraries() -> Vec<(&'static [&'static str], Source)> {
+    vec![
+        (
+            &["parsley", "list"],
+            Source::Scheme(include_str!("library/list.scm")),
+        ),
+        #[cfg(feature = "strings")]
+        (
+            &["parsley", "string"],
+            Source::Scheme(include_str!("library/string.scm")),
+        ),
+        #[cfg(feature = "math-extra")]
+        (&["parsley", "math"], Source::Native(Context::define_math)),
+    ]
+}
+
+fn library_name(name: &[&str]) -> SExp {
+    name.iter().map(|part| SExp::sym(part)).collect()
+}
+
+impl Context {
+    /// `(import (parsley list) ...)`: load each of the named libraries into
+    /// the global scope, unless it has been loaded already. Nothing is loaded
+    /// unless every name is one of `(available-libraries)`.
+    pub(super) fn import(&mut self, expr: SExp) -> Result {
+        let libs = libraries();
+        let mut wanted = Vec::new();
+
+        for spec in expr {
+            let given = spec.to_string();
+            match libs
+                .iter()
+                .position(|(name, _)| library_name(name).to_string() == given)
+            {
+                Some(index) => wanted.push((given, index)),
+                None => {
+                    return Err(Error::Type {
+                        expected: "name of an available library",
+                        given,
+                    })
+                }
+            }
+        }
+
+        for (name, index) in wanted {
+            if !self.imported.contains(&name) {
+                self.load_library(&libs[index].1)?;
+                self.imported.insert(name);
+            }
+        }
+
+        Ok(Atom(Undefined))
+    }
+
+    /// Load a library in the global scope, wherever `import` is called.
+    fn load_library(&mut self, source: &Source) -> Result {
+        self.push_cont();
+        self.use_env(self.global_env());
+        let result = match source {
+            Source::Native(define) => {
+                define(self);
+                Ok(Atom(Undefined))
+            }
+            Source::Scheme(code) => self.run(code),
+        };
+        self.pop_cont();
+
+        result
+    }
+
+    /// `(available-libraries)`: the names of the libraries that `import` can
+    /// load, such as `((parsley list) (parsley string))`.
+    pub(super) fn available_libraries() -> SExp {
+        libraries()
+            .iter()
+            .map(|(name, _)| library_name(name))
+            .collect()
+    }
+}
@@ -0,0 +1,130 @@
+;;; (parsley list): operations on lists, beyond the few built into the base
+;;; context. Each takes proper lists, and builds its result without recursing
+;;; on the length of its arguments.
+
+(define (length lst)
+  (let loop ((lst lst) (n 0))
+    (if (null? lst) n (loop (cdr lst) (+ n 1)))))
+
+(define (reverse lst)
+  (let loop ((lst lst) (acc '()))
+    (if (null? lst) acc (loop (cdr lst) (cons (car lst) acc)))))
+
+(define (append . lsts)
+  (let loop ((lsts (reverse lsts)) (acc '()))
+    (if (null? lsts)
+        acc
+        (loop (cdr lsts)
+              (let prepend ((head (reverse (car lsts))) (acc acc))
+                (if (null? head) acc (prepend (cdr head) (cons (car head) acc))))))))
+
+(define (list-tail lst k)
+  (if (= k 0) lst (list-tail (cdr lst) (- k 1))))
+
+(define (list-ref lst k)
+  (car (list-tail lst k)))
+
+(define (last-pair lst)
+  (if (null? (cdr lst)) lst (last-pair (cdr lst))))
+
+(define (last lst)
+  (car (last-pair lst)))
+
+(define (take lst k)
+  (let loop ((lst lst) (k k) (acc '()))
+    (if (= k 0) (reverse acc) (loop (cdr lst) (- k 1) (cons (car lst) acc)))))
+
+(define (drop lst k)
+  (list-tail lst k))
+
+(define (iota count . start-step)
+  (let ((start (if (null? start-step) 0 (car start-step)))
+        (step (if (or (null? start-step) (null? (cdr start-step))) 1 (car (cdr start-step)))))
+    (let loop ((i (- count 1)) (acc '()))
+      (if (< i 0) acc (loop (- i 1) (cons (+ start (* i step)) acc))))))
+
+(define (member x lst . compare)
+  (let ((same? (if (null? compare) equal? (car compare))))
+    (let loop ((lst lst))
+      (cond ((null? lst) #f)
+            ((same? x (car lst)) lst)
+            (else (loop (cdr lst)))))))
+
+(define (memq x lst) (member x lst eq?))
+(define (memv x lst) (member x lst eqv?))
+
+(define (assoc key alist . compare)
+  (let ((same? (if (null? compare) equal? (car compare))))
+    (let loop ((alist alist))
+      (cond ((null? alist) #f)
+            ((same? key (car (car alist))) (car alist))
+            (else (loop (cdr alist)))))))
+
+(define (assq key alist) (assoc key alist eq?))
+(define (assv key alist) (assoc key alist eqv?))
+
+(define (for-each f lst)
+  (let loop ((lst lst))
+    (unless (null? lst)
+      (f (car lst))
+      (loop (cdr lst)))))
+
+(define (fold-right f init lst)
+  (let loop ((lst (reverse lst)) (acc init))
+    (if (null? lst) acc (loop (cdr lst) (f (car lst) acc)))))
+
+(define (reduce f default lst)
+  (if (null? lst)
+      default
+      (let loop ((lst (cdr lst)) (acc (car lst)))
+        (if (null? lst) acc (loop (cdr lst) (f (car lst) acc))))))
+
+(define (any pred lst)
+  (let loop ((lst lst))
+    (cond ((null? lst) #f)
+          ((pred (car lst)))
+          (else (loop (cdr lst))))))
+
+(define (every pred lst)
+  (let loop ((lst lst) (last #t))
+    (cond ((null? lst) last)
+          ((pred (car lst)) => (lambda (result) (loop (cdr lst) result)))
+          (else #f))))
+
+(define (count pred lst)
+  (let loop ((lst lst) (n 0))
+    (cond ((null? lst) n)
+          ((pred (car lst)) (loop (cdr lst) (+ n 1)))
+          (else (loop (cdr lst) n)))))
+
+(define (remove pred lst)
+  (let loop ((lst lst) (acc '()))
+    (cond ((null? lst) (reverse acc))
+          ((pred (car lst)) (loop (cdr lst) acc))
+          (else (loop (cdr lst) (cons (car lst) acc))))))
+
+(define (partition pred lst)
+  (let loop ((lst lst) (in '()) (out '()))
+    (cond ((null? lst) (values (reverse in) (reverse out)))
+          ((pred (car lst)) (loop (cdr lst) (cons (car lst) in) out))
+          (else (loop (cdr lst) in (cons (car lst) out))))))
+
+(define (delete x lst)
+  (remove (lambda (y) (equal? x y)) lst))
+
+(define (delete-duplicates lst)
+  (let loop ((lst lst) (acc '()))
+    (cond ((null? lst) (reverse acc))
+          ((member (car lst) acc) (loop (cdr lst) acc))
+          (else (loop (cdr lst) (cons (car lst) acc))))))
+
+(define (filter-map f lst)
+  (let loop ((lst lst) (acc '()))
+    (if (null? lst)
+        (reverse acc)
+        (let ((result (f (car lst))))
+          (loop (cdr lst) (if result (cons result acc) acc))))))
+
+(define (append-map f lst)
+  (let loop ((lst (reverse lst)) (acc '()))
+    (if (null? lst) acc (loop (cdr lst) (append (f (car lst)) acc)))))
@@ -0,0 +1,12 @@
+;;; (parsley string): operations on strings, beyond the ones built into the
+;;; base context.
+
+(define (string-null? s)
+  (= (string-length s) 0))
+
+(define (string-prefix? prefix s)
+  (and (not (< (string-length s) (string-length prefix)))
+       (string=? prefix (substring s 0 (string-length prefix)))))
+
+(define (string-suffix? suffix s)
+  (string-prefix? (string-reverse suffix) (string-reverse s)))
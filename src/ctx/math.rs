@@ -19,7 +19,7 @@ impl Context {
     ///     assert_eq!(ctx.run(lhs).unwrap(), ctx.run(rhs).unwrap())
     /// };
     ///
-    /// asrt("(is-nan NaN)", "#t");
+    /// asrt("(is-nan +nan.0)", "#t");
     /// asrt("(floor -4.07326)", "-5");
     /// asrt("(ceil 7.1)", "8");
     /// asrt("(hypot 3 4)", "5");
@@ -28,6 +28,13 @@ impl Context {
     /// ```
     #[must_use]
     pub fn math(mut self) -> Self {
+        self.define_math();
+        self
+    }
+
+    /// The definitions made by [`math`](#method.math), which are also
+    /// available as the library `(parsley math)`.
+    pub(super) fn define_math(&mut self) {
         // identification
         define_with!(self, "is-nan", Num::is_nan, make_unary_numeric);
         define_with!(self, "is-infinite", Num::is_infinite, make_unary_numeric);
@@ -77,7 +84,5 @@ impl Context {
         // unit conversions
         define_with!(self, "to-degrees", Num::to_degrees, make_unary_numeric);
         define_with!(self, "to-radians", Num::to_radians, make_unary_numeric);
-
-        self
     }
 }
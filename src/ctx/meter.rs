@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use super::super::Result;
+use super::Context;
+
+/// The resources used by an evaluation, as measured by
+/// [`Context::run_metered`](struct.Context.html#method.run_metered).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalReport {
+    /// The number of reduction steps taken by the evaluator.
+    pub steps: usize,
+    /// The deepest nesting of evaluations at any point.
+    pub max_depth: usize,
+    /// The number of environment frames (scopes) created, e.g. by calling a
+    /// procedure or entering a `let`.
+    pub allocations: usize,
+    /// The wall-clock time taken. Always zero on WebAssembly, which has no
+    /// clock to measure it with.
+    pub duration: Duration,
+}
+
+/// The running count for an evaluation being metered.
+#[derive(Default)]
+pub(super) struct Meter {
+    report: EvalReport,
+    depth: usize,
+}
+
+impl Meter {
+    pub(super) fn enter(&mut self) {
+        self.depth += 1;
+        self.report.max_depth = self.report.max_depth.max(self.depth);
+    }
+
+    pub(super) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(super) fn step(&mut self) {
+        self.report.steps += 1;
+    }
+
+    pub(super) fn allocate(&mut self) {
+        self.report.allocations += 1;
+    }
+
+    /// Count the resources used by a nested evaluation as part of this one.
+    fn absorb(&mut self, inner: &EvalReport) {
+        self.report.steps += inner.steps;
+        self.report.max_depth = self.report.max_depth.max(self.depth + inner.max_depth);
+        self.report.allocations += inner.allocations;
+    }
+}
+
+impl Context {
+    /// Run a code snippet, as with [`run`](#method.run), and report the
+    /// resources it used along with its result. Hosts can use the report to
+    /// bill or throttle scripts.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define (count n) (if (= n 0) 0 (+ 1 (count (- n 1)))))").unwrap();
+    ///
+    /// let (result, small) = ctx.run_metered("(count 2)");
+    /// assert_eq!(result.unwrap(), SExp::from(2));
+    /// let (_, large) = ctx.run_metered("(count 20)");
+    ///
+    /// assert!(small.steps > 0 && large.steps > small.steps);
+    /// assert!(large.max_depth > small.max_depth);
+    /// assert_eq!(large.allocations, 21);
+    ///
+    /// let (result, report) = ctx.run_metered("(car '())");
+    /// assert!(result.is_err());
+    /// assert!(report.steps > 0);
+    /// ```
+    pub fn run_metered(&mut self, code: &str) -> (Result, EvalReport) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let start = std::time::Instant::now();
+
+        let outer = self.meter.replace(Meter::default());
+        let result = self.run(code);
+        let inner = std::mem::replace(&mut self.meter, outer);
+
+        let mut report = inner.map(|m| m.report).unwrap_or_default();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            report.duration = start.elapsed();
+        }
+
+        if let Some(outer) = &mut self.meter {
+            outer.absorb(&report);
+        }
+
+        (result, report)
+    }
+}
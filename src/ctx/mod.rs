@@ -1,12 +1,43 @@
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::rc::Rc;
 
-use super::{Cont, Env, Ns, Primitive, Proc, Result, SExp};
+pub use self::control::ReplControl;
+pub use self::doc::BindingDoc;
+pub use self::host::AsyncHost;
+pub use self::meter::EvalReport;
+pub use self::snapshot::Snapshot;
 
+use super::env;
+use super::utils::edit_distance;
+use super::{Cont, Env, Error, Ns, Num, NumberFormat, Primitive, Proc, Result, SExp, Symbol};
+
+mod alias;
 mod base;
+#[cfg(feature = "clos")]
+mod clos;
+mod control;
 mod core;
+mod doc;
+mod environment;
+mod events;
+mod exception;
+#[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+mod fs;
+mod generic;
+mod host;
+mod hygiene;
+mod import;
+mod library;
+#[cfg(feature = "math-extra")]
 mod math;
+mod meter;
+mod param;
+mod resource;
+mod snapshot;
+mod timer;
+mod types;
 mod write;
 
 /// Evaluation context for LISP expressions.
@@ -22,7 +53,13 @@ mod write;
 /// the provided methods operate on the "user" environment, as the intended use
 /// case keeps the other environments immutable once they have been initialized.
 pub struct Context {
-    core: Ns,
+    /// The resources to close and the hooks to call when the context is
+    /// dropped. This is the first field, so that it is dropped before the
+    /// rest of the context: the resources are closed in a fixed order, rather
+    /// than as the values referring to them happen to be dropped.
+    cleanup: resource::Cleanup,
+    /// Shared by every context on a thread, since it never changes.
+    core: Rc<Ns>,
     cont: Rc<RefCell<Cont>>,
     /// You can `insert` additional definitions here to make them available
     /// throughout the runtime. These definitions will not go out of scope
@@ -30,15 +67,107 @@ pub struct Context {
     /// semantic details).
     pub lang: Ns,
     out: Option<String>,
+    /// The most output the current evaluation may write, if limited.
+    output_limit: Option<usize>,
+    /// The output written so far by the current evaluation, in bytes.
+    output_written: usize,
+    max_parse_depth: usize,
+    number_format: NumberFormat,
+    symbol_count: usize,
+    /// Names which macro expansions use to refer to bindings shadowed where
+    /// the macros are used.
+    aliases: hygiene::Aliases,
+    continuation_count: usize,
+    parameters: Vec<param::Parameter>,
+    annotations: HashMap<String, SExp>,
+    type_check: bool,
+    generics: HashMap<String, generic::MethodTable>,
+    /// The parent of each type tag which has one (a subclass made by
+    /// `define-class`), for generic procedures to fall back on.
+    supertypes: HashMap<String, String>,
+    #[cfg(feature = "clos")]
+    classes: HashMap<String, clos::Class>,
+    /// The installed exception handlers, innermost last. `None` marks a
+    /// `guard`, which catches anything raised inside it.
+    handlers: Vec<Option<SExp>>,
+    awaits: Option<host::Awaits>,
+    /// The scheduler's clock, in milliseconds, and the pending timers, in the
+    /// order they are due.
+    clock: usize,
+    timers: Vec<timer::Timer>,
+    /// The handlers of each named event, registered by the host with `on`.
+    events: HashMap<String, Vec<SExp>>,
+    /// The directory that file paths are confined to, if any.
+    #[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+    fs_root: Option<std::path::PathBuf>,
+    /// The files being read by `include`, innermost last.
+    #[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+    includes: Vec<std::path::PathBuf>,
+    /// The resources used so far by the evaluation being metered, if any.
+    meter: Option<meter::Meter>,
+    /// The revision of the user environment when it was last marked clean.
+    clean_revision: u64,
+    /// Deprecated language-level names which have not been used yet, and the
+    /// names to use instead.
+    deprecated: HashMap<String, Option<String>>,
+    /// Warnings for the host to show, such as the use of deprecated names.
+    warnings: Vec<String>,
+    /// Called by `exit` and `restart`, as set by `on_exit`.
+    exit_hook: Option<Box<dyn FnMut(ReplControl)>>,
+    /// The variables whose inits are being evaluated, innermost last.
+    initializing: Vec<String>,
+    /// The names of the libraries loaded by `import` so far.
+    imported: HashSet<String>,
+}
+
+/// Quote a value which has already been evaluated, to pass it to a procedure
+/// being applied with `eval`.
+fn quoted(value: SExp) -> SExp {
+    SExp::Null.cons(value).cons(SExp::sym("quote"))
+}
+
+thread_local! {
+    static CORE: Rc<Ns> = Rc::new(Context::core());
 }
 
 impl Default for Context {
     fn default() -> Self {
         Self {
-            core: Self::core(),
+            cleanup: resource::Cleanup::default(),
+            core: CORE.with(Rc::clone),
             cont: Cont::default().into_rc(),
             lang: Ns::new(),
             out: None,
+            output_limit: None,
+            output_written: 0,
+            max_parse_depth: SExp::MAX_DEPTH,
+            number_format: NumberFormat::default(),
+            symbol_count: 0,
+            aliases: hygiene::Aliases::default(),
+            continuation_count: 0,
+            parameters: Vec::new(),
+            annotations: HashMap::new(),
+            type_check: false,
+            generics: HashMap::new(),
+            supertypes: HashMap::new(),
+            #[cfg(feature = "clos")]
+            classes: HashMap::new(),
+            handlers: Vec::new(),
+            awaits: None,
+            clock: 0,
+            timers: Vec::new(),
+            events: HashMap::new(),
+            #[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+            fs_root: None,
+            #[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+            includes: Vec::new(),
+            meter: None,
+            clean_revision: env::current_revision(),
+            deprecated: HashMap::new(),
+            warnings: Vec::new(),
+            exit_hook: None,
+            initializing: Vec::new(),
+            imported: HashSet::new(),
         }
     }
 }
@@ -48,6 +177,9 @@ impl Context {
     ///
     /// See [`Context::pop`](#method.pop) for a usage example.
     pub fn push(&mut self) {
+        if let Some(meter) = &mut self.meter {
+            meter.allocate();
+        }
         self.cont.borrow_mut().push();
     }
 
@@ -73,8 +205,14 @@ impl Context {
     }
 
     /// Create a new definition in the current scope.
-    pub fn define(&mut self, key: &str, value: SExp) {
-        self.cont.borrow().env().define(key, value);
+    pub fn define(&mut self, key: impl Into<Symbol>, value: SExp) {
+        let key = key.into();
+        self.cont.borrow().env().define(&key, value);
+    }
+
+    /// Create several new definitions in the current scope.
+    pub(super) fn define_all(&mut self, defns: Ns) {
+        self.cont.borrow().env().extend(defns);
     }
 
     /// Get the definition for a symbol in the execution environment.
@@ -84,15 +222,17 @@ impl Context {
     /// # Override semantics
     /// This method searches for a definition in the following order:
     ///
-    ///   1. The core language
-    ///   2. User definitions, starting from the most recent scope and working
-    ///      backward to the top-level
-    ///   3. [Language-level definitions](#structfield.lang)
+    ///   1. User definitions in local scopes (e.g. made by `let` or `lambda`),
+    ///      starting from the most recent scope
+    ///   2. The core language
+    ///   3. User definitions at the top-level
+    ///   4. [Language-level definitions](#structfield.lang)
     ///
     /// What this means is that definitions populated in the `lang` field can be
     /// overridden inside the runtime (e.g. in a REPL), but special form keywords
-    /// cannot. For example, we can `(define null "foo")`, but we cannot
-    /// `(set! and or)`.
+    /// can only be shadowed by local bindings. For example, we can
+    /// `(define null "foo")`, and `(let ((if list)) (if 1 2 3))` is a list, but
+    /// we cannot `(set! and or)` at the top-level.
     ///
     /// # Examples
     /// ```
@@ -108,13 +248,21 @@ impl Context {
     /// ```
     #[must_use]
     pub fn get(&self, key: &str) -> Option<SExp> {
-        // first check core (reserved keywords)
+        // user definitions
+        let user = self.cont.borrow().env().lookup(key);
+
+        // local bindings shadow everything else
+        if let Some((exp, false)) = user {
+            return Some(exp);
+        }
+
+        // then check core (reserved keywords)
         if let Some(exp) = self.core.get(key) {
             return Some(exp.clone());
         }
 
-        // then the environment stack
-        if let Some(exp) = self.cont.borrow().env().get(key) {
+        // then the top-level
+        if let Some((exp, _)) = user {
             return Some(exp);
         }
 
@@ -123,8 +271,147 @@ impl Context {
             return Some(exp.clone());
         }
 
-        // otherwise fail
-        None
+        // then names made up by macros
+        self.get_alias(key)
+    }
+
+    /// An `UndefinedSymbol` error for a name, suggesting up to three bound
+    /// names which are only a few edits away from it (and not so short that
+    /// everything is).
+    pub(super) fn undefined(&self, sym: &str) -> Error {
+        let len = sym.chars().count();
+        let max_distance = (len / 3).max(1);
+
+        let mut names = self
+            .core
+            .keys()
+            .chain(self.lang.keys())
+            .cloned()
+            .collect::<HashSet<_>>();
+        for envt in self.cont.borrow().env().iter() {
+            names.extend(envt.local().into_keys());
+        }
+
+        let mut suggestions = names
+            .into_iter()
+            .filter_map(|name| {
+                let distance = edit_distance(sym, &name);
+                let close = distance <= max_distance && distance < len.min(name.chars().count());
+                close.then_some((distance, name))
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort();
+
+        Error::UndefinedSymbol {
+            sym: sym.into(),
+            suggestions: suggestions
+                .into_iter()
+                .take(3)
+                .map(|(_, name)| name.into())
+                .collect(),
+        }
+    }
+
+    /// An `Uninitialized` error for a name, blaming the definition being
+    /// evaluated, if any.
+    fn uninitialized(&self, sym: &str) -> Error {
+        Error::Uninitialized {
+            sym: sym.into(),
+            defining: self.initializing.last().map(Symbol::from),
+        }
+    }
+
+    /// Get the definition for a symbol, or an `Err` if there is none.
+    fn get_defined(&self, key: &str) -> std::result::Result<SExp, Error> {
+        self.get(key).ok_or_else(|| self.undefined(key))
+    }
+
+    /// Get the definition for a symbol as a number.
+    ///
+    /// # Errors
+    /// Returns `Err` if the symbol is undefined or is not bound to a number.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// use parsley::Num;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define port (+ 8000 80))").unwrap();
+    /// assert_eq!(ctx.get_number("port").unwrap(), Num::from(8080));
+    /// assert!(ctx.get_number("car").is_err());
+    /// ```
+    pub fn get_number(&self, key: &str) -> std::result::Result<Num, Error> {
+        match self.get_defined(key)? {
+            SExp::Atom(Primitive::Number(n)) => Ok(n),
+            other => Err(Error::Type {
+                expected: "number",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    /// Get the definition for a symbol as a string.
+    ///
+    /// # Errors
+    /// Returns `Err` if the symbol is undefined or is not bound to a string.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run(r#"(define host "localhost")"#).unwrap();
+    /// assert_eq!(ctx.get_string("host").unwrap(), "localhost");
+    /// assert!(ctx.get_string("potato").is_err());
+    /// ```
+    pub fn get_string(&self, key: &str) -> std::result::Result<String, Error> {
+        match self.get_defined(key)? {
+            SExp::Atom(Primitive::String(s)) => Ok(s),
+            other => Err(Error::Type {
+                expected: "string",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    /// Get the definition for a symbol as a boolean.
+    ///
+    /// # Errors
+    /// Returns `Err` if the symbol is undefined or is not bound to a boolean.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define verbose? (> 2 1))").unwrap();
+    /// assert!(ctx.get_bool("verbose?").unwrap());
+    /// assert!(ctx.get_bool("pi").is_err());
+    /// ```
+    pub fn get_bool(&self, key: &str) -> std::result::Result<bool, Error> {
+        match self.get_defined(key)? {
+            SExp::Atom(Primitive::Boolean(b)) => Ok(b),
+            other => Err(Error::Type {
+                expected: "bool",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    /// Check whether a symbol is visible from a given environment, including
+    /// core and language-level definitions.
+    pub(super) fn is_bound_in(&self, key: &str, envt: &Env) -> bool {
+        self.core.contains_key(key)
+            || envt.contains(key)
+            || self.lang.contains_key(key)
+            || self.is_alias(key)
+    }
+
+    /// Generate a fresh symbol name which cannot be produced by the reader.
+    pub(super) fn fresh_symbol(&mut self, base: &str) -> String {
+        self.symbol_count += 1;
+        format!("{base}#{}", self.symbol_count)
     }
 
     /// Re-bind an existing definition to a new value.
@@ -145,7 +432,12 @@ impl Context {
     /// assert_eq!(ctx.get("x"), Some(SExp::from("potato"))); // check that its value is now "potato"
     /// ```
     pub fn set(&mut self, key: &str, value: SExp) -> Result {
-        self.cont.borrow().env().set(key, value)
+        if self.is_alias(key) {
+            return self.set_alias(key, value);
+        }
+
+        let result = self.cont.borrow().env().set(key, value);
+        result.map_err(|_| self.undefined(key))
     }
 
     /// Push a new partial continuation with an existing environment.
@@ -153,6 +445,28 @@ impl Context {
         self.cont.borrow_mut().set_env(envt);
     }
 
+    /// Start a new scope for a call to a procedure closed over `envt`.
+    ///
+    /// A tail call from a frame nothing else refers to would drop that frame,
+    /// so if it has the same parent (as when a named `let` or a recursive
+    /// procedure calls itself), it is cleared and reused instead of
+    /// allocating a new one.
+    pub(super) fn enter_frame(&mut self, envt: &Rc<Env>) {
+        let current = self.cont.borrow().env();
+        let reusable = Rc::strong_count(&current) == 2
+            && Rc::weak_count(&current) == 0
+            && current
+                .parent()
+                .is_some_and(|parent| Rc::ptr_eq(&parent, envt));
+
+        if reusable {
+            current.clear();
+        } else {
+            self.use_env(envt.clone());
+            self.push();
+        }
+    }
+
     /// Push a new partial continuation onto the stack.
     pub(super) fn push_cont(&mut self) {
         self.cont = Cont::from(&self.cont).into_rc();
@@ -202,7 +516,55 @@ impl Context {
     /// assert_eq!(ctx.run("x").unwrap(), SExp::from(6));
     /// ```
     pub fn run(&mut self, expr: &str) -> Result {
-        self.eval(expr.parse::<SExp>()?)
+        let code = SExp::parse_with_max_depth(expr, self.max_parse_depth)?;
+
+        if self.type_check {
+            self.check_types(&code)?;
+        }
+
+        self.eval(code)
+    }
+
+    /// Evaluate a sequence of S-Expressions in order, as `begin` would, and
+    /// return the value of the last one. An empty sequence evaluates to
+    /// `#<undefined>`.
+    ///
+    /// # Errors
+    /// Evaluation stops at the first expression that returns an `Err`.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// let exprs = vec![
+    ///     sexp![SExp::sym("define"), SExp::sym("x"), 6],
+    ///     sexp![SExp::sym("*"), SExp::sym("x"), 7],
+    /// ];
+    /// assert_eq!(ctx.eval_sequence(&exprs).unwrap(), SExp::from(42));
+    /// ```
+    pub fn eval_sequence(&mut self, exprs: &[SExp]) -> Result {
+        exprs
+            .iter()
+            .try_fold(SExp::Atom(Primitive::Undefined), |_, expr| {
+                self.eval(expr.clone())
+            })
+    }
+
+    /// Set how deeply lists, vectors and quotes may be nested in code passed
+    /// to [`run`](#method.run). Defaults to [`SExp::MAX_DEPTH`].
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.set_max_parse_depth(3);
+    /// assert!(ctx.run("(car '(1 2))").is_ok());
+    /// assert!(ctx.run("(car '((1) 2))").is_err());
+    /// ```
+    pub fn set_max_parse_depth(&mut self, max_depth: usize) {
+        self.max_parse_depth = max_depth;
     }
 
     /// Evaluate an S-Expression in a context.
@@ -233,23 +595,38 @@ impl Context {
     /// assert_eq!(ctx.eval(exp2).unwrap(), SExp::from(10));
     /// ```
     pub fn eval(&mut self, mut expr: SExp) -> Result {
-        use super::Error::{NotAProcedure, NullList, UndefinedSymbol};
+        use super::Error::{NotAProcedure, NullList};
         use super::Func::Tail;
-        use super::Primitive::{Procedure, Symbol, Undefined};
+        use super::Primitive::{Macro as MacroPrim, Procedure, Symbol, Undefined};
         use super::SExp::{Atom, Null, Pair};
 
+        // the output limit applies to each evaluation started by the host
+        if self.cont.borrow().parent().is_none() {
+            self.output_written = 0;
+        }
         self.push_cont();
+        if let Some(meter) = &mut self.meter {
+            meter.enter();
+        }
 
         let res = loop {
+            if let Some(meter) = &mut self.meter {
+                meter.step();
+            }
+
             expr = match expr {
                 // cannot evaluate null
                 Null => break Err(NullList),
                 // check if symbol is defined
                 Atom(Symbol(sym)) => match self.get(&sym) {
-                    None | Some(Atom(Undefined)) => {
-                        break Err(UndefinedSymbol { sym });
+                    None => break Err(self.undefined(&sym)),
+                    Some(Atom(Undefined)) => break Err(self.uninitialized(&sym)),
+                    Some(exp) => {
+                        if !self.deprecated.is_empty() {
+                            self.check_deprecated(&sym, &exp);
+                        }
+                        exp
                     }
-                    Some(exp) => exp,
                 },
                 // continue evaluation
                 Atom(Procedure(Proc {
@@ -265,16 +642,32 @@ impl Context {
                 // it's an application
                 Pair { head, tail } => {
                     // evaluate the first element
-                    match self.eval(*head)? {
+                    let proc = match self.eval(*head) {
+                        Ok(proc) => proc,
+                        Err(err) => break Err(err),
+                    };
+
+                    match proc {
+                        // if it is a macro, expand it and evaluate the result
+                        Atom(MacroPrim(m)) => {
+                            match m.expand(*tail, self) {
+                                Ok(expansion) => expr = expansion,
+                                Err(err) => break Err(err),
+                            }
+                            continue;
+                        }
                         // if it is indeed a procedure
                         Atom(Procedure(p)) => {
                             let args = if p.defer_eval() {
-                                *tail
+                                Ok(*tail)
                             } else {
-                                self.eval_args(*tail)?
+                                self.eval_args(*tail)
                             };
                             // then apply it
-                            p.apply(args, self)?
+                            match args.and_then(|args| p.apply(args, self)) {
+                                Ok(result) => result,
+                                Err(err) => break Err(err),
+                            }
                         }
                         // otherwise complain
                         proc => {
@@ -293,6 +686,9 @@ impl Context {
             }
         };
 
+        if let Some(meter) = &mut self.meter {
+            meter.exit();
+        }
         self.pop_cont();
         res
     }
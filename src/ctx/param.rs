@@ -0,0 +1,135 @@
+use super::super::SExp::{self, Atom, Null};
+use super::super::{Error, Func, Primitive, Proc, Result};
+use super::core::symbol_name;
+use super::{quoted, Context};
+
+/// The state of a parameter object created by `make-parameter`.
+pub(super) struct Parameter {
+    /// The value bound by each enclosing `parameterize`, innermost last. The
+    /// first is the initial value, which is never unbound.
+    values: Vec<SExp>,
+    converter: Option<SExp>,
+}
+
+impl Context {
+    /// `(make-parameter value [converter])`: the converter, if given, is
+    /// applied to the initial value and to any value bound by `parameterize`.
+    pub(super) fn make_parameter(&mut self, expr: SExp) -> Result {
+        let (value, rest) = self.eval_args(expr)?.split_car()?;
+        let converter = rest.car().ok();
+
+        let value = match &converter {
+            Some(c) => self.eval(Null.cons(quoted(value)).cons(c.clone()))?,
+            None => value,
+        };
+
+        self.parameters.push(Parameter {
+            values: vec![value],
+            converter,
+        });
+
+        Ok(Proc::new(
+            Func::Parameter(self.parameters.len() - 1),
+            0,
+            Some("parameter"),
+        )
+        .into())
+    }
+
+    pub(crate) fn parameter_value(&self, id: usize) -> Result {
+        self.parameters
+            .get(id)
+            .and_then(|p| p.values.last().cloned())
+            .ok_or(Error::Type {
+                expected: "parameter of this context",
+                given: "parameter".to_string(),
+            })
+    }
+
+    /// Evaluate the body of a `parameterize` form with each parameter bound to
+    /// a new value. The old values are restored on the way out, whether the
+    /// body returns or fails.
+    pub(super) fn eval_parameterize(&mut self, expr: SExp) -> Result {
+        let (bindings, body) = expr.split_car()?;
+
+        // evaluate everything before binding anything
+        let mut ids = Vec::new();
+        let mut values = Vec::new();
+        for binding in bindings {
+            let (param, value) = binding.split_car()?;
+            let id = match self.eval(param)? {
+                Atom(Primitive::Procedure(Proc {
+                    func: Func::Parameter(id),
+                    ..
+                })) if id < self.parameters.len() => id,
+                other => {
+                    return Err(Error::Type {
+                        expected: "parameter",
+                        given: other.type_of().to_string(),
+                    })
+                }
+            };
+
+            let value = self.eval(value.car()?)?;
+            let value = match self.parameters[id].converter.clone() {
+                Some(c) => self.eval(Null.cons(quoted(value)).cons(c))?,
+                None => value,
+            };
+
+            ids.push(id);
+            values.push(value);
+        }
+
+        for (&id, value) in ids.iter().zip(values) {
+            self.parameters[id].values.push(value);
+        }
+
+        // the body has to be finished before the bindings are undone, so it
+        // can't be left as a tail call
+        let result = self.eval_defer(&body).and_then(|res| self.eval(res));
+
+        for &id in &ids {
+            self.parameters[id].values.pop();
+        }
+
+        result
+    }
+
+    /// Evaluate the body of a `fluid-let` form with each (existing) variable
+    /// temporarily set to a new value. The old values are restored on the way
+    /// out, whether the body returns or fails.
+    pub(super) fn eval_fluid_let(&mut self, expr: SExp) -> Result {
+        let (bindings, body) = expr.split_car()?;
+
+        // evaluate everything before setting anything
+        let mut names = Vec::new();
+        let mut values = Vec::new();
+        for binding in bindings {
+            let (name, value) = binding.split_car()?;
+            let name = symbol_name(name)?;
+            if self.get(&name).is_none() {
+                return Err(self.undefined(&name));
+            }
+
+            values.push(self.eval(value.car()?)?);
+            names.push(name);
+        }
+
+        let mut saved = Vec::new();
+        for (name, value) in names.into_iter().zip(values) {
+            if let Some(old) = self.get(&name) {
+                self.set(&name, value)?;
+                saved.push((name, old));
+            }
+        }
+
+        // as with `parameterize`, the body can't be left as a tail call
+        let result = self.eval_defer(&body).and_then(|res| self.eval(res));
+
+        for (name, old) in saved.into_iter().rev() {
+            self.set(&name, old)?;
+        }
+
+        result
+    }
+}
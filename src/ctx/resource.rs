@@ -0,0 +1,133 @@
+use super::super::primitives::{Resource, WeakRef};
+use super::super::Primitive::{Resource as ResourcePrim, Void, WeakRef as WeakRefPrim};
+use super::super::SExp::{self, Atom};
+use super::super::{Error, Result};
+use super::Context;
+
+/// What to do when a context is dropped: close the resources registered with
+/// it, then call the hooks added by `on_drop`. The resources are only
+/// referenced weakly, so that each can still be closed as soon as nothing
+/// else uses it.
+///
+/// This is kept apart from the rest of the context, since a type which
+/// implements `Drop` can't be built with struct update syntax.
+#[derive(Default)]
+pub(super) struct Cleanup {
+    resources: Vec<WeakRef>,
+    hooks: Vec<Box<dyn FnOnce()>>,
+}
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        // the most recently registered first, since it may depend on the
+        // ones registered before it
+        for weak in self.resources.drain(..).rev() {
+            if let Some(Atom(ResourcePrim(r))) = weak.get() {
+                r.close();
+            }
+        }
+        for hook in self.hooks.drain(..) {
+            hook();
+        }
+    }
+}
+
+fn resource_arg(e: SExp) -> std::result::Result<Resource, Error> {
+    match e {
+        Atom(ResourcePrim(r)) => Ok(r),
+        other => Err(Error::Type {
+            expected: "resource",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+impl Context {
+    /// Close a resource when this context is dropped, if it hasn't been
+    /// closed already. The context doesn't keep the resource alive.
+    ///
+    /// When the context is dropped, the resources still open are closed in
+    /// the reverse of the order they were registered in, before any hooks
+    /// added with [`on_drop`](#method.on_drop) are called. This happens even
+    /// if they are still referenced by values outside of the context.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    /// use parsley::prelude::*;
+    /// use parsley::Resource;
+    ///
+    /// let closed = Rc::new(Cell::new(false));
+    /// let closed_by_finalizer = Rc::clone(&closed);
+    /// let file = Resource::with_finalizer("file", (), move |()| closed_by_finalizer.set(true));
+    ///
+    /// let mut ctx = Context::base();
+    /// ctx.register_resource(&file);
+    /// ctx.define("file", file.into());
+    /// // the global scope and this procedure refer to each other, so neither
+    /// // would be dropped on its own
+    /// ctx.run("(define (get-file) file)").unwrap();
+    ///
+    /// drop(ctx);
+    /// assert!(closed.get());
+    /// ```
+    pub fn register_resource(&mut self, resource: &Resource) {
+        let resources = &mut self.cleanup.resources;
+        resources.retain(|weak| weak.get().is_some());
+        resources.push(WeakRef::new(resource.clone().into()));
+    }
+
+    /// Call a function when this context is dropped, after its registered
+    /// resources have been closed. Functions are called in the order they
+    /// were added.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use parsley::prelude::*;
+    /// use parsley::Resource;
+    ///
+    /// let log = Rc::new(RefCell::new(Vec::new()));
+    /// let mut ctx = Context::base();
+    /// for name in ["first", "second"] {
+    ///     let log = Rc::clone(&log);
+    ///     ctx.on_drop(move || log.borrow_mut().push(format!("hook {name}")));
+    /// }
+    /// let log_by_finalizer = Rc::clone(&log);
+    /// let file = Resource::with_finalizer("file", (), move |()| {
+    ///     log_by_finalizer.borrow_mut().push("file".to_string());
+    /// });
+    /// ctx.register_resource(&file);
+    ///
+    /// drop(ctx);
+    /// assert_eq!(*log.borrow(), ["file", "hook first", "hook second"]);
+    /// ```
+    pub fn on_drop(&mut self, hook: impl FnOnce() + 'static) {
+        self.cleanup.hooks.push(Box::new(hook));
+    }
+
+    /// `(weak-ref-deref r)`: the target of a weak reference, or `#f` if it
+    /// has been dropped.
+    pub(super) fn weak_ref_deref(e: SExp) -> Result {
+        match e {
+            Atom(WeakRefPrim(w)) => Ok(w.get().unwrap_or_else(|| false.into())),
+            other => Err(Error::Type {
+                expected: "weak-ref",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    /// `(close-resource r)`
+    pub(super) fn close_resource(e: SExp) -> Result {
+        resource_arg(e)?.close();
+        Ok(Atom(Void))
+    }
+
+    /// `(resource-closed? r)`
+    pub(super) fn resource_closed(e: SExp) -> Result {
+        Ok(resource_arg(e)?.is_closed().into())
+    }
+}
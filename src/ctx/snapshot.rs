@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use super::super::env;
+use super::super::{Env, Ns};
+use super::Context;
+
+/// A copy of the user definitions in a [`Context`](struct.Context.html), which
+/// can be restored later. See [`Context::snapshot`](struct.Context.html#method.snapshot).
+pub struct Snapshot {
+    /// Each scope in the environment, innermost first, with its definitions
+    /// at the time of the snapshot.
+    scopes: Vec<(Rc<Env>, Ns)>,
+    revision: u64,
+}
+
+impl Snapshot {
+    /// The revision of the user environment when the snapshot was taken, to
+    /// pass to [`Context::changes_since`](struct.Context.html#method.changes_since).
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.revision
+    }
+}
+
+impl Context {
+    /// Take a snapshot of the user environment.
+    ///
+    /// Only the definitions in each scope are copied, and the scopes
+    /// themselves are restored in place, so closures created before the
+    /// snapshot see the restored definitions too.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define x 1)").unwrap();
+    /// let snapshot = ctx.snapshot();
+    ///
+    /// ctx.run("(set! x 2) (define y 3)").unwrap();
+    /// ctx.restore(&snapshot);
+    ///
+    /// assert_eq!(ctx.run("x").unwrap(), SExp::from(1));
+    /// assert!(ctx.run("y").is_err());
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        let mut scopes = Vec::new();
+        let mut envt = Some(self.cont.borrow().env());
+
+        while let Some(e) = envt {
+            envt = e.parent();
+            let defns = e.local();
+            scopes.push((e, defns));
+        }
+
+        Snapshot {
+            scopes,
+            revision: env::current_revision(),
+        }
+    }
+
+    /// Roll the user environment back to a snapshot, including any scopes
+    /// which have been pushed or popped since it was taken.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        for (envt, defns) in &snapshot.scopes {
+            envt.replace(defns.clone());
+        }
+
+        if let Some((innermost, _)) = snapshot.scopes.first() {
+            self.use_env(innermost.clone());
+        }
+    }
+
+    /// The global scope of the user environment.
+    pub(super) fn global_env(&self) -> Rc<Env> {
+        let mut envt = self.cont.borrow().env();
+        while let Some(parent) = envt.parent() {
+            envt = parent;
+        }
+        envt
+    }
+
+    /// The current revision of the user environment, which can be passed to
+    /// [`changes_since`](#method.changes_since) later.
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        env::current_revision()
+    }
+
+    /// List the names in the global scope which have been defined, changed or
+    /// removed since a revision (or [snapshot](struct.Snapshot.html#method.id)).
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define x 1) (define y 2)").unwrap();
+    /// let snapshot = ctx.snapshot();
+    ///
+    /// ctx.run("(set! x 3) (define z 4) (let ((w 5)) w)").unwrap();
+    /// assert_eq!(ctx.changes_since(snapshot.id()), vec!["x", "z"]);
+    ///
+    /// ctx.restore(&snapshot);
+    /// assert_eq!(ctx.changes_since(snapshot.id()), vec!["x", "z"]);
+    /// ```
+    #[must_use]
+    pub fn changes_since(&self, revision: u64) -> Vec<String> {
+        self.global_env().changes_since(revision)
+    }
+
+    /// Check whether the global scope has changed since the context was
+    /// created or last [marked clean](#method.mark_clean).
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    /// assert!(!ctx.is_dirty());
+    ///
+    /// ctx.run("(define x 1)").unwrap();
+    /// assert!(ctx.is_dirty());
+    ///
+    /// ctx.mark_clean();
+    /// ctx.run("(+ x 1)").unwrap();
+    /// assert!(!ctx.is_dirty());
+    /// ```
+    #[must_use]
+    pub fn is_dirty(&self) -> bool {
+        self.global_env().last_change() > self.clean_revision
+    }
+
+    /// Mark the current state of the user environment as clean, e.g. once it
+    /// has been saved.
+    pub fn mark_clean(&mut self) {
+        self.clean_revision = env::current_revision();
+    }
+}
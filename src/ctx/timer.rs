@@ -0,0 +1,101 @@
+use super::super::Primitive::{Number, Void};
+use super::super::SExp::{self, Atom, Null};
+use super::super::{Error, Result};
+use super::host::Request;
+use super::Context;
+
+/// A one-shot timer set by `after`.
+#[derive(Clone)]
+pub(super) struct Timer {
+    /// When the timer is due, by the scheduler's clock.
+    due: usize,
+    thunk: SExp,
+}
+
+fn milliseconds(exp: SExp) -> std::result::Result<usize, Error> {
+    match exp {
+        Atom(Number(n)) if f64::from(n.clone()) >= 0. => Ok(usize::from(n)),
+        other => Err(Error::Type {
+            expected: "non-negative number",
+            given: other.type_of().to_string(),
+        }),
+    }
+}
+
+impl Context {
+    /// `(after ms thunk)`: call the thunk once the scheduler's clock has
+    /// advanced by `ms`. Timers which are due at the same time are called in
+    /// the order they were set.
+    pub(super) fn after(&mut self, expr: SExp) -> Result {
+        let (ms, rest) = self.eval_args(expr)?.split_car()?;
+        let thunk = rest.car()?;
+
+        let due = self.clock + milliseconds(ms)?;
+        let i = self.timers.partition_point(|t| t.due <= due);
+        self.timers.insert(i, Timer { due, thunk });
+
+        Ok(Atom(Void))
+    }
+
+    /// `(sleep ms)`: wait, then advance the scheduler's clock, calling any
+    /// timers which come due in the meantime. Under an async host, the host
+    /// does the waiting (see [`AsyncHost::sleep`](trait.AsyncHost.html#method.sleep));
+    /// otherwise the thread sleeps, which is not possible on wasm.
+    pub(super) fn sleep(&mut self, expr: SExp) -> Result {
+        let ms = milliseconds(self.eval(expr.car()?)?)?;
+
+        if self.awaits.is_some() {
+            self.suspend(Request::Sleep(ms))?;
+        } else if cfg!(target_arch = "wasm32") {
+            // there's no blocking the only thread
+            return Err(Error::Suspended);
+        } else {
+            std::thread::sleep(std::time::Duration::from_millis(ms as u64));
+        }
+
+        self.advance_clock(ms)?;
+        Ok(Atom(Void))
+    }
+
+    /// Advance the scheduler's clock, calling each timer set by `after` which
+    /// comes due, in order. A host can call this regularly (e.g. once per
+    /// animation frame) with the time elapsed since the last call.
+    ///
+    /// # Errors
+    /// Returns `Err` if a timer fails. Any timers after it are left pending.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define ticks 0) (after 20 (lambda () (set! ticks (+ ticks 1))))").unwrap();
+    /// assert_eq!(ctx.next_timer(), Some(20));
+    ///
+    /// ctx.advance_clock(15).unwrap();
+    /// assert_eq!(ctx.run("ticks").unwrap(), SExp::from(0));
+    /// assert_eq!(ctx.next_timer(), Some(5));
+    ///
+    /// ctx.advance_clock(15).unwrap();
+    /// assert_eq!(ctx.run("ticks").unwrap(), SExp::from(1));
+    /// assert_eq!(ctx.next_timer(), None);
+    /// ```
+    pub fn advance_clock(&mut self, ms: usize) -> std::result::Result<(), Error> {
+        let until = self.clock + ms;
+
+        while self.timers.first().is_some_and(|t| t.due <= until) {
+            let timer = self.timers.remove(0);
+            self.clock = timer.due;
+            self.eval(Null.cons(timer.thunk))?;
+        }
+
+        self.clock = until;
+        Ok(())
+    }
+
+    /// How many milliseconds until the next timer is due, if any is set.
+    #[must_use]
+    pub fn next_timer(&self) -> Option<usize> {
+        self.timers.first().map(|t| t.due - self.clock)
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use super::super::Primitive::{
+    Boolean, Character, Number, String as LispString, Symbol, Undefined,
+};
+use super::super::SExp::{self, Atom, Pair};
+use super::super::{Error, Result};
+use super::Context;
+
+/// The types which the checker knows how to recognize in literals.
+const CHECKED_TYPES: [&str; 5] = ["number", "string", "bool", "char", "symbol"];
+
+fn type_name(ty: &SExp) -> Option<&str> {
+    match ty {
+        Atom(Symbol(s)) if s == "boolean" => Some("bool"),
+        Atom(Symbol(s)) if CHECKED_TYPES.contains(&s.as_str()) => Some(s),
+        _ => None,
+    }
+}
+
+/// The type of an argument, if it is a literal.
+fn literal_type(arg: &SExp) -> Option<&'static str> {
+    match arg {
+        Atom(Number(_)) => Some("number"),
+        Atom(LispString(_)) => Some("string"),
+        Atom(Boolean(_)) => Some("bool"),
+        Atom(Character(_)) => Some("char"),
+        Pair { head, tail } if **head == SExp::sym("quote") => match &**tail {
+            Pair { head, .. } if matches!(**head, Atom(Symbol(_))) => Some("symbol"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Look for `(: name type)` forms anywhere in some code.
+fn collect_annotations(code: &SExp, annotations: &mut HashMap<String, SExp>) {
+    if let Pair { head, tail } = code {
+        if **head == SExp::sym(":") {
+            if let (Some(Atom(Symbol(name))), Some(ty)) = (tail.iter().next(), tail.iter().nth(1)) {
+                annotations.insert(name.clone(), ty.clone());
+            }
+        }
+
+        if **head != SExp::sym("quote") {
+            code.iter()
+                .for_each(|e| collect_annotations(e, annotations));
+        }
+    }
+}
+
+/// Check each application of an annotated procedure in some code, comparing
+/// the types of any literal arguments against the annotation.
+fn check(code: &SExp, annotations: &HashMap<String, SExp>) -> std::result::Result<(), Error> {
+    let Pair { head, tail } = code else {
+        return Ok(());
+    };
+
+    match &**head {
+        Atom(Symbol(q)) if q == "quote" || q == ":" => return Ok(()),
+        Atom(Symbol(name)) => {
+            if let Some(Pair {
+                head: arrow,
+                tail: types,
+            }) = annotations.get(name)
+            {
+                if **arrow == SExp::sym("->") {
+                    let n_args = types.len().saturating_sub(1);
+                    for (i, (arg, ty)) in tail.iter().zip(types.iter().take(n_args)).enumerate() {
+                        match (literal_type(arg), type_name(ty)) {
+                            (Some(given), Some(expected)) if given != expected => {
+                                return Err(Error::Annotation {
+                                    name: name.clone(),
+                                    arg: i + 1,
+                                    expected: expected.to_string(),
+                                    given: format!("{given} {arg:?}"),
+                                });
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+        }
+        _ => (),
+    }
+
+    code.iter().try_for_each(|e| check(e, annotations))
+}
+
+impl Context {
+    /// `(: name type)`: record a type annotation for a name. A procedure type
+    /// is written `(-> arg-type ... result-type)`.
+    pub(super) fn eval_annotate(&mut self, expr: SExp) -> Result {
+        let (name, tail) = expr.split_car()?;
+        let ty = tail.car()?;
+
+        match name {
+            Atom(Symbol(name)) => {
+                self.annotations.insert(name, ty);
+                Ok(Atom(Undefined))
+            }
+            other => Err(Error::Type {
+                expected: "symbol",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    /// Get the type annotation recorded for a name with `(: name type)`.
+    #[must_use]
+    pub fn type_annotation(&self, name: &str) -> Option<SExp> {
+        self.annotations.get(name).cloned()
+    }
+
+    /// Turn the static type check on or off for code passed to
+    /// [`run`](#method.run). It is off by default.
+    ///
+    /// The check is gradual: it only compares literal arguments (numbers,
+    /// strings, booleans, characters and quoted symbols) in applications of
+    /// procedures with a `(: name (-> ...))` annotation, and ignores anything
+    /// it cannot see the type of.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    /// ctx.set_type_check(true);
+    ///
+    /// ctx.run("(: double (-> number number))").unwrap();
+    /// ctx.run("(define (double x) (* 2 x))").unwrap();
+    ///
+    /// assert_eq!(ctx.run("(double 4)").unwrap(), SExp::from(8));
+    /// // rejected before anything is evaluated
+    /// assert!(ctx.run("(display \"hi\") (double \"4\")").is_err());
+    /// assert_eq!(ctx.get_output(), None);
+    /// ```
+    pub fn set_type_check(&mut self, enabled: bool) {
+        self.type_check = enabled;
+    }
+
+    pub(super) fn check_types(&self, code: &SExp) -> std::result::Result<(), Error> {
+        let mut annotations = self.annotations.clone();
+        collect_annotations(code, &mut annotations);
+        check(code, &annotations)
+    }
+}
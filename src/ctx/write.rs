@@ -1,5 +1,6 @@
 use std::fmt::{Error, Write};
 
+use super::super::{effect, Error as LispError, NumberFormat};
 use super::Context;
 
 const PREALLOC_BUFFER: usize = 199;
@@ -21,6 +22,73 @@ impl Context {
     pub fn get_output(&mut self) -> Option<String> {
         self.out.take()
     }
+
+    /// Limit how much output (in bytes) each evaluation may write with
+    /// `display` and friends, or remove the limit with `None`. Writing past
+    /// the limit stops evaluation with `Error::OutputLimit`, so that a runaway
+    /// loop can't keep filling the buffer.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base().capturing();
+    ///
+    /// ctx.set_output_limit(Some(6));
+    /// assert!(ctx.run("(display \"abc\") (display \"def\")").is_ok());
+    /// assert!(ctx.run("(display \"abcd\") (display \"efg\")").is_err());
+    /// assert_eq!(ctx.get_output().unwrap(), "abcdefabcd");
+    ///
+    /// // each evaluation gets the whole limit
+    /// ctx.capture();
+    /// assert!(ctx.run("(display \"abcdef\")").is_ok());
+    /// assert!(ctx.run("(let loop () (display \"x\") (loop))").is_err());
+    /// assert_eq!(ctx.get_output().unwrap(), "abcdefxxxxxx");
+    /// ```
+    pub fn set_output_limit(&mut self, limit: Option<usize>) {
+        self.output_limit = limit;
+    }
+
+    /// Write output for the current evaluation, unless it would go past the
+    /// output limit.
+    pub(super) fn write_output(&mut self, s: &str) -> Result<(), LispError> {
+        let written = self.output_written + s.len();
+        match self.output_limit {
+            Some(limit) if written > limit => Err(LispError::OutputLimit(limit)),
+            _ => {
+                self.output_written = written;
+                Ok(self.write_str(s)?)
+            }
+        }
+    }
+
+    /// Set the default format used by `number->string`.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// use parsley::{Notation, NumberFormat};
+    /// let mut ctx = Context::base();
+    ///
+    /// assert_eq!(ctx.run("(number->string 1e-7)").unwrap(), SExp::from("1e-7"));
+    ///
+    /// ctx.set_number_format(NumberFormat {
+    ///     notation: Notation::Fixed,
+    ///     precision: None,
+    /// });
+    /// assert_eq!(
+    ///     ctx.run("(number->string 1e-7)").unwrap(),
+    ///     SExp::from("0.0000001")
+    /// );
+    /// ```
+    pub fn set_number_format(&mut self, format: NumberFormat) {
+        self.number_format = format;
+    }
+
+    /// Get the default format used by `number->string`.
+    #[must_use]
+    pub fn number_format(&self) -> NumberFormat {
+        self.number_format
+    }
 }
 
 impl Write for Context {
@@ -28,6 +96,7 @@ impl Write for Context {
         if let Some(ref mut st) = &mut self.out {
             write!(st, "{}", s)
         } else {
+            effect::record();
             print!("{}", s);
             Ok(())
         }
@@ -0,0 +1,77 @@
+//! A count of the side effects which restoring a snapshot can't undo, such as
+//! changing a set or printing to standard output, so that evaluation is only
+//! replayed from a snapshot when that won't repeat any of them.
+//!
+//! Side effects are only counted while an evaluation is being watched, so
+//! evaluation which is never replayed pays for no more than a check. Values
+//! can't leave the thread they were made on, so the counts are kept per
+//! thread.
+
+use std::cell::Cell;
+
+thread_local! {
+    static EFFECTS: Cell<u64> = const { Cell::new(0) };
+    /// How many scopes have been made while watching, to number each new one.
+    static SCOPES: Cell<u64> = const { Cell::new(0) };
+    /// While an evaluation is watched, changes to (non-top-level) scopes
+    /// numbered below this are side effects, since they were made before it.
+    static WATCHED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Count a side effect, if an evaluation is being watched.
+pub fn record() {
+    if WATCHED.with(Cell::get).is_some() {
+        EFFECTS.with(|n| n.set(n.get() + 1));
+    }
+}
+
+/// The number for a new scope. Scopes made while nothing is watched are all
+/// numbered 0, which is before any watched evaluation.
+pub fn new_scope() -> u64 {
+    if WATCHED.with(Cell::get).is_none() {
+        return 0;
+    }
+    SCOPES.with(|n| {
+        n.set(n.get() + 1);
+        n.get()
+    })
+}
+
+/// Count a change to a scope, if it was made before the evaluation being
+/// watched started.
+pub fn scope_changed(scope: u64) {
+    if matches!(WATCHED.with(Cell::get), Some(watched) if scope < watched) {
+        EFFECTS.with(|n| n.set(n.get() + 1));
+    }
+}
+
+/// Start watching an evaluation: changes to the scopes made so far count as
+/// side effects until the returned watch is dropped.
+pub fn watch() -> Watch {
+    let outer = WATCHED.with(|watched| watched.replace(Some(SCOPES.with(Cell::get) + 1)));
+    Watch {
+        start: EFFECTS.with(Cell::get),
+        outer,
+    }
+}
+
+/// An evaluation being watched for side effects.
+pub struct Watch {
+    /// The number of side effects when watching started.
+    start: u64,
+    /// What was being watched before, if anything.
+    outer: Option<u64>,
+}
+
+impl Watch {
+    /// Whether any side effects have happened since watching started.
+    pub fn any(&self) -> bool {
+        EFFECTS.with(Cell::get) != self.start
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        WATCHED.with(|watched| watched.set(self.outer));
+    }
+}
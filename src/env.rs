@@ -2,24 +2,42 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::iter::IntoIterator;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use super::{Error, Result, SExp};
+use super::{effect, Error, Result, SExp};
 
 /// A type to represent an execution environment.
 pub type Ns = HashMap<String, SExp>;
 
 type Link = Option<Rc<Env>>;
 
+/// Counts changes to top-level scopes, so that revisions of different scopes
+/// can be compared (e.g. after the global scope is replaced).
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+pub fn current_revision() -> u64 {
+    REVISION.load(Ordering::Relaxed)
+}
+
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Env {
     env: RefCell<Ns>,
     parent: Link,
+    /// The revision at which each name was last changed, for top-level
+    /// scopes only.
+    changes: RefCell<HashMap<String, u64>>,
+    /// The number of the scope, in the order scopes were made while an
+    /// evaluation was watched, to tell whether changing it is a side effect
+    /// (see `effect::scope_changed`).
+    number: u64,
 }
 
 impl Env {
     pub fn new(parent: Link) -> Self {
         Self {
             parent,
+            number: effect::new_scope(),
             ..Self::default()
         }
     }
@@ -41,9 +59,47 @@ impl Env {
     }
 
     pub fn extend(&self, other: Ns) {
+        for key in other.keys() {
+            self.touch(key);
+        }
         self.env.borrow_mut().extend(other.into_iter());
     }
 
+    /// Record a change to a definition: by revision, if this is a top-level
+    /// scope, or otherwise as a possible side effect.
+    fn touch(&self, key: &str) {
+        if self.parent.is_none() {
+            let revision = REVISION.fetch_add(1, Ordering::Relaxed) + 1;
+            self.changes.borrow_mut().insert(key.to_string(), revision);
+        } else {
+            effect::scope_changed(self.number);
+        }
+    }
+
+    /// The names defined, changed or removed in this scope after a revision,
+    /// if it is a top-level scope.
+    pub fn changes_since(&self, revision: u64) -> Vec<String> {
+        let mut names = self
+            .changes
+            .borrow()
+            .iter()
+            .filter(|(_, &changed)| changed > revision)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// The revision of the most recent change to this scope.
+    pub fn last_change(&self) -> u64 {
+        self.changes
+            .borrow()
+            .values()
+            .max()
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub fn get(&self, key: &str) -> Option<SExp> {
         for ns in self.iter() {
             if let Some(val) = ns.env.borrow().get(key) {
@@ -54,17 +110,73 @@ impl Env {
         None
     }
 
+    /// Look up a key, and whether it was found in a top-level scope.
+    pub fn lookup(&self, key: &str) -> Option<(SExp, bool)> {
+        for ns in self.iter() {
+            if let Some(val) = ns.env.borrow().get(key) {
+                return Some((val.clone(), ns.parent.is_none()));
+            }
+        }
+
+        None
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.iter().any(|ns| ns.env.borrow().contains_key(key))
+    }
+
+    /// The scope a key is bound in: this one, or the nearest of its parents.
+    pub fn scope_of(self: &Rc<Self>, key: &str) -> Option<Rc<Self>> {
+        let mut scope = Rc::clone(self);
+        loop {
+            if scope.contains_local(key) {
+                return Some(scope);
+            }
+            scope = scope.parent()?;
+        }
+    }
+
+    /// Check whether a key is bound in this scope (not its parents).
+    pub fn contains_local(&self, key: &str) -> bool {
+        self.env.borrow().contains_key(key)
+    }
+
+    /// Get a copy of the definitions in this scope only (not its parents).
+    pub fn local(&self) -> Ns {
+        self.env.borrow().clone()
+    }
+
+    /// Replace all of the definitions in this scope (not its parents).
+    pub fn replace(&self, defns: Ns) {
+        let old = self.env.replace(defns);
+        let new = self.env.borrow();
+
+        for key in old.keys().chain(new.keys()) {
+            if old.get(key) != new.get(key) {
+                self.touch(key);
+            }
+        }
+    }
+
+    /// Remove all of the definitions in this scope (not its parents).
+    pub fn clear(&self) {
+        for key in self.env.borrow().keys() {
+            self.touch(key);
+        }
+        self.env.borrow_mut().clear();
+    }
+
     pub fn define(&self, key: &str, val: SExp) {
+        self.touch(key);
         self.env.borrow_mut().insert(key.to_string(), val);
     }
 
     pub fn set(&self, key: &str, val: SExp) -> Result {
-        let possible_err = Error::UndefinedSymbol {
-            sym: key.to_string(),
-        };
+        let possible_err = Error::undefined(key);
 
         for ns in self.iter() {
             if ns.env.borrow().get(key).is_some() {
+                ns.touch(key);
                 return ns
                     .env
                     .borrow_mut()
@@ -77,6 +189,14 @@ impl Env {
     }
 }
 
+/// Environments are only equal if they are the same environment, not just
+/// if they have the same bindings.
+impl PartialEq for Env {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
 pub struct Iter<'a>(Option<&'a Env>);
 
 impl<'a> Iterator for Iter<'a> {
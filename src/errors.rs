@@ -1,45 +1,145 @@
 use std::fmt;
 
-use super::SExp;
+use super::{Primitive, ReplControl, SExp, Symbol};
 
+/// A range of byte offsets into parsed source code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    #[must_use]
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// An error in the form of some code, either while parsing it or while
+/// expanding a special form.
+///
+/// Errors found while parsing carry a [`Span`](struct.Span.html) locating
+/// them in the source.
 #[derive(Debug)]
 pub enum SyntaxError {
-    UnmatchedQuote(String),
+    /// A string literal with no closing quote. The span runs from the opening
+    /// quote to the end of the input.
+    UnterminatedString {
+        span: Span,
+    },
+    /// A list or vector closed by the wrong delimiter (`given`), never closed
+    /// (`given` is `None`), or closed without being opened (`expected` is
+    /// `None`). The span runs from the opening to the closing delimiter, or as
+    /// far as either exists.
     UnmatchedParen {
-        exp: String,
-        expected: char,
+        expected: Option<char>,
         given: Option<char>,
+        span: Span,
+    },
+    /// A ` . ` which is not between the last two items of a list.
+    UnexpectedDot {
+        span: Span,
+    },
+    /// A numeric literal which could not be parsed.
+    InvalidNumber {
+        text: String,
+        span: Option<Span>,
     },
     InvalidCond(SExp),
-    NotANumber(String),
+    /// A `do` variable binding which is not `(var init)` or `(var init step)`,
+    /// or a test clause which is not a list.
+    InvalidDoClause(SExp),
+    InvalidSyntaxRule(SExp),
+    NoMatchingRule(SExp),
+    EllipsisDepth(String),
+    InvalidRecordField(String),
     NotAPrimitive(String),
     NotAToken(String),
+    /// Lists and vectors nested more deeply than the parser allows.
+    TooDeep(usize),
+    /// A `define/contract` contract which is not of the form
+    /// `(-> arg-pred ... result-pred)` with a predicate for each parameter.
+    InvalidContract(SExp),
 }
 
+impl SyntaxError {
+    /// The location of the error in the source, if known.
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            SyntaxError::UnterminatedString { span }
+            | SyntaxError::UnmatchedParen { span, .. }
+            | SyntaxError::UnexpectedDot { span } => Some(*span),
+            SyntaxError::InvalidNumber { span, .. } => *span,
+            _ => None,
+        }
+    }
+
+    pub(crate) fn with_span(mut self, at: Span) -> Self {
+        if let SyntaxError::InvalidNumber { span, .. } = &mut self {
+            span.get_or_insert(at);
+        }
+        self
+    }
+}
+
+impl ::std::error::Error for SyntaxError {}
+
 impl fmt::Display for SyntaxError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            SyntaxError::UnmatchedQuote(s) => write!(f, "Unmatched quote: {}", s),
+            SyntaxError::UnterminatedString { span } => {
+                write!(f, "Unterminated string starting at {}", span.start)
+            }
             SyntaxError::UnmatchedParen {
-                exp,
-                expected,
+                expected: Some(e),
                 given: Some(g),
+                span,
+            } => write!(f, "Paren mismatch: expected {e}, given {g} at {span}"),
+            SyntaxError::UnmatchedParen {
+                expected: Some(e),
+                span,
+                ..
             } => write!(
                 f,
-                "Paren mismatch: expected {}, given {} in expression {}",
-                expected, g, exp
+                "Paren mismatch: expected {e} and no match found for paren at {}",
+                span.start
             ),
-            SyntaxError::UnmatchedParen { exp, expected, .. } => write!(
+            SyntaxError::UnmatchedParen { given, span, .. } => write!(
                 f,
-                "Paren mismatch: expected {} and no match found in expression {}",
-                expected, exp
+                "Paren mismatch: unexpected {} at {}",
+                given.unwrap_or(')'),
+                span.start
             ),
+            SyntaxError::UnexpectedDot { span } => write!(f, "Unexpected ` . ` at {span}"),
+            SyntaxError::InvalidNumber { text, span: None } => {
+                write!(f, "Could not parse as a number: {text}")
+            }
+            SyntaxError::InvalidNumber {
+                text,
+                span: Some(span),
+            } => write!(f, "Could not parse as a number: {text} at {span}"),
             SyntaxError::InvalidCond(e) => write!(f, "Invalid `cond` clause: {}", e),
-            SyntaxError::NotANumber(s) => write!(f, "Could not parse as a number: {}", s),
+            SyntaxError::InvalidDoClause(e) => write!(f, "Invalid `do` clause: {e}"),
+            SyntaxError::InvalidSyntaxRule(e) => write!(f, "Invalid `syntax-rules` clause: {e}"),
+            SyntaxError::NoMatchingRule(e) => write!(f, "No syntax rule matches form: {e}"),
+            SyntaxError::EllipsisDepth(s) => {
+                write!(f, "Pattern variable used at the wrong ellipsis depth: {s}")
+            }
+            SyntaxError::InvalidRecordField(s) => write!(f, "Invalid record field spec: ({s})"),
             SyntaxError::NotAPrimitive(s) => {
                 write!(f, "Could not parse as a primitive value: {}", s)
             }
             SyntaxError::NotAToken(s) => write!(f, "Unrecognized token: {}", s),
+            SyntaxError::TooDeep(n) => write!(f, "Exceeded maximum nesting depth of {n}"),
+            SyntaxError::InvalidContract(e) => write!(f, "Invalid contract: {e}"),
         }
     }
 }
@@ -53,7 +153,16 @@ pub enum Error {
         given: String,
     },
     UndefinedSymbol {
-        sym: String,
+        sym: Symbol,
+        /// Bound names which `sym` may be a misspelling of, closest first.
+        suggestions: Vec<Symbol>,
+    },
+    /// A variable which is bound but has not been given a value yet, such as
+    /// an internal `define` or a `letrec` binding used by an init before its
+    /// own. `defining` is the variable whose init was being evaluated, if any.
+    Uninitialized {
+        sym: Symbol,
+        defining: Option<Symbol>,
     },
     Arity {
         expected: usize,
@@ -77,9 +186,102 @@ pub enum Error {
     Index {
         i: usize,
     },
+    DivisionByZero,
+    /// A collection was changed by the procedure called on each of its
+    /// members, such as by `set-add!` inside `set-for-each`.
+    Modified {
+        kind: &'static str,
+    },
+    /// A value passed into or out of a procedure defined with
+    /// `define/contract` failed a predicate of its contract. Bad arguments
+    /// blame the caller, and a bad result blames the procedure itself.
+    Contract {
+        name: String,
+        /// The 1-based position of the argument, or `None` for the result.
+        arg: Option<usize>,
+        expected: String,
+        given: String,
+        blame: String,
+    },
+    /// A generic procedure was called with a first argument of a type it has
+    /// no method for.
+    NoMethod {
+        name: String,
+        given: String,
+    },
+    /// A literal argument which does not match the type annotation of the
+    /// procedure it is passed to, found by the static type check.
+    Annotation {
+        name: String,
+        /// The 1-based position of the argument.
+        arg: usize,
+        expected: String,
+        given: String,
+    },
+    /// Signalled by `(error message irritant...)`.
+    User {
+        message: String,
+        irritants: Vec<SExp>,
+    },
+    /// A value passed to `raise` (or a condition re-raised by `guard`) which
+    /// was not caught.
+    Raised {
+        value: SExp,
+    },
+    /// Evaluation stopped at an `await` to wait for a future. Outside of
+    /// `Context::eval_with_async_host`, there is nothing to wait for.
+    Suspended,
+    /// Evaluation stopped at an `await` after a side effect which
+    /// `Context::eval_with_async_host` would repeat by evaluating it again.
+    SideEffect,
+    /// Raised by invoking an escape continuation, and caught by the
+    /// `call/cc` that created it.
+    Continuation {
+        id: usize,
+        value: SExp,
+    },
+    /// Raised by `exit` or `restart` to stop evaluation, for the host to act
+    /// on. It can't be caught.
+    Control(ReplControl),
+    /// More output than the limit set by `Context::set_output_limit` was
+    /// written in one evaluation. Like `Control`, it can't be caught.
+    OutputLimit(usize),
     IO(String),
 }
 
+impl Error {
+    /// An `UndefinedSymbol` error, without any suggestions.
+    pub fn undefined(sym: impl Into<Symbol>) -> Self {
+        Error::UndefinedSymbol {
+            sym: sym.into(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// The names suggested in place of an undefined symbol, if this is an
+    /// `UndefinedSymbol` error.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// let error = ctx.run("(fliter odd? '(1 2 3))").unwrap_err();
+    /// assert_eq!(error.suggestions(), ["filter"]);
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Undefined symbol: fliter (did you mean `filter`?)"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn suggestions(&self) -> &[Symbol] {
+        match self {
+            Error::UndefinedSymbol { suggestions, .. } => suggestions,
+            _ => &[],
+        }
+    }
+}
+
 impl ::std::error::Error for Error {}
 
 impl fmt::Display for Error {
@@ -89,7 +291,28 @@ impl fmt::Display for Error {
             Error::Type { expected, given } => {
                 write!(f, "Type error: expected {}, got {}", expected, given)
             }
-            Error::UndefinedSymbol { sym } => write!(f, "Undefined symbol: {}", sym),
+            Error::UndefinedSymbol { sym, suggestions } => {
+                write!(f, "Undefined symbol: {sym}")?;
+                if let Some((last, rest)) = suggestions.split_last() {
+                    let rest = rest.iter().map(|s| format!("`{s}`")).collect::<Vec<_>>();
+                    if rest.is_empty() {
+                        write!(f, " (did you mean `{last}`?)")?;
+                    } else {
+                        write!(f, " (did you mean {} or `{last}`?)", rest.join(", "))?;
+                    }
+                }
+                Ok(())
+            }
+            Error::Uninitialized {
+                sym,
+                defining: Some(defining),
+            } => write!(
+                f,
+                "Variable `{sym}` used before initialization, in the definition of `{defining}`"
+            ),
+            Error::Uninitialized { sym, .. } => {
+                write!(f, "Variable `{sym}` used before initialization")
+            }
             Error::Arity { expected, given } => write!(
                 f,
                 "Arity mismatch: expected {} parameters, got {}.",
@@ -109,6 +332,58 @@ impl fmt::Display for Error {
             Error::NullList => write!(f, "Expected a pair, got null."),
             Error::NotAProcedure { exp } => write!(f, "{} is not a procedure.", exp),
             Error::Index { i } => write!(f, "Tried to access invalid index: [{}]", i),
+            Error::DivisionByZero => write!(f, "Division by exact zero."),
+            Error::Modified { kind } => {
+                write!(f, "The {kind} was modified while iterating over it.")
+            }
+            Error::Contract {
+                name,
+                arg,
+                expected,
+                given,
+                blame,
+            } => {
+                let position = match arg {
+                    Some(n) => format!("argument {n}"),
+                    None => "result".to_string(),
+                };
+                write!(
+                    f,
+                    "Contract violation in {name}: expected {expected} for {position}, given {given}. Blaming: {blame}"
+                )
+            }
+            Error::NoMethod { name, given } => {
+                write!(f, "No method of {name} applies to type {given}")
+            }
+            Error::Annotation {
+                name,
+                arg,
+                expected,
+                given,
+            } => write!(
+                f,
+                "Type error: {name} expects {expected} for argument {arg}, given {given}"
+            ),
+            Error::User { message, irritants } => {
+                write!(f, "Error: {message}")?;
+                for irritant in irritants {
+                    write!(f, " {irritant}")?;
+                }
+                Ok(())
+            }
+            Error::Raised {
+                value: SExp::Atom(Primitive::Condition(c)),
+            } => write!(f, "{}", c.error()),
+            Error::Raised { value } => write!(f, "Uncaught exception: {value}"),
+            Error::Suspended => write!(f, "Cannot await outside of an async host."),
+            Error::SideEffect => write!(f, "Cannot await after a side effect, which would repeat."),
+            Error::Continuation { value, .. } => write!(
+                f,
+                "Continuation invoked outside of its dynamic extent, with value: {value}"
+            ),
+            Error::Control(ReplControl::Exit(code)) => write!(f, "Exited with code {code}"),
+            Error::Control(_) => write!(f, "Restart requested"),
+            Error::OutputLimit(limit) => write!(f, "Output exceeded the limit of {limit} bytes"),
             Error::IO(err) => write!(f, "I/O error: {}", err),
         }
     }
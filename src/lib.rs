@@ -32,22 +32,26 @@ mod sexp;
 
 mod cont;
 mod ctx;
+mod effect;
 mod env;
 mod errors;
 mod primitives;
 mod proc;
+mod symbol;
+mod syntax;
 mod utils;
 
 use self::cont::Cont;
-pub use self::ctx::Context;
+pub use self::ctx::{AsyncHost, BindingDoc, Context, EvalReport, ReplControl, Snapshot};
 use self::env::{Env, Ns};
-pub use self::errors::Error;
-use self::errors::SyntaxError;
-pub use self::primitives::Num;
-use self::primitives::Primitive;
+pub use self::errors::{Error, Span, SyntaxError};
+use self::primitives::{Condition, Primitive, Promise, RecordType};
+pub use self::primitives::{Notation, Num, NumberFormat, Resource};
 pub use self::proc::utils as proc_utils;
 use self::proc::{Func, Proc};
 pub use self::sexp::SExp;
+pub use self::symbol::Symbol;
+use self::syntax::Macro;
 
 /// A shorthand Result type.
 pub type Result = ::std::result::Result<SExp, Error>;
@@ -69,6 +73,39 @@ pub fn run(code: &str) -> Result {
     Context::base().run(code)
 }
 
+/// Run many independent code snippets in parallel, each in its own
+/// [base context](./struct.Context.html#method.base), and return their
+/// outcomes in the same order.
+///
+/// Values are reference-counted and can't leave the thread they were made on,
+/// so each result (or error) is rendered as a string before being returned.
+/// The snippets can't share any state with each other or with the caller.
+///
+/// Only available with the `parallel` feature, and not on WebAssembly.
+///
+/// # Example
+/// ```
+/// let results = parsley::par_run(&["(+ 1 2)", "(define x 5) (* x x)", "y"]);
+///
+/// assert_eq!(results[0], Ok("3".to_string()));
+/// assert_eq!(results[1], Ok("25".to_string()));
+/// assert!(results[2].is_err());
+/// ```
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+#[must_use]
+pub fn par_run(snippets: &[&str]) -> Vec<::std::result::Result<String, String>> {
+    use rayon::prelude::*;
+
+    snippets
+        .par_iter()
+        .map(|code| {
+            run(code)
+                .map(|value| value.to_string())
+                .map_err(|error| error.to_string())
+        })
+        .collect()
+}
+
 /// Quick access to the important stuff.
 pub mod prelude {
     pub use super::{eval, run, sexp, Context, SExp};
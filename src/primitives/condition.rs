@@ -0,0 +1,69 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::super::{Error, SExp};
+use super::Primitive::Condition as ConditionPrim;
+
+/// A primitive value that wraps an error from the interpreter, so that it can
+/// be caught and inspected by Scheme code.
+#[derive(Clone)]
+pub struct Condition(Rc<Error>);
+
+impl Condition {
+    pub fn new(err: Error) -> Self {
+        Self(Rc::new(err))
+    }
+
+    pub fn error(&self) -> &Error {
+        &self.0
+    }
+
+    /// The message of the error, as given to `error` or as it would be
+    /// printed.
+    pub fn message(&self) -> String {
+        match &*self.0 {
+            Error::User { message, .. } => message.clone(),
+            err => err.to_string(),
+        }
+    }
+
+    /// The irritants given to `error`, if that is where the error came from.
+    pub fn irritants(&self) -> SExp {
+        match &*self.0 {
+            Error::User { irritants, .. } => irritants.iter().cloned().collect(),
+            _ => SExp::Null,
+        }
+    }
+
+    /// Get the wrapped error back, to raise it again. If the condition is
+    /// still referenced elsewhere, the condition itself is raised.
+    pub fn into_error(self) -> Error {
+        Rc::try_unwrap(self.0).unwrap_or_else(|err| Error::Raised {
+            value: SExp::Atom(ConditionPrim(Self(err))),
+        })
+    }
+}
+
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<condition: {}>", self.0)
+    }
+}
+
+impl From<Condition> for SExp {
+    fn from(c: Condition) -> Self {
+        SExp::Atom(ConditionPrim(c))
+    }
+}
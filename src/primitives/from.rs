@@ -7,6 +7,49 @@ use super::{
     Primitive::{self, Boolean, Character, Number, String, Symbol},
 };
 
+/// Whether an atom starts like a number: with a radix or exactness prefix,
+/// or with a digit, possibly after signs and a decimal point. Symbols like
+/// `-`, `...` and `->x` don't.
+fn looks_numeric(s: &str) -> bool {
+    if let Some(prefix) = s.strip_prefix('#') {
+        return prefix.starts_with(['x', 'X', 'o', 'O', 'b', 'B', 'd', 'D', 'e', 'E', 'i', 'I']);
+    }
+    let unsigned = s.trim_start_matches(['+', '-']);
+    let digits = unsigned.strip_prefix('.').unwrap_or(unsigned);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// The names of characters which can be written as `#\name`.
+pub(super) const CHAR_NAMES: [(&str, char); 9] = [
+    ("alarm", '\u{7}'),
+    ("backspace", '\u{8}'),
+    ("delete", '\u{7f}'),
+    ("escape", '\u{1b}'),
+    ("newline", '\n'),
+    ("null", '\0'),
+    ("return", '\r'),
+    ("space", ' '),
+    ("tab", '\t'),
+];
+
+/// Read the part of a character literal after `#\`: a single character, a
+/// name like `space`, or a hex scalar value like `x41` (optionally followed by
+/// a `;`).
+fn parse_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+
+    if let Some(&(_, c)) = CHAR_NAMES.iter().find(|(name, _)| *name == s) {
+        return Some(c);
+    }
+
+    let hex = s.strip_prefix('x')?;
+    let hex = hex.strip_suffix(';').unwrap_or(hex);
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
 impl FromStr for Primitive {
     type Err = SyntaxError;
 
@@ -14,15 +57,23 @@ impl FromStr for Primitive {
         match s {
             "#t" => return Ok(Boolean(true)),
             "#f" => return Ok(Boolean(false)),
+            "#!default" => return Ok(Primitive::Default),
+            // markers in lambda lists
+            "#!optional" | "#!rest" => return Ok(Symbol(s.to_string())),
             _ => (),
         }
 
-        if let Ok(num) = s.parse::<Num>() {
-            return Ok(Number(num));
+        match s.parse::<Num>() {
+            Ok(num) => return Ok(Number(num)),
+            // `1.2.3` or `--5` is a mistake, not a symbol
+            Err(err) if looks_numeric(s) => return Err(err),
+            Err(_) => (),
         }
 
-        if s.len() == 3 && s.starts_with("#\\") {
-            return Ok(Character(s.chars().nth(2).unwrap()));
+        if let Some(name) = s.strip_prefix("#\\") {
+            return parse_char(name)
+                .map(Character)
+                .ok_or_else(|| SyntaxError::NotAPrimitive(s.to_string()));
         }
 
         if s.starts_with('"') && s.ends_with('"') {
@@ -1,29 +1,67 @@
 use std::fmt;
+use std::rc::Rc;
 use std::string::String as CoreString;
 
-use super::{proc::Proc, Ns, SExp};
+use super::{proc::Proc, syntax::Macro, SExp};
 
 use self::Primitive::{
-    Boolean, Character, Env, Number, Procedure, String, Symbol, Undefined, Vector, Void,
+    Boolean, Character, Condition as ConditionPrim, Deque as DequePrim, Env, Heap as HeapPrim,
+    Macro as MacroPrim, Number, Port as PortPrim, Procedure, Promise as PromisePrim,
+    Record as RecordPrim, Resource as ResourcePrim, Set as SetPrim, String, Symbol, Undefined,
+    Values, Vector, Void, WeakRef as WeakRefPrim,
 };
 
-pub use self::num::Num;
+pub use self::condition::Condition;
+pub use self::num::{Notation, Num, NumberFormat};
+pub use self::port::Port;
+pub use self::promise::Promise;
+pub use self::queue::{Deque, Heap};
+pub use self::record::{Record, RecordType};
+pub use self::resource::Resource;
+pub use self::set::Set;
+pub use self::weak::WeakRef;
 
+mod condition;
 mod from;
 mod num;
+mod port;
+mod promise;
+mod queue;
+mod record;
+mod resource;
+mod set;
+mod tracked;
+mod weak;
 
 #[derive(Clone, PartialEq)]
 pub enum Primitive {
     Void,
     Undefined,
+    /// The value of an optional parameter which was not given (`#!default`).
+    Default,
     Boolean(bool),
     Character(char),
     Number(Num),
     String(CoreString),
     Symbol(CoreString),
-    Env(Ns),
+    /// A first-class environment, from `the-environment` or
+    /// `make-environment`.
+    Env(Rc<super::Env>),
     Procedure(Proc),
+    Macro(Macro),
+    Promise(Promise),
+    Port(Port),
+    Heap(Heap),
+    Deque(Deque),
+    Set(Set),
+    Record(Record),
+    Condition(Condition),
+    /// A value held on behalf of the host.
+    Resource(Resource),
+    WeakRef(WeakRef),
     Vector(Vec<SExp>),
+    /// The results of `(values ...)` with any number of arguments but one.
+    Values(Vec<SExp>),
 }
 
 impl fmt::Debug for Primitive {
@@ -31,13 +69,28 @@ impl fmt::Debug for Primitive {
         match self {
             Void => f.write_str("#<void>"),
             Undefined => f.write_str("#<undefined>"),
+            Self::Default => f.write_str("#!default"),
             Boolean(b) => f.write_str(if *b { "#t" } else { "#f" }),
-            Character(c) => write!(f, "#\\{}", c),
+            Character(c) => match from::CHAR_NAMES.iter().find(|(_, named)| named == c) {
+                Some((name, _)) => write!(f, "#\\{name}"),
+                None if c.is_control() => write!(f, "#\\x{:x}", u32::from(*c)),
+                None => write!(f, "#\\{c}"),
+            },
             Number(n) => write!(f, "{}", n),
             String(s) => write!(f, "\"{}\"", s),
             Symbol(s) => write!(f, "{}", s),
             Env(_) => write!(f, "#<environment>"),
             Procedure(p) => write!(f, "{}", p),
+            MacroPrim(m) => write!(f, "{m}"),
+            PromisePrim(p) => write!(f, "{p}"),
+            PortPrim(p) => write!(f, "{p}"),
+            HeapPrim(h) => write!(f, "{h}"),
+            DequePrim(d) => write!(f, "{d}"),
+            SetPrim(s) => write!(f, "{s}"),
+            RecordPrim(r) => write!(f, "{r:?}"),
+            ConditionPrim(c) => write!(f, "{c:?}"),
+            ResourcePrim(r) => write!(f, "{r}"),
+            WeakRefPrim(w) => write!(f, "{w}"),
             Vector(v) => write!(
                 f,
                 "#({})",
@@ -46,6 +99,14 @@ impl fmt::Debug for Primitive {
                     .collect::<Vec<_>>()
                     .join(" ")
             ),
+            Values(v) => write!(
+                f,
+                "{}",
+                v.iter()
+                    .map(|e| format!("{e:?}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
         }
     }
 }
@@ -54,17 +115,33 @@ impl fmt::Display for Primitive {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Undefined | Void => Ok(()),
+            Self::Default => f.write_str("#!default"),
             Boolean(b) => f.write_str(if *b { "#t" } else { "#f" }),
             Character(c) => write!(f, "{}", c),
             Number(n) => write!(f, "{}", n),
             String(s) | Symbol(s) => f.write_str(s),
             Env(_) => write!(f, "#<environment>"),
             Procedure(p) => write!(f, "{}", p),
+            MacroPrim(m) => write!(f, "{m}"),
+            PromisePrim(p) => write!(f, "{p}"),
+            PortPrim(p) => write!(f, "{p}"),
+            HeapPrim(h) => write!(f, "{h}"),
+            DequePrim(d) => write!(f, "{d}"),
+            SetPrim(s) => write!(f, "{s}"),
+            RecordPrim(r) => write!(f, "{r}"),
+            ConditionPrim(c) => write!(f, "{c}"),
+            ResourcePrim(r) => write!(f, "{r}"),
+            WeakRefPrim(w) => write!(f, "{w}"),
             Vector(v) => write!(
                 f,
                 "#({})",
                 v.iter().map(SExp::to_string).collect::<Vec<_>>().join(" ")
             ),
+            Values(v) => write!(
+                f,
+                "{}",
+                v.iter().map(SExp::to_string).collect::<Vec<_>>().join("\n")
+            ),
         }
     }
 }
@@ -74,6 +151,7 @@ impl Primitive {
         match self {
             Void => "void",
             Undefined => "undefined",
+            Self::Default => "default",
             Boolean(_) => "bool",
             Character(_) => "char",
             Number(_) => "number",
@@ -81,7 +159,18 @@ impl Primitive {
             Symbol(_) => "symbol",
             Env(_) => "environment",
             Procedure { .. } => "procedure",
+            MacroPrim(_) => "macro",
+            PromisePrim(_) => "promise",
+            PortPrim(_) => "port",
+            HeapPrim(_) => "heap",
+            DequePrim(_) => "deque",
+            SetPrim(_) => "set",
+            RecordPrim(r) => r.type_name(),
+            ConditionPrim(_) => "condition",
+            ResourcePrim(_) => "resource",
+            WeakRefPrim(_) => "weak-ref",
             Vector(_) => "vector",
+            Values(_) => "values",
         }
     }
 }
@@ -5,35 +5,263 @@
     clippy::cast_sign_loss
 )]
 
-use std::f64::{EPSILON, INFINITY, NEG_INFINITY};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::fmt;
 use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
 use std::str::FromStr;
 
+#[cfg(feature = "bignum")]
+use num_bigint::BigInt;
+#[cfg(feature = "bignum")]
+use num_traits::{FromPrimitive, Pow, Signed, ToPrimitive, Zero};
+
+#[cfg(feature = "bignum")]
+use self::Num::Big;
 use self::Num::{Float, Int};
-use super::super::SyntaxError;
+use super::super::{Error, SyntaxError};
 
 type IntT = isize;
 
+/// The largest result, in bits, that raising a big integer to a power may
+/// produce. Anything larger is computed inexactly by `pow`, and is an error
+/// from `checked_pow`.
+#[cfg(feature = "bignum")]
+const MAX_POW_BITS: u64 = 1 << 24;
+
+/// How inexact numbers are written by `number->string`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Notation {
+    /// Positional notation for moderate magnitudes, scientific for very large
+    /// or very small ones (`1e21` and above, or below `1e-6`).
+    Auto,
+    /// Always positional, e.g. `0.0000001`.
+    Fixed,
+    /// Always scientific, e.g. `1e-7`.
+    Scientific,
+}
+
+/// The smallest power of two which makes a float an integer when multiplied
+/// by it, or 1 if it is infinite or NaN.
+fn float_denominator(f: f64) -> f64 {
+    if !f.is_finite() {
+        return 1.;
+    }
+
+    let mut d = 1.;
+    while (f * d).fract() != 0. && d.is_finite() {
+        d *= 2.;
+    }
+    d
+}
+
+/// Write an inexact number so that it reads back as the same one: with a
+/// decimal point, so that it isn't taken for an exact integer, or as one of
+/// `+inf.0`, `-inf.0` and `+nan.0`.
+fn fmt_float(f: &mut fmt::Formatter, x: f64) -> fmt::Result {
+    if x.is_nan() {
+        f.write_str("+nan.0")
+    } else if x.is_infinite() {
+        f.write_str(if x > 0. { "+inf.0" } else { "-inf.0" })
+    } else if x.fract() == 0. {
+        write!(f, "{x}.0")
+    } else {
+        write!(f, "{x}")
+    }
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Notation::Auto),
+            "fixed" => Ok(Notation::Fixed),
+            "scientific" => Ok(Notation::Scientific),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+/// Formatting options for converting numbers to strings.
+///
+/// # Example
+/// ```
+/// use parsley::{Notation, Num, NumberFormat};
+///
+/// let sci = NumberFormat {
+///     notation: Notation::Scientific,
+///     precision: Some(2),
+/// };
+/// assert_eq!(Num::from(1234.5).format(sci), "1.23e3");
+/// assert_eq!(Num::from(0.0000001).format(NumberFormat::default()), "1e-7");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub notation: Notation,
+    /// Number of digits after the decimal point, or as many as needed.
+    pub precision: Option<usize>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            notation: Notation::Auto,
+            precision: None,
+        }
+    }
+}
+
 /// A numeric type that adapts its precision based on its usage.
-#[derive(Clone, Copy, Debug, PartialOrd)]
+///
+/// Exact integers which overflow `Int` become `Big` if the `bignum` feature
+/// is enabled, or inexact `Float`s if it is not.
+#[derive(Clone, Debug)]
 pub enum Num {
     Float(f64),
     Int(IntT),
+    /// An exact integer too large for `Int`. Integers which fit in `Int` are
+    /// never kept in this form.
+    #[cfg(feature = "bignum")]
+    Big(BigInt),
+}
+
+#[cfg(feature = "bignum")]
+impl Num {
+    /// An exact integer, in the smallest form that holds it.
+    fn big(b: BigInt) -> Self {
+        match b.to_isize() {
+            Some(i) => Int(i),
+            None => Big(b),
+        }
+    }
+
+    /// An exact integer raised to a non-negative exact power, or an error if
+    /// the result would be larger than `MAX_POW_BITS`.
+    fn exact_pow(self, exp: Self) -> Result<Self, Error> {
+        if let (Int(i0), Int(i1)) = (&self, &exp) {
+            if let Some(i) = u32::try_from(*i1).ok().and_then(|i1| i0.checked_pow(i1)) {
+                return Ok(Int(i));
+            }
+        }
+
+        let (base, exp) = (self.into_big(), exp.into_big());
+        match base.to_isize() {
+            Some(0) => Ok(Int(IntT::from(exp.is_zero()))),
+            Some(1) => Ok(Int(1)),
+            Some(-1) => Ok(Int(if exp.bit(0) { -1 } else { 1 })),
+            _ => match exp.to_u32() {
+                Some(e) if base.bits().saturating_mul(u64::from(e)) <= MAX_POW_BITS => {
+                    Ok(Self::big(base.pow(e)))
+                }
+                _ => Err(Error::Type {
+                    expected: "exponent small enough for an exact result",
+                    given: exp.to_string(),
+                }),
+            },
+        }
+    }
+
+    /// This number as a big integer, truncating it if it is inexact.
+    fn into_big(self) -> BigInt {
+        match self {
+            Float(f) => BigInt::from_f64(f.trunc()).unwrap_or_default(),
+            Int(i) => BigInt::from(i),
+            Big(b) => b,
+        }
+    }
 }
 
 impl Num {
+    /// An integer which has overflowed, in the form that arithmetic on it
+    /// should continue in: big if there are big integers, otherwise inexact.
+    fn widen(i: IntT) -> Self {
+        #[cfg(feature = "bignum")]
+        return Big(BigInt::from(i));
+        #[cfg(not(feature = "bignum"))]
+        return Float(i as f64);
+    }
+
+    /// Whether this number is exact (an integer) rather than inexact.
+    #[must_use]
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Float(_))
+    }
+
+    /// The inexact number closest to this one.
+    #[must_use]
+    pub fn to_inexact(self) -> Self {
+        Float(self.into())
+    }
+
+    /// The exact number closest to this one. There are no exact fractions,
+    /// so an inexact number is rounded to the nearest integer. `None` if it
+    /// is not finite (or does not fit in an exact integer).
+    #[must_use]
+    pub fn to_exact(self) -> Option<Self> {
+        let Float(f) = self else {
+            return Some(self);
+        };
+        let f = f.round();
+        if !f.is_finite() {
+            None
+        } else if f >= IntT::MIN as f64 && f < IntT::MAX as f64 {
+            Some(Int(f as IntT))
+        } else {
+            #[cfg(feature = "bignum")]
+            return BigInt::from_f64(f).map(Self::big);
+            #[cfg(not(feature = "bignum"))]
+            return None;
+        }
+    }
+
+    /// The exact number equal to this one, for `exact` and the `#e` prefix.
+    /// There are no exact fractions, so a number with a fractional part has
+    /// no exact equivalent.
+    ///
+    /// # Errors
+    /// Returns `Err` unless the number is a finite integer.
+    pub fn checked_to_exact(self) -> Result<Self, Error> {
+        let given = self.to_string();
+        Some(self)
+            .filter(Self::is_integer)
+            .and_then(Self::to_exact)
+            .ok_or(Error::Type {
+                expected: "finite integer",
+                given,
+            })
+    }
+
+    /// Write a number as a string. Exact integers are always written in full;
+    /// the format only affects inexact numbers.
+    #[must_use]
+    pub fn format(self, format: NumberFormat) -> String {
+        let f = match self {
+            Float(f) if f.is_finite() => f,
+            _ => return self.to_string(),
+        };
+
+        let scientific = match format.notation {
+            Notation::Auto => f != 0. && (f.abs() < 1e-6 || f.abs() >= 1e21),
+            Notation::Fixed => false,
+            Notation::Scientific => true,
+        };
+
+        match (scientific, format.precision) {
+            (true, Some(p)) => format!("{f:.p$e}"),
+            (true, None) => format!("{f:e}"),
+            (false, Some(p)) => format!("{f:.p$}"),
+            (false, None) => self.to_string(),
+        }
+    }
+
     #[must_use]
     pub fn abs(self) -> Self {
         match self {
             Float(f) => Float(f.abs()),
-            Int(i) => {
-                if let Some(i0) = i.checked_abs() {
-                    Int(i0)
-                } else {
-                    Float((i as f64).abs())
-                }
-            }
+            Int(i) => i.checked_abs().map_or_else(|| Self::widen(i).abs(), Int),
+            #[cfg(feature = "bignum")]
+            Big(b) => Self::big(b.abs()),
         }
     }
 
@@ -43,15 +271,187 @@ impl Num {
         Self: From<T>,
     {
         match (self, other.into()) {
-            (Int(i0), Int(i1)) => i0
-                .checked_pow(i1 as u32)
-                .map_or_else(|| Float((i0 as f64).powi(i1 as i32)), Int),
-            (Float(f), Int(i)) => Float(f.powi(i as i32)),
-            (Int(i), Float(f)) => Float((i as f64).powf(f)),
-            (Float(f0), Float(f1)) => Float(f0.powf(f1)),
+            (Int(i0), Int(i1)) => u32::try_from(i1)
+                .ok()
+                .and_then(|i1| i0.checked_pow(i1))
+                .map_or_else(|| Self::widen(i0).pow::<Self>(Int(i1)), Int),
+            #[cfg(feature = "bignum")]
+            (Big(b), Int(i)) if i >= 0 && b.bits().saturating_mul(i as u64) <= MAX_POW_BITS => {
+                Self::big(b.pow(i as u32))
+            }
+            (n, Int(i)) => Float(f64::from(n).powi(i as i32)),
+            (n0, n1) => Float(f64::from(n0).powf(f64::from(n1))),
+        }
+    }
+
+    /// Raise to a power like `pow`, without giving up exactness where the
+    /// result should be exact: an exact zero raised to a negative power is
+    /// `DivisionByZero`, and with big integers, an exact power too large to
+    /// compute is an error rather than an infinity.
+    ///
+    /// # Errors
+    /// Returns `DivisionByZero` for an exact zero raised to a negative power,
+    /// or a type error if an exact result would be too large.
+    pub fn checked_pow<T>(self, other: T) -> Result<Self, Error>
+    where
+        Self: From<T>,
+    {
+        match (self, Self::from(other)) {
+            (Int(0), n) if n.is_exact() && n < Int(0) => Err(Error::DivisionByZero),
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() && n1 >= Int(0) => n0.exact_pow(n1),
+            (n0, n1) => Ok(n0.pow::<Self>(n1)),
+        }
+    }
+
+    /// Divide, returning `None` if both operands are exact and the divisor
+    /// is zero. Inexact division follows IEEE semantics.
+    #[must_use]
+    pub fn checked_div<T>(self, other: T) -> Option<Self>
+    where
+        Self: From<T>,
+    {
+        match (self, other.into()) {
+            (n0, Int(0)) if n0.is_exact() => None,
+            (n0, n1) => Some(Div::<Self>::div(n0, n1)),
         }
     }
 
+    /// Remainder with the sign of the dividend, returning `None` if both
+    /// operands are exact and the divisor is zero.
+    #[must_use]
+    pub fn checked_rem<T>(self, other: T) -> Option<Self>
+    where
+        Self: From<T>,
+    {
+        match (self, other.into()) {
+            (n0, Int(0)) if n0.is_exact() => None,
+            (Int(i0), Int(i1)) => Some(Int(i0.wrapping_rem(i1))),
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() => {
+                Some(Self::big(n0.into_big() % n1.into_big()))
+            }
+            (n0, n1) => Some(Float(f64::from(n0) % f64::from(n1))),
+        }
+    }
+
+    /// Modulus with the sign of the divisor, returning `None` if both
+    /// operands are exact and the divisor is zero.
+    #[must_use]
+    pub fn checked_modulo<T>(self, other: T) -> Option<Self>
+    where
+        Self: From<T>,
+    {
+        match (self, other.into()) {
+            (n0, Int(0)) if n0.is_exact() => None,
+            (Int(i0), Int(i1)) => {
+                let r = i0.wrapping_rem(i1);
+                Some(Int(if r != 0 && (r < 0) != (i1 < 0) {
+                    r + i1
+                } else {
+                    r
+                }))
+            }
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() => {
+                let (b0, b1) = (n0.into_big(), n1.into_big());
+                let r = b0 % &b1;
+                Some(Self::big(
+                    if !r.is_zero() && r.is_negative() != b1.is_negative() {
+                        r + b1
+                    } else {
+                        r
+                    },
+                ))
+            }
+            (n0, n1) => {
+                let (f0, f1) = (f64::from(n0), f64::from(n1));
+                let r = f0 % f1;
+                Some(Float(if r != 0. && (r < 0.) != (f1 < 0.) {
+                    r + f1
+                } else {
+                    r
+                }))
+            }
+        }
+    }
+
+    /// The smaller of two numbers, which is inexact if either of them is.
+    /// If either is NaN, so is the result.
+    #[must_use]
+    pub fn min<T>(self, other: T) -> Self
+    where
+        Self: From<T>,
+    {
+        self.pick(other.into(), Ordering::Less)
+    }
+
+    /// The larger of two numbers, which is inexact if either of them is.
+    /// If either is NaN, so is the result.
+    #[must_use]
+    pub fn max<T>(self, other: T) -> Self
+    where
+        Self: From<T>,
+    {
+        self.pick(other.into(), Ordering::Greater)
+    }
+
+    fn pick(self, other: Self, keep: Ordering) -> Self {
+        let inexact = !self.is_exact() || !other.is_exact();
+        let picked = match self.partial_cmp(&other) {
+            Some(ordering) if ordering == keep || ordering == Ordering::Equal => self,
+            Some(_) => other,
+            None => return Float(f64::NAN),
+        };
+
+        if inexact {
+            picked.to_inexact()
+        } else {
+            picked
+        }
+    }
+
+    /// Whether this number is an integer, exact or not.
+    #[must_use]
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Float(f) => f.fract() == 0.,
+            _ => true,
+        }
+    }
+
+    /// The (non-negative) greatest common divisor, returning `None` if either
+    /// operand is not an integer. The result is inexact if either operand is.
+    #[must_use]
+    pub fn checked_gcd(self, other: Self) -> Option<Self> {
+        if !self.is_integer() || !other.is_integer() {
+            return None;
+        }
+
+        let inexact = !self.is_exact() || !other.is_exact();
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while b != Int(0) {
+            let r = a.clone().checked_rem::<Self>(b.clone())?;
+            a = b;
+            b = r;
+        }
+
+        Some(if inexact { a.to_inexact() } else { a })
+    }
+
+    /// The (non-negative) least common multiple, returning `None` if either
+    /// operand is not an integer. The result is inexact if either operand is.
+    #[must_use]
+    pub fn checked_lcm(self, other: Self) -> Option<Self> {
+        let gcd = self.clone().checked_gcd(other.clone())?;
+
+        Some(if gcd == Int(0) {
+            gcd
+        } else {
+            (self / gcd * other).abs()
+        })
+    }
+
     #[must_use]
     pub fn is_nan(self) -> bool {
         if let Float(f) = self {
@@ -84,6 +484,8 @@ impl Num {
         match self {
             Float(f) => f.is_sign_positive(),
             Int(i) => i.is_positive(),
+            #[cfg(feature = "bignum")]
+            Big(b) => b.is_positive(),
         }
     }
 
@@ -92,45 +494,77 @@ impl Num {
         match self {
             Float(f) => f.is_sign_negative(),
             Int(i) => i.is_negative(),
+            #[cfg(feature = "bignum")]
+            Big(b) => b.is_negative(),
         }
     }
 
+    /// The largest integer not greater than this number. Inexact numbers
+    /// stay inexact.
     #[must_use]
     pub fn floor(self) -> Self {
         if let Float(f) = self {
-            Int(f.floor() as IntT)
+            Float(f.floor())
         } else {
             self
         }
     }
 
+    /// The smallest integer not less than this number. Inexact numbers stay
+    /// inexact.
     #[must_use]
     pub fn ceil(self) -> Self {
         if let Float(f) = self {
-            Int(f.ceil() as IntT)
+            Float(f.ceil())
         } else {
             self
         }
     }
 
+    /// The closest integer to this number, rounding to even when it is
+    /// halfway between two integers. Inexact numbers stay inexact.
     #[must_use]
     pub fn round(self) -> Self {
         if let Float(f) = self {
-            Int(f.round() as IntT)
+            Float(f.round_ties_even())
         } else {
             self
         }
     }
 
+    /// The closest integer to this number which is no larger in magnitude.
+    /// Inexact numbers stay inexact.
     #[must_use]
     pub fn trunc(self) -> Self {
         if let Float(f) = self {
-            Int(f.trunc() as IntT)
+            Float(f.trunc())
         } else {
             self
         }
     }
 
+    /// The numerator of this number as a fraction in lowest terms. An
+    /// inexact number is exactly some fraction with a power of two as its
+    /// denominator, and its numerator is inexact too.
+    #[must_use]
+    pub fn numerator(self) -> Self {
+        match self {
+            Float(f) => Float(f * float_denominator(f)),
+            n => n,
+        }
+    }
+
+    /// The (positive) denominator of this number as a fraction in lowest
+    /// terms, which is 1 for an integer. Inexact numbers give an inexact
+    /// denominator.
+    #[must_use]
+    pub fn denominator(self) -> Self {
+        match self {
+            Float(f) => Float(float_denominator(f)),
+            _ => Int(1),
+        }
+    }
+
     #[must_use]
     pub fn fract(self) -> Self {
         if let Float(f) = self {
@@ -145,6 +579,8 @@ impl Num {
         match self {
             Float(f) => Int(f.signum() as IntT),
             Int(i) => Int(i.signum()),
+            #[cfg(feature = "bignum")]
+            Big(b) => Int(if b.is_negative() { -1 } else { 1 }),
         }
     }
 
@@ -153,9 +589,33 @@ impl Num {
         Float(f64::from(self).recip())
     }
 
+    /// The square root, which is exact if this number is an exact square.
     #[must_use]
     pub fn sqrt(self) -> Self {
-        Float(f64::from(self).sqrt())
+        match self.clone().exact_integer_sqrt() {
+            Some((root, Int(0))) => root,
+            _ => Float(f64::from(self).sqrt()),
+        }
+    }
+
+    /// The largest integer whose square is no greater than this number, and
+    /// the difference between the two. `None` unless this number is an exact,
+    /// non-negative integer.
+    #[must_use]
+    pub fn exact_integer_sqrt(self) -> Option<(Self, Self)> {
+        match self {
+            Int(i) if i >= 0 => {
+                let root = i.isqrt();
+                Some((Int(root), Int(i - root * root)))
+            }
+            #[cfg(feature = "bignum")]
+            Big(b) if !b.is_negative() => {
+                let root = b.sqrt();
+                let rest = &b - &root * &root;
+                Some((Self::big(root), Self::big(rest)))
+            }
+            _ => None,
+        }
     }
 
     #[must_use]
@@ -175,9 +635,10 @@ impl Num {
 
     #[must_use]
     pub fn exp2(self) -> Self {
-        match self {
-            Float(f) => Float(f.exp2()),
-            Int(i) => Int((2 as IntT).pow(i as u32)),
+        if let Float(f) = self {
+            Float(f.exp2())
+        } else {
+            Int(2).pow(self)
         }
     }
 
@@ -256,19 +717,139 @@ impl Num {
     }
 }
 
+impl Num {
+    /// Read a number with radix (`#x`, `#o`, `#b`, `#d`) and exactness (`#e`,
+    /// `#i`) prefixes, at most one of each, in either order. `#e` on a number
+    /// with no exact equivalent is an error, as with `exact`.
+    pub(crate) fn from_prefixed(s: &str) -> Result<Self, Error> {
+        let invalid = || {
+            Error::from(SyntaxError::InvalidNumber {
+                text: s.to_string(),
+                span: None,
+            })
+        };
+        let mut radix = None;
+        let mut exact = None;
+        let mut rest = s;
+        while let Some(prefixed) = rest.strip_prefix('#') {
+            let mut chars = prefixed.chars();
+            match chars.next().ok_or_else(invalid)?.to_ascii_lowercase() {
+                'x' if radix.is_none() => radix = Some(16),
+                'o' if radix.is_none() => radix = Some(8),
+                'b' if radix.is_none() => radix = Some(2),
+                'd' if radix.is_none() => radix = Some(10),
+                'e' if exact.is_none() => exact = Some(true),
+                'i' if exact.is_none() => exact = Some(false),
+                _ => return Err(invalid()),
+            }
+            rest = chars.as_str();
+        }
+
+        let num = match radix.unwrap_or(10) {
+            10 => rest.parse().map_err(|_| invalid())?,
+            radix => Self::from_radix(rest, radix).ok_or_else(invalid)?,
+        };
+        match exact {
+            Some(true) => num.checked_to_exact(),
+            Some(false) => Ok(num.to_inexact()),
+            None => Ok(num),
+        }
+    }
+
+    /// Read an integer in a radix other than 10.
+    fn from_radix(s: &str, radix: u32) -> Option<Self> {
+        let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        if let Ok(num) = IntT::from_str_radix(s, radix) {
+            return Some(Int(num));
+        }
+
+        #[cfg(feature = "bignum")]
+        return BigInt::parse_bytes(s.as_bytes(), radix).map(Self::big);
+        #[cfg(not(feature = "bignum"))]
+        {
+            let magnitude = digits.chars().fold(0.0, |acc, c| {
+                acc * f64::from(radix) + f64::from(c.to_digit(radix).unwrap_or(0))
+            });
+            Some(Float(if s.starts_with('-') {
+                -magnitude
+            } else {
+                magnitude
+            }))
+        }
+    }
+
+    /// Write an exact integer in radix 2, 8, 10 or 16, or `None` for an
+    /// inexact number.
+    pub(crate) fn to_radix(&self, radix: u32) -> Option<String> {
+        match self {
+            Int(i) => {
+                let sign = if *i < 0 { "-" } else { "" };
+                let n = i.unsigned_abs();
+                Some(match radix {
+                    2 => format!("{sign}{n:b}"),
+                    8 => format!("{sign}{n:o}"),
+                    16 => format!("{sign}{n:x}"),
+                    _ => format!("{sign}{n}"),
+                })
+            }
+            #[cfg(feature = "bignum")]
+            Big(b) => Some(b.to_str_radix(radix)),
+            Float(_) => None,
+        }
+    }
+}
+
 impl FromStr for Num {
     type Err = SyntaxError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('#') {
+            return Self::from_prefixed(s).map_err(|err| match err {
+                Error::Syntax(err) => err,
+                _ => SyntaxError::InvalidNumber {
+                    text: s.to_string(),
+                    span: None,
+                },
+            });
+        }
+
+        match s {
+            "+inf.0" => return Ok(Float(f64::INFINITY)),
+            "-inf.0" => return Ok(Float(f64::NEG_INFINITY)),
+            "+nan.0" | "-nan.0" => return Ok(Float(f64::NAN)),
+            _ => (),
+        }
+
         if let Ok(num) = s.parse::<IntT>() {
             return Ok(Int(num));
         }
 
-        if let Ok(num) = s.parse::<f64>() {
-            return Ok(Float(num));
+        // only plain digits, since big integers can also be written with `_`
+        #[cfg(feature = "bignum")]
+        if s.trim_start_matches(['+', '-'])
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        {
+            if let Ok(num) = s.parse::<BigInt>() {
+                return Ok(Self::big(num));
+            }
+        }
+
+        // infinities and NaN are only written as above, and a literal too
+        // large to represent is not one
+        match s.parse::<f64>() {
+            Ok(num) if num.is_finite() => return Ok(Float(num)),
+            _ => (),
         }
 
-        Err(SyntaxError::NotANumber(s.to_string()))
+        Err(SyntaxError::InvalidNumber {
+            text: s.to_string(),
+            span: None,
+        })
     }
 }
 
@@ -304,14 +885,35 @@ impl From<f64> for Num {
 
 impl PartialEq for Num {
     fn eq(&self, other: &Self) -> bool {
-        match (*self, *other) {
+        match (self, other) {
             (Int(i0), Int(i1)) => i0 == i1,
-            (Float(f), Int(i)) | (Int(i), Float(f)) => (f - (i as f64)).abs() < EPSILON,
             (Float(f0), Float(f1)) => {
-                f0 == INFINITY && f1 == INFINITY
-                    || f0 == NEG_INFINITY && f1 == NEG_INFINITY
-                    || (f0 - f1).abs() < EPSILON
+                *f0 == f64::INFINITY && *f1 == f64::INFINITY
+                    || *f0 == f64::NEG_INFINITY && *f1 == f64::NEG_INFINITY
+                    || (f0 - f1).abs() < f64::EPSILON
+            }
+            (Float(f), n) | (n, Float(f)) => (f - f64::from(n.clone())).abs() < f64::EPSILON,
+            #[cfg(feature = "bignum")]
+            (Big(b0), Big(b1)) => b0 == b1,
+            // a big integer is never small enough to be an `Int`
+            #[cfg(feature = "bignum")]
+            _ => false,
+        }
+    }
+}
+
+impl PartialOrd for Num {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Int(i0), Int(i1)) => i0.partial_cmp(i1),
+            (Float(_), _) | (_, Float(_)) => {
+                f64::from(self.clone()).partial_cmp(&f64::from(other.clone()))
             }
+            #[cfg(feature = "bignum")]
+            _ => self
+                .clone()
+                .into_big()
+                .partial_cmp(&other.clone().into_big()),
         }
     }
 }
@@ -321,6 +923,10 @@ impl From<Num> for usize {
         match n {
             Num::Float(f) => f as Self,
             Num::Int(i) => i as Self,
+            #[cfg(feature = "bignum")]
+            Num::Big(b) => b
+                .to_usize()
+                .unwrap_or(if b.is_negative() { 0 } else { Self::MAX }),
         }
     }
 }
@@ -330,6 +936,12 @@ impl From<Num> for f64 {
         match n {
             Num::Float(f) => f,
             Num::Int(i) => i as Self,
+            #[cfg(feature = "bignum")]
+            Num::Big(b) => b.to_f64().unwrap_or(if b.is_negative() {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }),
         }
     }
 }
@@ -337,8 +949,10 @@ impl From<Num> for f64 {
 impl fmt::Display for Num {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Float(l) => write!(f, "{}", l),
-            Int(i) => write!(f, "{}", i),
+            Float(l) => fmt_float(f, *l),
+            Int(i) => write!(f, "{i}"),
+            #[cfg(feature = "bignum")]
+            Big(b) => write!(f, "{b}"),
         }
     }
 }
@@ -348,11 +962,10 @@ impl Neg for Num {
 
     fn neg(self) -> Self::Output {
         match self {
-            Int(i) => match i.checked_neg() {
-                Some(i0) => Int(i0),
-                None => Float(-(i as f64)),
-            },
+            Int(i) => i.checked_neg().map_or_else(|| -Self::widen(i), Int),
             Float(f) => Float(-f),
+            #[cfg(feature = "bignum")]
+            Big(b) => Self::big(-b),
         }
     }
 }
@@ -367,9 +980,10 @@ where
         match (self, other.into()) {
             (Int(i0), Int(i1)) => i0
                 .checked_add(i1)
-                .map_or_else(|| Float((i0 as f64) + (i1 as f64)), Int),
-            (Float(f), Int(i)) | (Int(i), Float(f)) => Float(f + (i as f64)),
-            (Float(f0), Float(f1)) => Float(f0 + f1),
+                .map_or_else(|| Add::<Self>::add(Self::widen(i0), Self::widen(i1)), Int),
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() => Self::big(n0.into_big() + n1.into_big()),
+            (n0, n1) => Float(f64::from(n0) + f64::from(n1)),
         }
     }
 }
@@ -384,10 +998,10 @@ where
         match (self, other.into()) {
             (Int(i0), Int(i1)) => i0
                 .checked_sub(i1)
-                .map_or_else(|| Float((i0 as f64) - (i1 as f64)), Int),
-            (Float(f), Int(i)) => Float(f - (i as f64)),
-            (Int(i), Float(f)) => Float((i as f64) - f),
-            (Float(f0), Float(f1)) => Float(f0 - f1),
+                .map_or_else(|| Sub::<Self>::sub(Self::widen(i0), Self::widen(i1)), Int),
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() => Self::big(n0.into_big() - n1.into_big()),
+            (n0, n1) => Float(f64::from(n0) - f64::from(n1)),
         }
     }
 }
@@ -402,9 +1016,10 @@ where
         match (self, other.into()) {
             (Int(i0), Int(i1)) => i0
                 .checked_mul(i1)
-                .map_or_else(|| Float((i0 as f64) * (i1 as f64)), Int),
-            (Float(f), Int(i)) | (Int(i), Float(f)) => Float(f * (i as f64)),
-            (Float(f0), Float(f1)) => Float(f0 * f1),
+                .map_or_else(|| Mul::<Self>::mul(Self::widen(i0), Self::widen(i1)), Int),
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() => Self::big(n0.into_big() * n1.into_big()),
+            (n0, n1) => Float(f64::from(n0) * f64::from(n1)),
         }
     }
 }
@@ -417,18 +1032,22 @@ where
 
     fn div(self, other: T) -> Self::Output {
         match (self, other.into()) {
-            (Int(i0), Int(i1)) => {
-                if let Some(0) = i0.checked_rem(i1) {
-                    if let Some(i) = i0.checked_div(i1) {
-                        return Int(i);
-                    }
+            (Int(i0), Int(i1)) => match (i0.checked_rem(i1), i0.checked_div(i1)) {
+                (Some(0), Some(i)) => Int(i),
+                // only the most negative integer divided by -1 overflows
+                (None, _) if i1 != 0 => Div::<Self>::div(Self::widen(i0), Self::widen(i1)),
+                _ => Float((i0 as f64) / (i1 as f64)),
+            },
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() && n1 != Int(0) => {
+                let (b0, b1) = (n0.into_big(), n1.into_big());
+                if (&b0 % &b1).is_zero() {
+                    Self::big(b0 / b1)
+                } else {
+                    Float(f64::from(Big(b0)) / f64::from(Big(b1)))
                 }
-
-                Float((i0 as f64) / (i1 as f64))
             }
-            (Float(f), Int(i)) => Float(f / (i as f64)),
-            (Int(i), Float(f)) => Float((i as f64) / f),
-            (Float(f0), Float(f1)) => Float(f0 / f1),
+            (n0, n1) => Float(f64::from(n0) / f64::from(n1)),
         }
     }
 }
@@ -445,9 +1064,11 @@ where
                 Some(i) => Int(i),
                 None => Float((i0 as f64) % (i1 as f64)),
             },
-            (Float(f), Int(i)) => Float(f % (i as f64)),
-            (Int(i), Float(f)) => Float((i as f64) % f),
-            (Float(f0), Float(f1)) => Float(f0 % f1),
+            #[cfg(feature = "bignum")]
+            (n0, n1) if n0.is_exact() && n1.is_exact() && n1 != Int(0) => {
+                Self::big(n0.into_big() % n1.into_big())
+            }
+            (n0, n1) => Float(f64::from(n0) % f64::from(n1)),
         }
     }
 }
@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::super::SExp;
+use super::Primitive::Port as PortPrim;
+
+/// A primitive value that wraps an output port.
+#[derive(Clone)]
+pub enum Port {
+    /// The output of the context: standard output, or the buffer used when
+    /// output is being captured.
+    Console,
+    /// A port which collects its output in a string, from
+    /// `open-output-string`.
+    String(Rc<RefCell<String>>),
+}
+
+impl Port {
+    pub fn string() -> Self {
+        Self::String(Rc::default())
+    }
+
+    /// Everything written to a string port so far, or `None` for the console.
+    pub fn contents(&self) -> Option<String> {
+        match self {
+            Self::Console => None,
+            Self::String(s) => Some(s.borrow().clone()),
+        }
+    }
+}
+
+impl PartialEq for Port {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Console, Self::Console) => true,
+            (Self::String(s0), Self::String(s1)) => Rc::ptr_eq(s0, s1),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Console => write!(f, "#<port:console>"),
+            Self::String(_) => write!(f, "#<port:string>"),
+        }
+    }
+}
+
+impl From<Port> for SExp {
+    fn from(p: Port) -> Self {
+        SExp::Atom(PortPrim(p))
+    }
+}
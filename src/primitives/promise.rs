@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::super::{Context, Error, Result, SExp};
+use super::Primitive::Promise as PromisePrim;
+use SExp::{Atom, Null};
+
+#[derive(Clone)]
+enum State {
+    /// Not yet forced. If `chained` (from `delay-force`), the thunk produces
+    /// another promise to be forced in its place.
+    Pending {
+        thunk: SExp,
+        chained: bool,
+    },
+    Done(SExp),
+}
+
+/// A primitive value that wraps a memoized, delayed computation.
+#[derive(Clone)]
+pub struct Promise(Rc<RefCell<State>>);
+
+impl Promise {
+    /// A promise which will call a (nullary) procedure when forced.
+    pub fn delayed(thunk: SExp, chained: bool) -> Self {
+        Self(Rc::new(RefCell::new(State::Pending { thunk, chained })))
+    }
+
+    /// A promise which has already been forced.
+    pub fn ready(value: SExp) -> Self {
+        Self(Rc::new(RefCell::new(State::Done(value))))
+    }
+
+    /// A new promise in the same state, with its thunk or value replaced by
+    /// the result of a function.
+    pub(crate) fn try_map(
+        &self,
+        mut f: impl FnMut(SExp) -> Result,
+    ) -> std::result::Result<Self, Error> {
+        let state = match self.0.borrow().clone() {
+            State::Pending { thunk, chained } => State::Pending {
+                thunk: f(thunk)?,
+                chained,
+            },
+            State::Done(value) => State::Done(f(value)?),
+        };
+        Ok(Self(Rc::new(RefCell::new(state))))
+    }
+
+    /// Get the value of the promise, computing it if this is the first time.
+    ///
+    /// Chains of promises produced by `delay-force` are followed iteratively,
+    /// so they do not grow the stack.
+    pub fn force(&self, ctx: &mut Context) -> Result {
+        loop {
+            let state = self.0.borrow().clone();
+            let (thunk, chained) = match state {
+                State::Done(value) => return Ok(value),
+                State::Pending { thunk, chained } => (thunk, chained),
+            };
+
+            let value = ctx.eval(Null.cons(thunk))?;
+
+            // forcing the promise may have (re-entrantly) forced it already
+            if let State::Done(value) = &*self.0.borrow() {
+                return Ok(value.clone());
+            }
+
+            match value {
+                Atom(PromisePrim(next)) if chained => {
+                    let next_state = next.0.borrow().clone();
+                    *self.0.borrow_mut() = next_state;
+                }
+                value => {
+                    *self.0.borrow_mut() = State::Done(value.clone());
+                    return Ok(value);
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for Promise {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for Promise {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<promise>")
+    }
+}
+
+impl From<Promise> for SExp {
+    fn from(p: Promise) -> Self {
+        Atom(PromisePrim(p))
+    }
+}
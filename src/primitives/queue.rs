@@ -0,0 +1,219 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+use super::super::{effect, Error, Result, SExp};
+use super::tracked::Tracked;
+use super::Num;
+use super::Primitive::{Deque as DequePrim, Heap as HeapPrim};
+
+/// A value in a heap. Lower priorities are greater, so they come out of the
+/// (max-)heap first, and values with the same priority come out in the order
+/// they were added.
+struct Entry {
+    priority: f64,
+    seq: u64,
+    value: SExp,
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+#[derive(Default)]
+struct Entries {
+    heap: BinaryHeap<Entry>,
+    next_seq: u64,
+}
+
+/// A mutable priority queue, from `make-heap`, which gives back the value
+/// with the lowest priority first.
+#[derive(Clone, Default)]
+pub struct Heap(Rc<RefCell<Entries>>);
+
+impl Heap {
+    pub fn insert(&self, priority: Num, value: SExp) {
+        effect::record();
+        let mut entries = self.0.borrow_mut();
+        let seq = entries.next_seq;
+        entries.next_seq += 1;
+        entries.heap.push(Entry {
+            priority: priority.into(),
+            seq,
+            value,
+        });
+    }
+
+    /// Remove the value with the lowest priority.
+    pub fn pop(&self) -> Option<SExp> {
+        effect::record();
+        self.0.borrow_mut().heap.pop().map(|entry| entry.value)
+    }
+
+    /// The value with the lowest priority.
+    pub fn peek(&self) -> Option<SExp> {
+        self.0.borrow().heap.peek().map(|entry| entry.value.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().heap.is_empty()
+    }
+
+    /// A new heap with the same priorities, and each value replaced by the
+    /// result of a function.
+    pub(crate) fn try_map(
+        &self,
+        mut f: impl FnMut(SExp) -> Result,
+    ) -> std::result::Result<Self, Error> {
+        let inner = self.0.borrow();
+        let heap = inner
+            .heap
+            .iter()
+            .map(|entry| {
+                Ok(Entry {
+                    priority: entry.priority,
+                    seq: entry.seq,
+                    value: f(entry.value.clone())?,
+                })
+            })
+            .collect::<std::result::Result<_, Error>>()?;
+
+        Ok(Self(Rc::new(RefCell::new(Entries {
+            heap,
+            next_seq: inner.next_seq,
+        }))))
+    }
+
+    /// The values in the order they would be removed.
+    pub fn to_vec(&self) -> Vec<SExp> {
+        let inner = self.0.borrow();
+        let mut entries = inner.heap.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.cmp(a));
+        entries
+            .into_iter()
+            .map(|entry| entry.value.clone())
+            .collect()
+    }
+}
+
+impl PartialEq for Heap {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for Heap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<heap size={}>", self.len())
+    }
+}
+
+impl From<Heap> for SExp {
+    fn from(h: Heap) -> Self {
+        SExp::Atom(HeapPrim(h))
+    }
+}
+
+/// A mutable double-ended queue, from `make-deque`.
+#[derive(Clone, Default)]
+pub struct Deque(Rc<Tracked<VecDeque<SExp>>>);
+
+impl Deque {
+    pub fn push_front(&self, value: SExp) {
+        self.0.borrow_mut().push_front(value);
+    }
+
+    pub fn push_back(&self, value: SExp) {
+        self.0.borrow_mut().push_back(value);
+    }
+
+    pub fn pop_front(&self) -> Option<SExp> {
+        if self.is_empty() {
+            return None;
+        }
+        self.0.borrow_mut().pop_front()
+    }
+
+    pub fn pop_back(&self) -> Option<SExp> {
+        if self.is_empty() {
+            return None;
+        }
+        self.0.borrow_mut().pop_back()
+    }
+
+    pub fn front(&self) -> Option<SExp> {
+        self.0.borrow().front().cloned()
+    }
+
+    pub fn back(&self) -> Option<SExp> {
+        self.0.borrow().back().cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    /// The number of times values have been added or removed so far.
+    pub fn changes(&self) -> u64 {
+        self.0.changes()
+    }
+
+    /// The values from front to back.
+    pub fn to_vec(&self) -> Vec<SExp> {
+        self.0.borrow().iter().cloned().collect()
+    }
+}
+
+impl FromIterator<SExp> for Deque {
+    fn from_iter<I: IntoIterator<Item = SExp>>(iter: I) -> Self {
+        Self(Rc::new(Tracked::new(iter.into_iter().collect())))
+    }
+}
+
+impl PartialEq for Deque {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for Deque {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<deque size={}>", self.len())
+    }
+}
+
+impl From<Deque> for SExp {
+    fn from(d: Deque) -> Self {
+        SExp::Atom(DequePrim(d))
+    }
+}
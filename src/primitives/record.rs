@@ -0,0 +1,185 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::super::{effect, Error, Func, Proc, Result, SExp};
+use super::Primitive::{Record as RecordPrim, Undefined};
+use SExp::Atom;
+
+/// The shape of a record, as declared by `define-record-type`.
+#[derive(Debug)]
+pub struct RecordType {
+    name: String,
+    fields: Vec<String>,
+}
+
+/// An instance of a record type. Copies of it share its fields, so changing
+/// a field of one changes it for all of them.
+#[derive(Clone)]
+pub struct Record {
+    rtd: Rc<RecordType>,
+    values: Rc<RefCell<Vec<SExp>>>,
+}
+
+impl RecordType {
+    pub fn new(name: &str, fields: Vec<String>) -> Rc<Self> {
+        // `<point>` is conventional, but it prints as `#<point ...>`
+        let name = name
+            .strip_prefix('<')
+            .and_then(|n| n.strip_suffix('>'))
+            .unwrap_or(name);
+
+        Rc::new(Self {
+            name: name.to_string(),
+            fields,
+        })
+    }
+
+    fn index_of(&self, field: &str) -> std::result::Result<usize, Error> {
+        self.fields
+            .iter()
+            .position(|f| f == field)
+            .ok_or_else(|| Error::undefined(field))
+    }
+
+    fn check<'a>(self: &Rc<Self>, exp: &'a SExp) -> std::result::Result<&'a Record, Error> {
+        match exp {
+            Atom(RecordPrim(r)) if Rc::ptr_eq(&r.rtd, self) => Ok(r),
+            other => Err(Error::Type {
+                expected: "matching record",
+                given: other.type_of().to_string(),
+            }),
+        }
+    }
+
+    /// Make a procedure which takes the given fields (in order) and returns a
+    /// new record. Any other fields are left undefined.
+    pub fn constructor(
+        self: &Rc<Self>,
+        name: &str,
+        args: &[String],
+    ) -> std::result::Result<Proc, Error> {
+        let indices = args
+            .iter()
+            .map(|a| self.index_of(a))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let rtd = self.clone();
+
+        Ok(Proc::new(
+            Func::Pure(Rc::new(move |e| {
+                let mut values = vec![Atom(Undefined); rtd.fields.len()];
+                for (i, v) in indices.iter().zip(e) {
+                    values[*i] = v;
+                }
+
+                Ok(Atom(RecordPrim(Record {
+                    rtd: rtd.clone(),
+                    values: Rc::new(RefCell::new(values)),
+                })))
+            })),
+            args.len(),
+            Some(name),
+        ))
+    }
+
+    pub fn predicate(self: &Rc<Self>, name: &str) -> Proc {
+        let rtd = self.clone();
+
+        Proc::new(
+            Func::Pure(Rc::new(move |e| Ok(rtd.check(&e.car()?).is_ok().into()))),
+            1,
+            Some(name),
+        )
+    }
+
+    pub fn accessor(self: &Rc<Self>, name: &str, field: &str) -> std::result::Result<Proc, Error> {
+        let i = self.index_of(field)?;
+        let rtd = self.clone();
+
+        Ok(Proc::new(
+            Func::Pure(Rc::new(move |e| {
+                Ok(rtd.check(&e.car()?)?.values.borrow()[i].clone())
+            })),
+            1,
+            Some(name),
+        ))
+    }
+
+    /// Make a procedure which changes a field of a record in place.
+    pub fn modifier(self: &Rc<Self>, name: &str, field: &str) -> std::result::Result<Proc, Error> {
+        let i = self.index_of(field)?;
+        let rtd = self.clone();
+
+        Ok(Proc::new(
+            Func::Pure(Rc::new(move |e| {
+                let (record, tail) = e.split_car()?;
+                let value = tail.car()?;
+                rtd.check(&record)?.values.borrow_mut()[i] = value;
+                effect::record();
+
+                Ok(Atom(Undefined))
+            })),
+            2,
+            Some(name),
+        ))
+    }
+}
+
+impl Record {
+    pub fn type_name(&self) -> &str {
+        &self.rtd.name
+    }
+
+    /// A record of the same type, with each value replaced by the result of
+    /// a function.
+    pub(crate) fn try_map(
+        &self,
+        f: impl FnMut(SExp) -> Result,
+    ) -> std::result::Result<Self, Error> {
+        let values = self
+            .values
+            .borrow()
+            .iter()
+            .cloned()
+            .map(f)
+            .collect::<std::result::Result<_, _>>()?;
+
+        Ok(Self {
+            rtd: self.rtd.clone(),
+            values: Rc::new(RefCell::new(values)),
+        })
+    }
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.rtd, &other.rtd)
+            && (Rc::ptr_eq(&self.values, &other.values) || self.values == other.values)
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<{}", self.rtd.name)?;
+        for (field, value) in self.rtd.fields.iter().zip(self.values.borrow().iter()) {
+            write!(f, " {field}={value:?}")?;
+        }
+        write!(f, ">")
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<{}", self.rtd.name)?;
+        for (field, value) in self.rtd.fields.iter().zip(self.values.borrow().iter()) {
+            write!(f, " {field}={value}")?;
+        }
+        write!(f, ">")
+    }
+}
+
+impl From<Record> for SExp {
+    fn from(r: Record) -> Self {
+        Atom(RecordPrim(r))
+    }
+}
@@ -0,0 +1,149 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use super::super::{effect, SExp};
+use super::Primitive::Resource as ResourcePrim;
+
+type Finalizer = Box<dyn FnOnce(Box<dyn Any>)>;
+
+pub(super) struct Inner {
+    kind: String,
+    value: RefCell<Option<Box<dyn Any>>>,
+    finalizer: RefCell<Option<Finalizer>>,
+}
+
+impl Inner {
+    fn close(&self) {
+        let value = self.value.borrow_mut().take();
+        let finalizer = self.finalizer.borrow_mut().take();
+
+        if value.is_some() {
+            effect::record();
+        }
+        if let (Some(value), Some(finalizer)) = (value, finalizer) {
+            finalizer(value);
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// A value held on behalf of the host, such as a file or a database handle,
+/// which Scheme code can pass around but not look inside.
+///
+/// A resource is closed (running its finalizer, if it has one) by the first
+/// of: a call to [`close`](#method.close) or `(close-resource r)`, the last
+/// reference to it being dropped, or the context it is registered with (see
+/// [`Context::register_resource`]) being dropped.
+///
+/// [`Context::register_resource`]: struct.Context.html#method.register_resource
+///
+/// # Example
+/// ```
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use parsley::prelude::*;
+/// use parsley::Resource;
+///
+/// let closed = Rc::new(Cell::new(false));
+/// let closed_by_finalizer = Rc::clone(&closed);
+/// let handle = Resource::with_finalizer("db", 42_u32, move |_| closed_by_finalizer.set(true));
+///
+/// assert_eq!(handle.with(|id: &mut u32| *id), Some(42));
+/// assert_eq!(handle.to_string(), "#<resource:db>");
+///
+/// let mut ctx = Context::base();
+/// ctx.define("handle", handle.clone().into());
+/// ctx.run("(close-resource handle)").unwrap();
+/// assert!(closed.get());
+/// assert!(handle.is_closed());
+/// assert_eq!(handle.with(|id: &mut u32| *id), None);
+/// ```
+#[derive(Clone)]
+pub struct Resource(Rc<Inner>);
+
+impl Resource {
+    /// A resource of the given kind (as it is printed) with no finalizer.
+    pub fn new<T: Any>(kind: &str, value: T) -> Self {
+        Self(Rc::new(Inner {
+            kind: kind.to_string(),
+            value: RefCell::new(Some(Box::new(value))),
+            finalizer: RefCell::new(None),
+        }))
+    }
+
+    /// A resource of the given kind which is passed to `finalizer` when it is
+    /// closed.
+    pub fn with_finalizer<T: Any>(
+        kind: &str,
+        value: T,
+        finalizer: impl FnOnce(T) + 'static,
+    ) -> Self {
+        let resource = Self::new(kind, value);
+        *resource.0.finalizer.borrow_mut() = Some(Box::new(move |value: Box<dyn Any>| {
+            if let Ok(value) = value.downcast::<T>() {
+                finalizer(*value);
+            }
+        }));
+        resource
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> &str {
+        &self.0.kind
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.0.value.borrow().is_none()
+    }
+
+    /// Close the resource now, running its finalizer if it is still open.
+    pub fn close(&self) {
+        self.0.close();
+    }
+
+    /// Call a function with the value of the resource, if it is still open
+    /// and holds a `T`.
+    pub fn with<T: Any, R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        effect::record();
+        let mut value = self.0.value.borrow_mut();
+        value.as_mut()?.downcast_mut().map(f)
+    }
+
+    pub(super) fn downgrade(&self) -> Weak<Inner> {
+        Rc::downgrade(&self.0)
+    }
+
+    pub(super) fn upgrade(weak: &Weak<Inner>) -> Option<Self> {
+        weak.upgrade().map(Self)
+    }
+}
+
+impl PartialEq for Resource {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_closed() {
+            write!(f, "#<resource:{} (closed)>", self.0.kind)
+        } else {
+            write!(f, "#<resource:{}>", self.0.kind)
+        }
+    }
+}
+
+impl From<Resource> for SExp {
+    fn from(r: Resource) -> Self {
+        SExp::Atom(ResourcePrim(r))
+    }
+}
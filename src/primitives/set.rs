@@ -0,0 +1,109 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+use super::super::SExp;
+use super::tracked::Tracked;
+use super::Primitive::Set as SetPrim;
+
+/// A mutable set, from `set` or `list->set`. Members are compared with
+/// `equal?` and kept in the order they were added.
+#[derive(Clone, Default)]
+pub struct Set(Rc<Tracked<Vec<SExp>>>);
+
+impl Set {
+    /// Add a value, returning whether it was not already a member.
+    pub fn add(&self, value: SExp) -> bool {
+        let is_new = !self.contains(&value);
+        if is_new {
+            self.0.borrow_mut().push(value);
+        }
+        is_new
+    }
+
+    /// Remove a value, returning whether it was a member.
+    pub fn remove(&self, value: &SExp) -> bool {
+        let is_member = self.contains(value);
+        if is_member {
+            self.0.borrow_mut().retain(|member| member != value);
+        }
+        is_member
+    }
+
+    pub fn contains(&self, value: &SExp) -> bool {
+        self.0.borrow().contains(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    /// The number of times members have been added or removed so far.
+    pub fn changes(&self) -> u64 {
+        self.0.changes()
+    }
+
+    /// Whether every member of this set is also in another.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0.borrow().iter().all(|member| other.contains(member))
+    }
+
+    /// The members, in the order they were added.
+    pub fn to_vec(&self) -> Vec<SExp> {
+        self.0.borrow().clone()
+    }
+
+    /// A new set with the members of both.
+    pub fn union(&self, other: &Self) -> Self {
+        self.to_vec().into_iter().chain(other.to_vec()).collect()
+    }
+
+    /// A new set with the members of this one that are also in another.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.filter(|member| other.contains(member))
+    }
+
+    /// A new set with the members of this one that are not in another.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.filter(|member| !other.contains(member))
+    }
+
+    fn filter(&self, keep: impl Fn(&SExp) -> bool) -> Self {
+        let members = self.0.borrow();
+        Self(Rc::new(Tracked::new(
+            members.iter().filter(|m| keep(m)).cloned().collect(),
+        )))
+    }
+}
+
+impl FromIterator<SExp> for Set {
+    fn from_iter<I: IntoIterator<Item = SExp>>(iter: I) -> Self {
+        let set = Self::default();
+        for value in iter {
+            set.add(value);
+        }
+        set
+    }
+}
+
+impl PartialEq for Set {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for Set {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<set size={}>", self.len())
+    }
+}
+
+impl From<Set> for SExp {
+    fn from(s: Set) -> Self {
+        SExp::Atom(SetPrim(s))
+    }
+}
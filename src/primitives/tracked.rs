@@ -0,0 +1,38 @@
+use std::cell::{Cell, Ref, RefCell, RefMut};
+
+use super::super::effect;
+
+/// The contents of a mutable collection, along with a count of the times it
+/// has been changed. A procedure iterating over the collection compares the
+/// count before and after each call it makes, to notice the collection being
+/// changed underneath it.
+#[derive(Default)]
+pub(super) struct Tracked<T> {
+    value: RefCell<T>,
+    changes: Cell<u64>,
+}
+
+impl<T> Tracked<T> {
+    pub(super) fn new(value: T) -> Self {
+        Self {
+            value: RefCell::new(value),
+            changes: Cell::new(0),
+        }
+    }
+
+    pub(super) fn borrow(&self) -> Ref<'_, T> {
+        self.value.borrow()
+    }
+
+    /// Borrow the contents to change them, which counts as a change (and a
+    /// side effect) whether or not the caller goes on to make one.
+    pub(super) fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.changes.set(self.changes.get() + 1);
+        effect::record();
+        self.value.borrow_mut()
+    }
+
+    pub(super) fn changes(&self) -> u64 {
+        self.changes.get()
+    }
+}
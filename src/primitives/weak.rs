@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use super::super::{Env, SExp};
+use super::port::Port;
+use super::resource::{Inner, Resource};
+use super::Primitive::{Env as EnvPrim, Port as PortPrim, Resource as ResourcePrim};
+
+enum Target {
+    Resource(Weak<Inner>),
+    Env(Weak<Env>),
+    Port(Weak<RefCell<String>>),
+    /// Any other value is the same as a copy of it, so there is nothing for
+    /// the reference to lose.
+    Value(SExp),
+}
+
+/// A reference which does not keep its target alive, from `make-weak-ref`.
+/// Only values with an identity of their own (resources, environments and
+/// string ports) can go away while it is held.
+#[derive(Clone)]
+pub struct WeakRef(Rc<Target>);
+
+impl WeakRef {
+    pub fn new(value: SExp) -> Self {
+        Self(Rc::new(match value {
+            SExp::Atom(ResourcePrim(r)) => Target::Resource(r.downgrade()),
+            SExp::Atom(EnvPrim(e)) => Target::Env(Rc::downgrade(&e)),
+            SExp::Atom(PortPrim(Port::String(s))) => Target::Port(Rc::downgrade(&s)),
+            other => Target::Value(other),
+        }))
+    }
+
+    /// The target, unless it has been dropped.
+    pub fn get(&self) -> Option<SExp> {
+        match &*self.0 {
+            Target::Resource(r) => Resource::upgrade(r).map(SExp::from),
+            Target::Env(e) => e.upgrade().map(|e| SExp::Atom(EnvPrim(e))),
+            Target::Port(s) => s.upgrade().map(|s| Port::String(s).into()),
+            Target::Value(v) => Some(v.clone()),
+        }
+    }
+}
+
+impl PartialEq for WeakRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for WeakRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<weak-ref>")
+    }
+}
@@ -9,8 +9,9 @@ pub mod utils;
 /// A primitive value that wraps a procedure.
 #[derive(Clone)]
 pub struct Proc {
-    name: Option<String>,
+    name: Option<Rc<str>>,
     arity: Arity,
+    doc: Option<Rc<str>>,
     pub(crate) func: Func,
 }
 
@@ -22,12 +23,24 @@ impl Proc {
         String: From<V>,
     {
         Self {
-            name: name.map(String::from),
+            name: name.map(|n| String::from(n).into()),
             arity: arity.into(),
+            doc: None,
             func: func.into(),
         }
     }
 
+    /// Attach a documentation string to the procedure.
+    #[must_use]
+    pub fn with_doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into().into());
+        self
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
     pub fn get_arity(&self) -> SExp {
         self.arity.into()
     }
@@ -40,10 +53,47 @@ impl Proc {
         self.arity.check(n_args)
     }
 
+    /// The parameter list the procedure was defined with, e.g. `(x y . rest)`,
+    /// or `None` if it is not a lambda.
+    pub fn parameters(&self) -> Option<SExp> {
+        match &self.func {
+            Func::Lambda { formals, .. } => Some(SExp::from(&**formals)),
+            _ => None,
+        }
+    }
+
+    /// A lambda expression showing the parameters of the procedure, e.g.
+    /// `(lambda (x y . rest) ...)`.
+    pub fn signature(&self) -> Option<SExp> {
+        self.parameters()
+            .map(|params| sexp![SExp::sym("lambda"), params, SExp::sym("...")])
+    }
+
+    pub(crate) fn arity_bounds(&self) -> (usize, Option<usize>) {
+        (self.arity.min, self.arity.max)
+    }
+
     pub(crate) fn defer_eval(&self) -> bool {
         matches!(self.func, Func::Ctx(_))
     }
 
+    /// The environment a lambda closes over.
+    pub(crate) fn env(&self) -> Option<&Rc<Env>> {
+        match &self.func {
+            Func::Lambda { envt, .. } | Func::Tail { envt, .. } => Some(envt),
+            _ => None,
+        }
+    }
+
+    /// The same lambda, closing over another environment.
+    pub(crate) fn with_env(&self, envt: Rc<Env>) -> Self {
+        let mut proc = self.clone();
+        if let Func::Lambda { envt: e, .. } | Func::Tail { envt: e, .. } = &mut proc.func {
+            *e = envt;
+        }
+        proc
+    }
+
     pub(crate) fn is_tail(&self) -> bool {
         matches!(self.func, Func::Tail { .. })
     }
@@ -55,14 +105,39 @@ impl Proc {
             Func::Ctx(f) => f(ctx, args),
             Func::Pure(f) => f(args),
             Func::Tail { .. } => Ok(self.clone().into()),
-            Func::Lambda { body, envt, params } => {
+            Func::Parameter(id) => ctx.parameter_value(*id),
+            Func::Lambda {
+                body,
+                envt,
+                formals,
+            } => {
                 // start new scope and bind args to parameters
-                ctx.use_env(envt.clone());
-                ctx.push();
-                params
+                ctx.enter_frame(envt);
+                if let Some(name) = &formals.recursive {
+                    ctx.define(name, self.clone().into());
+                }
+                let mut args = args.into_iter();
+                formals
+                    .params
                     .iter()
-                    .zip(args.into_iter())
+                    .zip(args.by_ref())
                     .for_each(|(p, v)| ctx.define(p, v));
+                // missing optional args take their defaults, which can refer
+                // to the parameters before them
+                for (p, default) in &formals.optional {
+                    let v = match args.next() {
+                        Some(v) => v,
+                        None => ctx.eval(default.clone())?,
+                    };
+                    ctx.define(p, v);
+                }
+                // any remaining args are collected into the rest parameter
+                if let Some(rest) = &formals.rest {
+                    ctx.define(rest, args.collect());
+                }
+                for name in &formals.internal {
+                    ctx.define(name, Primitive::Undefined.into());
+                }
 
                 // evaluate each body expression, returning the last as a thunk
                 ctx.eval_defer(body)
@@ -85,6 +160,7 @@ impl PartialEq for Proc {
                     body: b1, envt: e1, ..
                 },
             ) => Rc::ptr_eq(b0, b1) && Rc::ptr_eq(e0, e1),
+            (Func::Parameter(id0), Func::Parameter(id1)) => id0 == id1,
             _ => false,
         }
     }
@@ -179,8 +255,85 @@ impl From<Arity> for SExp {
     }
 }
 
+/// The parameters of a lambda: required names, then any `#!optional` names
+/// with their default expressions, then any rest parameter.
+#[derive(Default)]
+pub struct Formals {
+    pub params: Vec<String>,
+    pub optional: Vec<(String, SExp)>,
+    pub rest: Option<String>,
+    /// A name for the procedure itself, bound in the scope of each call, as
+    /// for the loop of a named `let`. Binding it there rather than in the
+    /// scope the procedure closes over avoids a reference cycle between the
+    /// two, which would keep both alive forever.
+    pub recursive: Option<String>,
+    /// The names defined at the start of the body, which are bound (but
+    /// undefined) before any of it is evaluated.
+    pub internal: Vec<String>,
+}
+
+impl From<&Formals> for SExp {
+    fn from(formals: &Formals) -> Self {
+        let mut items = formals
+            .params
+            .iter()
+            .map(|p| SExp::sym(p))
+            .collect::<Vec<_>>();
+
+        if !formals.optional.is_empty() {
+            items.push(SExp::sym("#!optional"));
+            items.extend(
+                formals
+                    .optional
+                    .iter()
+                    .map(|(name, default)| match default {
+                        SExp::Atom(Primitive::Default) => SExp::sym(name),
+                        default => sexp![SExp::sym(name), default.clone()],
+                    }),
+            );
+        }
+
+        let rest = formals.rest.as_ref().map_or(SExp::Null, |r| SExp::sym(r));
+        items.into_iter().rev().fold(rest, SExp::cons)
+    }
+}
+
 type CtxFn = dyn Fn(&mut Context, SExp) -> Result;
-type PureFn = dyn Fn(SExp) -> Result;
+pub(crate) type PureFn = dyn Fn(SExp) -> Result;
+
+/// A procedure which is a plain function of the context, so that it can be
+/// listed in a static table and registered from there, rather than by code
+/// which builds each one.
+pub(crate) struct Builtin {
+    name: &'static str,
+    func: fn(&mut Context, SExp) -> Result,
+    arity: Arity,
+}
+
+impl Builtin {
+    /// A procedure taking at least `min` arguments, and at most `max` if
+    /// there is a limit.
+    pub(crate) const fn new(
+        name: &'static str,
+        func: fn(&mut Context, SExp) -> Result,
+        min: usize,
+        max: Option<usize>,
+    ) -> Self {
+        Self {
+            name,
+            func,
+            arity: Arity { min, max },
+        }
+    }
+
+    /// The name of the procedure and the procedure itself, to insert into a
+    /// namespace.
+    pub(crate) fn entry(&self) -> (String, SExp) {
+        let func = Func::Ctx(Rc::new(self.func));
+        let proc = Proc::new(func, self.arity, Some(self.name));
+        (self.name.to_string(), proc.into())
+    }
+}
 
 #[derive(Clone)]
 pub enum Func {
@@ -189,12 +342,14 @@ pub enum Func {
     Lambda {
         body: Rc<SExp>,
         envt: Rc<Env>,
-        params: Vec<String>,
+        formals: Rc<Formals>,
     },
     Tail {
         body: Rc<SExp>,
         envt: Rc<Env>,
     },
+    /// A parameter object, whose current value is kept by the `Context`.
+    Parameter(usize),
 }
 
 impl From<Rc<CtxFn>> for Func {
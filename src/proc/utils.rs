@@ -49,6 +49,39 @@ where
     ))
 }
 
+/// Make a procedure that takes one numeric argument, where the operation may
+/// fail.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+/// use parsley::proc_utils::*;
+/// use parsley::{Error, Num};
+///
+/// let recip = |n: Num| Num::from(1).checked_div(n).ok_or(Error::DivisionByZero);
+///
+/// assert!(Context::base().eval(sexp![make_checked_unary_numeric(recip, None), 0]).is_err());
+/// ```
+pub fn make_checked_unary_numeric<T>(
+    f: impl Fn(Num) -> Result<T, Error> + 'static,
+    name: Option<&str>,
+) -> SExp
+where
+    T: Into<SExp>,
+{
+    SExp::from(Proc::new(
+        Func::Pure(Rc::new(move |e| match e.car()? {
+            Atom(Number(n)) => f(n).map(Into::into),
+            other => Err(Error::Type {
+                expected: "number",
+                given: other.type_of().to_string(),
+            }),
+        })),
+        1,
+        name,
+    ))
+}
+
 /// Make a procedure that takes two numeric arguments.
 ///
 /// # Note
@@ -90,6 +123,49 @@ where
     ))
 }
 
+/// Make a procedure that takes two numeric arguments, where the operation
+/// may fail.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+/// use parsley::proc_utils::*;
+/// use parsley::{Error, Num};
+///
+/// let checked_rem = |a: Num, b| a.checked_rem(b).ok_or(Error::DivisionByZero);
+///
+/// assert_eq!(
+///     Context::base().eval(
+///         sexp![make_checked_binary_numeric(checked_rem, None), 7, 4]
+///     ).unwrap(),
+///     SExp::from(3),
+/// );
+/// ```
+pub fn make_checked_binary_numeric<T>(
+    f: impl Fn(Num, Num) -> Result<T, Error> + 'static,
+    name: Option<&str>,
+) -> SExp
+where
+    T: Into<SExp>,
+{
+    SExp::from(Proc::new(
+        Func::Pure(Rc::new(move |expr| {
+            let (arg0, tail) = expr.split_car()?;
+            let arg1 = tail.car()?;
+
+            match (arg0, arg1) {
+                (Atom(Number(n0)), Atom(Number(n1))) => f(n0, n1).map(Into::into),
+                (Atom(Number(_)), e) | (e, _) => Err(Error::Type {
+                    expected: "number",
+                    given: e.type_of().to_string(),
+                }),
+            }
+        })),
+        2,
+        name,
+    ))
+}
+
 /// Make a variadic procedure that takes a list of numeric arguments and folds
 /// the whole list.
 ///
@@ -142,6 +218,55 @@ where
     ))
 }
 
+/// Like [`make_fold_numeric`](./fn.make_fold_numeric.html), but the folding
+/// function may fail.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+/// use parsley::proc_utils::*;
+/// use parsley::{Error, Num};
+///
+/// let my_gcd = |accumulator: Num, current| {
+///     accumulator.checked_gcd(current).ok_or(Error::Type {
+///         expected: "integer",
+///         given: "number".to_string(),
+///     })
+/// };
+/// let my_gcd_proc = make_checked_fold_numeric(Num::from(0), my_gcd, None);
+///
+/// assert_eq!(
+///     Context::base().eval(
+///         sexp![my_gcd_proc.clone(), 12, 18, 27]
+///     ).unwrap(),
+///     SExp::from(3),
+/// );
+/// assert!(Context::base().eval(sexp![my_gcd_proc, 12, 1.5]).is_err());
+/// ```
+pub fn make_checked_fold_numeric<F>(init: Num, f: F, name: Option<&str>) -> SExp
+where
+    F: Fn(Num, Num) -> Result<Num, Error> + 'static,
+{
+    SExp::from(Proc::new(
+        Func::Pure(Rc::new(move |exp: SExp| {
+            exp.into_iter()
+                .try_fold(init.clone(), |acc, e| {
+                    if let SExp::Atom(Primitive::Number(n)) = e {
+                        f(acc, n)
+                    } else {
+                        Err(Error::Type {
+                            expected: "number",
+                            given: e.type_of().to_string(),
+                        })
+                    }
+                })
+                .map(SExp::from)
+        })),
+        (0,),
+        name,
+    ))
+}
+
 /// Make a variadic procedure that takes a list of numeric arguments, reserves
 /// the value of the first element as the initial accumulator, then folds the
 /// rest of the list into a number.
@@ -167,30 +292,52 @@ where
 pub fn make_fold_from0_numeric<F>(f: F, name: Option<&str>) -> SExp
 where
     F: Fn(Num, Num) -> Num + 'static,
+{
+    make_checked_fold_from0_numeric(move |acc, n| Ok(f(acc, n)), name)
+}
+
+/// Like [`make_fold_from0_numeric`](./fn.make_fold_from0_numeric.html), but
+/// the folding function may fail.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+/// use parsley::proc_utils::*;
+/// use parsley::Error;
+///
+/// let my_divide = |accumulator: parsley::Num, current| {
+///     accumulator.checked_div(current).ok_or(Error::DivisionByZero)
+/// };
+/// let my_div_proc = make_checked_fold_from0_numeric(my_divide, None);
+///
+/// assert_eq!(
+///     Context::base().eval(
+///         sexp![my_div_proc.clone(), 12, 2, 3]
+///     ).unwrap(),
+///     SExp::from(2),
+/// );
+/// assert!(Context::base().eval(sexp![my_div_proc, 12, 0]).is_err());
+/// ```
+pub fn make_checked_fold_from0_numeric<F>(f: F, name: Option<&str>) -> SExp
+where
+    F: Fn(Num, Num) -> Result<Num, Error> + 'static,
 {
     SExp::from(Proc::new(
         Func::Pure(Rc::new(move |exp: SExp| {
             let mut i = exp.into_iter();
             match i.next() {
-                Some(SExp::Atom(Primitive::Number(first))) => {
-                    match i.fold(Ok(first), |a, e| {
-                        if let Ok(val) = a {
-                            if let SExp::Atom(Primitive::Number(n)) = e {
-                                Ok(f(val, n))
-                            } else {
-                                Err(Error::Type {
-                                    expected: "number",
-                                    given: e.type_of().to_string(),
-                                })
-                            }
+                Some(SExp::Atom(Primitive::Number(first))) => i
+                    .try_fold(first, |acc, e| {
+                        if let SExp::Atom(Primitive::Number(n)) = e {
+                            f(acc, n)
                         } else {
-                            a
+                            Err(Error::Type {
+                                expected: "number",
+                                given: e.type_of().to_string(),
+                            })
                         }
-                    }) {
-                        Ok(v) => Ok(v.into()),
-                        Err(err) => Err(err),
-                    }
-                }
+                    })
+                    .map(SExp::from),
                 Some(other) => Err(Error::Type {
                     expected: "number",
                     given: other.type_of().to_string(),
@@ -247,3 +394,44 @@ where
         name,
     ))
 }
+
+/// Make a procedure that checks a relation holds between each pair of
+/// adjacent arguments (of which there must be at least two).
+///
+/// Every argument is converted with `get` first, so a type error is raised
+/// even if the relation already failed for earlier arguments.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+/// use parsley::proc_utils::*;
+/// use parsley::Error;
+///
+/// let len = |e: SExp| Ok::<_, Error>(e.to_string().len());
+/// let shorter = make_chained_comparison(len, |a, b| a < b, None);
+/// let mut ctx = Context::base();
+///
+/// assert_eq!(
+///     ctx.eval(sexp![shorter.clone(), "a", "bb", "ccc"]).unwrap(),
+///     SExp::from(true),
+/// );
+/// assert_eq!(
+///     ctx.eval(sexp![shorter, "a", "bb", "c"]).unwrap(),
+///     SExp::from(false),
+/// );
+/// ```
+pub fn make_chained_comparison<T, G, F>(get: G, f: F, name: Option<&str>) -> SExp
+where
+    G: Fn(SExp) -> Result<T, Error> + 'static,
+    F: Fn(&T, &T) -> bool + 'static,
+{
+    SExp::from(Proc::new(
+        Func::Pure(Rc::new(move |exp| {
+            let args = exp.into_iter().map(&get).collect::<Result<Vec<_>, _>>()?;
+
+            Ok(args.windows(2).all(|pair| f(&pair[0], &pair[1])).into())
+        })),
+        (2,),
+        name,
+    ))
+}
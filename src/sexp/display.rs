@@ -2,27 +2,54 @@ use super::Primitive::Symbol;
 use super::SExp::{self, Atom, Null, Pair};
 use std::fmt;
 
+type FmtItem = fn(&SExp, &mut fmt::Formatter) -> fmt::Result;
+
+/// Write a pair as a list, with the last element after a ` . ` if the list is
+/// improper, or as `'x` if it is a quotation.
+fn fmt_pair(f: &mut fmt::Formatter, head: &SExp, tail: &SExp, fmt_item: FmtItem) -> fmt::Result {
+    if let (
+        Atom(Symbol(q)),
+        Pair {
+            head: quoted,
+            tail: rest,
+        },
+    ) = (head, tail)
+    {
+        if q == "quote" && **rest == Null {
+            write!(f, "'")?;
+            return fmt_item(quoted, f);
+        }
+    }
+
+    write!(f, "(")?;
+    fmt_item(head, f)?;
+
+    let mut rest = tail;
+    loop {
+        match rest {
+            Null => break,
+            Pair { head, tail } => {
+                write!(f, " ")?;
+                fmt_item(head, f)?;
+                rest = tail;
+            }
+            atom @ Atom(_) => {
+                write!(f, " . ")?;
+                fmt_item(atom, f)?;
+                break;
+            }
+        }
+    }
+
+    write!(f, ")")
+}
+
 impl fmt::Debug for SExp {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Null => write!(f, "()",),
             Atom(a) => write!(f, "{:?}", a),
-            Pair { head, tail } => match &**head {
-                Atom(Symbol(q)) if q == "quote" => match &**tail {
-                    Pair { head: h2, tail: t2 } if **t2 == Null => write!(f, "'{}", h2),
-                    _ => write!(f, "'{}", tail),
-                },
-                _ => {
-                    write!(f, "({:?}", head)?;
-                    match &**tail {
-                        Atom(a) => write!(f, " . {:?}", a)?,
-                        null_or_pair => null_or_pair
-                            .iter()
-                            .try_for_each(|item| write!(f, " {:?}", item))?,
-                    }
-                    write!(f, ")")
-                }
-            },
+            Pair { head, tail } => fmt_pair(f, head, tail, <Self as fmt::Debug>::fmt),
         }
     }
 }
@@ -32,22 +59,7 @@ impl fmt::Display for SExp {
         match self {
             Null => write!(f, "()",),
             Atom(a) => write!(f, "{}", a),
-            Pair { head, tail } => match &**head {
-                Atom(Symbol(q)) if q == "quote" => match &**tail {
-                    Pair { head: h2, tail: t2 } if **t2 == Null => write!(f, "'{}", h2),
-                    _ => write!(f, "'{}", tail),
-                },
-                _ => {
-                    write!(f, "({}", head)?;
-                    match &**tail {
-                        Atom(a) => write!(f, " . {}", a)?,
-                        null_or_pair => null_or_pair
-                            .iter()
-                            .try_for_each(|item| write!(f, " {}", item))?,
-                    }
-                    write!(f, ")")
-                }
-            },
+            Pair { head, tail } => fmt_pair(f, head, tail, <Self as fmt::Display>::fmt),
         }
     }
 }
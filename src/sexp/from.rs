@@ -28,12 +28,24 @@ where
     }
 }
 
+/// `()` is the empty list, like `'()` in Scheme. Use
+/// [`SExp::unit`](enum.SExp.html#method.unit) for an expression with no value.
 impl From<()> for SExp {
     fn from(_: ()) -> Self {
         Null
     }
 }
 
+/// A one-tuple is a list of one element, *not* the element itself: `(5,)`
+/// converts to `(5)`.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+///
+/// assert_eq!(SExp::from((5,)), sexp![5]);
+/// assert_ne!(SExp::from((5,)), SExp::from(5));
+/// ```
 impl<T> From<(T,)> for SExp
 where
     SExp: From<T>,
@@ -46,6 +58,17 @@ where
     }
 }
 
+/// A two-tuple is a pair, *not* a list of two elements: `(1, 2)` converts to
+/// `(1 . 2)`, the same as [`SExp::dotted`](enum.SExp.html#method.dotted).
+/// Nest pairs ending in `()` to make a list, or use [`sexp!`](macro.sexp.html).
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+///
+/// assert_eq!(SExp::from((1, 2)).to_string(), "(1 . 2)");
+/// assert_eq!(SExp::from((1, (2, ()))), sexp![1, 2]);
+/// ```
 impl<T, U> From<(T, U)> for SExp
 where
     T: Into<SExp>,
@@ -59,6 +82,24 @@ where
     }
 }
 
+/// `None` converts to the empty list, and `Some(value)` to the value itself.
+///
+/// # Example
+/// ```
+/// use parsley::prelude::*;
+///
+/// assert_eq!(SExp::from(None::<i32>), SExp::null());
+/// assert_eq!(SExp::from(Some(5)), SExp::from(5));
+/// ```
+impl<T> From<Option<T>> for SExp
+where
+    T: Into<SExp>,
+{
+    fn from(opt: Option<T>) -> Self {
+        opt.map_or(Null, T::into)
+    }
+}
+
 impl<T> From<&[T]> for SExp
 where
     T: Into<SExp> + Clone,
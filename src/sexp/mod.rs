@@ -6,7 +6,7 @@ mod eval;
 mod iter;
 mod parse;
 
-use super::{utils, Error, Primitive, Result, SyntaxError};
+use super::{utils, Error, Primitive, Resource, Result, Span, Symbol, SyntaxError};
 
 use self::SExp::{Atom, Null, Pair};
 
@@ -76,6 +76,40 @@ impl SExp {
         }
     }
 
+    pub(super) fn set_nth(&mut self, n: usize, new: Self) -> Result {
+        let mut cell = self;
+
+        for _ in 0..n {
+            cell = match cell {
+                Pair { tail, .. } => tail,
+                _ => return Err(Error::Index { i: n }),
+            };
+        }
+
+        match cell {
+            Pair { .. } => cell.set_car(new),
+            _ => Err(Error::Index { i: n }),
+        }
+    }
+
+    /// Package up multiple return values. A single value stands for itself.
+    pub(super) fn values(mut values: Vec<Self>) -> Self {
+        if values.len() == 1 {
+            values.remove(0)
+        } else {
+            Atom(Primitive::Values(values))
+        }
+    }
+
+    /// Unpack the results of an expression which may have returned multiple
+    /// values.
+    pub(super) fn into_values(self) -> Vec<Self> {
+        match self {
+            Atom(Primitive::Values(values)) => values,
+            other => vec![other],
+        }
+    }
+
     /// The natural way to build up a list - from the end to the beginning.
     ///
     /// # Example
@@ -96,6 +130,68 @@ impl SExp {
         }
     }
 
+    /// Build a pair (a cons cell) from its two halves. When the second half
+    /// is not a list, the result is an improper list, printed with a ` . `.
+    ///
+    /// This is the same as converting a two-tuple, so a list has to end in
+    /// `()`: `SExp::from((1, (2, ())))` is the list `(1 2)`.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    ///
+    /// let pair = SExp::dotted(1, 2);
+    /// assert_eq!(pair.to_string(), "(1 . 2)");
+    /// assert_eq!(pair, SExp::from((1, 2)));
+    ///
+    /// assert_eq!(SExp::dotted(1, SExp::dotted(2, 3)).to_string(), "(1 2 . 3)");
+    /// assert_eq!(SExp::dotted(1, sexp![2, 3]).to_string(), "(1 2 3)");
+    /// assert_eq!(
+    ///     sexp![SExp::dotted(1, 2), SExp::dotted(SExp::dotted(3, 4), 5)].to_string(),
+    ///     "((1 . 2) ((3 . 4) . 5))"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn dotted(head: impl Into<Self>, tail: impl Into<Self>) -> Self {
+        Pair {
+            head: Box::new(head.into()),
+            tail: Box::new(tail.into()),
+        }
+    }
+
+    /// The empty list, `()`. This is the same as `SExp::Null` and
+    /// `SExp::from(())`, and unlike in some Lisps, it is not false.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    ///
+    /// assert_eq!(SExp::null(), SExp::Null);
+    /// assert_eq!(run("'()").unwrap(), SExp::null());
+    /// assert_ne!(SExp::null(), SExp::from(false));
+    /// ```
+    #[must_use]
+    pub fn null() -> Self {
+        Null
+    }
+
+    /// The value of an expression with nothing useful to return, such as
+    /// `(void)` or `(display x)`. It is neither the empty list nor false, and
+    /// it doesn't print anything.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    ///
+    /// assert_eq!(run("(void)").unwrap(), SExp::unit());
+    /// assert_ne!(SExp::unit(), SExp::null());
+    /// assert_eq!(SExp::unit().to_string(), "");
+    /// ```
+    #[must_use]
+    pub fn unit() -> Self {
+        Atom(Primitive::Void)
+    }
+
     /// Convenience method to build a symbolic atom.
     ///
     /// # Example
@@ -115,6 +211,42 @@ impl SExp {
         Atom(Primitive::Symbol(sym.to_string()))
     }
 
+    /// The name of a symbolic atom, or `None` for any other expression.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    ///
+    /// assert_eq!(SExp::sym("x").as_symbol().unwrap(), "x");
+    /// assert_eq!(SExp::from("x").as_symbol(), None);
+    /// ```
+    #[must_use]
+    pub fn as_symbol(&self) -> Option<Symbol> {
+        match self {
+            Atom(Primitive::Symbol(s)) => Some(s.into()),
+            _ => None,
+        }
+    }
+
+    /// The resource held by an atom, or `None` for any other expression.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// use parsley::Resource;
+    ///
+    /// let file = SExp::from(Resource::new("file", 3));
+    /// assert_eq!(file.as_resource().unwrap().kind(), "file");
+    /// assert!(SExp::from(3).as_resource().is_none());
+    /// ```
+    #[must_use]
+    pub fn as_resource(&self) -> Option<Resource> {
+        match self {
+            Atom(Primitive::Resource(r)) => Some(r.clone()),
+            _ => None,
+        }
+    }
+
     /// Printable type for an expression.
     ///
     /// # Example
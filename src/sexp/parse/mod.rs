@@ -4,8 +4,9 @@ use std::str::FromStr;
 use super::{
     utils, Error, Primitive, Result,
     SExp::{self, Atom, Null},
-    SyntaxError,
+    Span, SyntaxError,
 };
+use super::super::Num;
 
 mod tests;
 
@@ -41,6 +42,8 @@ enum Token {
     Quasiquote,
     Unquote,
     UnquoteSplicing,
+    DatumComment,
+    Dot,
     StringLiteral(String),
     Atom(String),
 }
@@ -61,9 +64,32 @@ impl Token {
             "`" => Some(Token::Quasiquote),
             "," => Some(Token::Unquote),
             ",@" => Some(Token::UnquoteSplicing),
+            "#;" => Some(Token::DatumComment),
             _ => None,
         }
     }
+
+    /// The text of a token which is written as a fixed sigil, the inverse of
+    /// `from_sigil`.
+    fn sigil(&self) -> Option<&'static str> {
+        match self {
+            Token::OpenParen(Paren::Round) => Some("("),
+            Token::OpenParen(Paren::Square) => Some("["),
+            Token::OpenParen(Paren::Curly) => Some("{"),
+            Token::OpenHashParen(Paren::Round) => Some("#("),
+            Token::OpenHashParen(Paren::Square) => Some("#["),
+            Token::OpenHashParen(Paren::Curly) => Some("#{"),
+            Token::CloseParen(Paren::Round) => Some(")"),
+            Token::CloseParen(Paren::Square) => Some("]"),
+            Token::CloseParen(Paren::Curly) => Some("}"),
+            Token::Quote => Some("'"),
+            Token::Quasiquote => Some("`"),
+            Token::Unquote => Some(","),
+            Token::UnquoteSplicing => Some(",@"),
+            Token::DatumComment => Some("#;"),
+            Token::Dot | Token::StringLiteral(_) | Token::Atom(_) => None,
+        }
+    }
 }
 
 impl FromStr for Token {
@@ -77,6 +103,10 @@ impl FromStr for Token {
                 return Ok(Token::StringLiteral(s[1..s.len() - 1].into()));
             }
 
+            if s == "." {
+                return Ok(Token::Dot);
+            }
+
             if s.chars().all(utils::is_atom_char) {
                 return Ok(Token::Atom(s.into()));
             }
@@ -86,167 +116,321 @@ impl FromStr for Token {
     }
 }
 
-fn get_next_token(s: &str) -> std::result::Result<(Option<Token>, &str), SyntaxError> {
-    let mut s = s.trim_start();
+/// A token and its location in the source.
+type Lexeme = (Token, Span);
+
+/// Find the next token in `src` at or after byte offset `pos`.
+fn get_next_token(src: &str, pos: usize) -> std::result::Result<Option<Lexeme>, SyntaxError> {
+    let mut s = src[pos..].trim_start();
 
     // throw out comments
-    if s.starts_with(';') {
+    while s.starts_with(';') {
         let next_newline = s.find('\n').unwrap_or(s.len());
-        s = &s[next_newline..];
+        s = s[next_newline..].trim_start();
     }
 
-    s = s.trim_start();
     if s.is_empty() {
-        return Ok((None, s));
+        return Ok(None);
     }
 
+    let start = src.len() - s.len();
+    let span = |len| Span::new(start, start + len);
+
     // special handling for string literals
     if s.starts_with('"') {
-        let mut pos = 1;
         let mut esc = false;
-        for c in s.chars().skip(1) {
-            match c {
-                '\\' => esc = !esc,
-                '"' if !esc => break,
-                _ => esc = false,
+        let close = s.char_indices().skip(1).find(|&(_, c)| match c {
+            '\\' => {
+                esc = !esc;
+                false
             }
-            pos += 1;
-        }
-
-        if pos == s.len() - 1 && !s.ends_with('"') {
-            return Err(SyntaxError::UnmatchedQuote(s.into()));
-        }
+            '"' if !esc => true,
+            _ => {
+                esc = false;
+                false
+            }
+        });
 
-        return Ok((Some(s[..=pos].parse()?), &s[pos + 1..]));
+        return match close {
+            Some((pos, _)) => Ok(Some((s[..=pos].parse()?, span(pos + 1)))),
+            None => Err(SyntaxError::UnterminatedString {
+                span: span(s.len()),
+            }),
+        };
     }
 
     // sigils - can be 1 or 2 chars
     for len in 1..3 {
-        if len <= s.len() {
-            let (t, rest) = s.split_at(len);
-            if let Some(tok) = Token::from_sigil(t) {
-                return Ok((Some(tok), rest));
+        if let Some(tok) = s.get(..len).and_then(Token::from_sigil) {
+            return Ok(Some((tok, span(len))));
+        }
+    }
+
+    // character literals - the character may be a delimiter, like in `#\(`
+    // or `#\ `, but a name like `#\space` ends at the next one
+    if let Some(rest) = s.strip_prefix("#\\") {
+        if let Some(c) = rest.chars().next() {
+            let mut len = 2 + c.len_utf8();
+            if utils::is_atom_char(c) {
+                let name = &s[len..];
+                len += name.find(|c| !utils::is_atom_char(c)).unwrap_or(name.len());
             }
+            return Ok(Some((Token::Atom(s[..len].into()), span(len))));
         }
     }
 
     // atom/primitive values
-    let pos = s.find(|c| !utils::is_atom_char(c)).unwrap_or(s.len());
-    Ok((Some(s[..pos].parse()?), &s[pos..]))
+    let len = s.find(|c| !utils::is_atom_char(c)).unwrap_or(s.len());
+    Ok(Some((s[..len].parse()?, span(len))))
 }
 
-fn lex(mut s: &str) -> std::result::Result<Vec<Token>, SyntaxError> {
+fn lex(s: &str) -> std::result::Result<Vec<Lexeme>, SyntaxError> {
     let mut tokens = Vec::new();
+    let mut pos = 0;
 
-    while !s.is_empty() {
-        let (tok, new_s) = get_next_token(s)?;
-        s = new_s;
-        if let Some(tok) = tok {
-            tokens.push(tok);
-        }
+    while let Some((tok, span)) = get_next_token(s, pos)? {
+        pos = span.end;
+        tokens.push((tok, span));
     }
 
     Ok(tokens)
 }
 
-fn parse_list_tokens(
-    tokens: &[Token],
-    paren_type: Paren,
-) -> std::result::Result<(Vec<SExp>, &[Token]), SyntaxError> {
-    let mut idx = 1;
-    let mut n = 0;
-
-    for tok in &tokens[1..] {
-        match *tok {
-            Token::OpenParen(_) | Token::OpenHashParen(_) => n += 1,
-            Token::CloseParen(p) if n == 0 && p == paren_type => break,
-            Token::CloseParen(ref p) if n == 0 => {
-                return Err(SyntaxError::UnmatchedParen {
-                    exp: format!("{:?}", tokens),
-                    expected: (&paren_type).into(),
-                    given: Some(p.into()),
-                });
+/// A quote symbol, or `None` for a datum comment, along with the index of the
+/// token it was read from.
+type Prefix = (Option<SExp>, usize);
+
+/// A list (or vector) which has been opened but not yet closed.
+struct Frame {
+    paren: Paren,
+    is_vector: bool,
+    /// Location of the opening token.
+    open: Span,
+    /// How deeply the items of the list are nested, counting lists and
+    /// quotes around them.
+    depth: usize,
+    /// Quotes and datum comments applied to the list as a whole, and how many
+    /// of them are quotes.
+    prefixes: Vec<Prefix>,
+    quotes: usize,
+    items: Vec<SExp>,
+    /// The number of items before a ` . `, if there is one. The item after it
+    /// becomes the tail of an improper list.
+    dot: Option<usize>,
+}
+
+impl Frame {
+    /// Build the list or vector, given the location of the closing token.
+    fn close(mut self, span: Span) -> std::result::Result<SExp, SyntaxError> {
+        if self.is_vector {
+            return Ok(Atom(Primitive::Vector(self.items)));
+        }
+
+        match self.dot {
+            // the dot must be followed by exactly one item
+            Some(n) if self.items.len() != n + 1 => Err(SyntaxError::UnexpectedDot { span }),
+            Some(_) => {
+                let last = self.items.pop().unwrap_or(Null);
+                Ok(self.items.into_iter().rev().fold(last, SExp::cons))
             }
-            Token::CloseParen(_) => n -= 1,
-            _ => (),
+            None => Ok(self.items.into()),
         }
-        idx += 1;
     }
+}
 
-    if n != 0 {
-        return Err(SyntaxError::UnmatchedParen {
-            exp: format!("{:?}", tokens),
-            expected: (&paren_type).into(),
-            given: None,
-        });
+fn quote_prefix(token: &Token) -> Option<SExp> {
+    match token {
+        Token::Quote => Some(SExp::sym("quote")),
+        Token::Quasiquote => Some(SExp::sym("quasiquote")),
+        Token::Unquote => Some(SExp::sym("unquote")),
+        Token::UnquoteSplicing => Some(SExp::sym("unquote-splicing")),
+        _ => None,
     }
+}
 
-    let mut list_tokens = &tokens[1..idx];
-    let mut list_out = Vec::new();
-
-    while !list_tokens.is_empty() {
-        let (expr, new_list_tokens) = get_next_sexp(list_tokens)?;
-        list_tokens = new_list_tokens;
-        list_out.push(expr);
+/// The error for input which ends part way through an expression: a list is
+/// still open, or a quote or datum comment has nothing after it.
+fn unterminated(tokens: &[Lexeme], stack: &[Frame], prefixes: &[Prefix]) -> SyntaxError {
+    if let Some(frame) = stack.last() {
+        let end = tokens.last().map_or(0, |(_, span)| span.end);
+        return SyntaxError::UnmatchedParen {
+            expected: Some((&frame.paren).into()),
+            given: None,
+            span: Span::new(frame.open.start, end),
+        };
     }
 
-    Ok((list_out, &tokens[idx + 1..]))
+    let (token, _) = &tokens[prefixes[0].1];
+    SyntaxError::NotAToken(
+        token
+            .sigil()
+            .map_or_else(|| format!("{token:?}"), str::to_string),
+    )
 }
 
-fn dequote(mut tokens: &[Token]) -> (Vec<SExp>, &[Token]) {
-    let mut v = Vec::new();
+/// Parse one expression from the front of a token list, returning it and the
+/// remaining tokens. There may be no expression left if the rest of the tokens
+/// are commented out with `#;`.
+///
+/// This works iteratively, with an explicit stack of open lists, so deeply
+/// nested input cannot overflow the call stack - it is instead rejected once
+/// it exceeds `max_depth`. Each quote counts as a level of nesting, since
+/// `'x` is short for `(quote x)`.
+/// `#e` on a number with no exact equivalent, such as `#e1.5`, is the same
+/// error as `(exact 1.5)` rather than a syntax error.
+fn exactness_error(err: SyntaxError) -> Error {
+    if let SyntaxError::InvalidNumber { text, .. } = &err {
+        if let Err(inexact @ Error::Type { .. }) = Num::from_prefixed(text) {
+            return inexact;
+        }
+    }
+    err.into()
+}
 
-    while !tokens.is_empty() {
-        let quote = SExp::sym(match tokens[0] {
-            Token::Quote => "quote",
-            Token::Quasiquote => "quasiquote",
-            Token::Unquote => "unquote",
-            Token::UnquoteSplicing => "unquote-splicing",
-            _ => break,
-        });
+fn get_next_sexp(
+    tokens: &[Lexeme],
+    max_depth: usize,
+) -> std::result::Result<(Option<SExp>, &[Lexeme]), SyntaxError> {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut prefixes: Vec<Prefix> = Vec::new();
+    // the number of quotes among `prefixes`
+    let mut quotes = 0;
+    let mut idx = 0;
+
+    loop {
+        let depth = stack.last().map_or(0, |frame| frame.depth) + quotes;
+
+        let Some((token, span)) = tokens.get(idx) else {
+            if stack.is_empty() && prefixes.is_empty() {
+                return Ok((None, &tokens[idx..]));
+            }
 
-        v.push(quote);
-        tokens = &tokens[1..];
-    }
+            return Err(unterminated(tokens, &stack, &prefixes));
+        };
+        idx += 1;
 
-    (v, tokens)
-}
+        let mut expr = match token {
+            Token::Atom(s) => Atom(s.parse().map_err(|e: SyntaxError| e.with_span(*span))?),
+            Token::StringLiteral(s) => Atom(Primitive::String(s.clone())),
+            Token::OpenParen(p) | Token::OpenHashParen(p) => {
+                if depth >= max_depth {
+                    return Err(SyntaxError::TooDeep(max_depth));
+                }
+
+                stack.push(Frame {
+                    paren: *p,
+                    is_vector: matches!(token, Token::OpenHashParen(_)),
+                    open: *span,
+                    depth: depth + 1,
+                    prefixes: std::mem::take(&mut prefixes),
+                    quotes: std::mem::take(&mut quotes),
+                    items: Vec::new(),
+                    dot: None,
+                });
+                continue;
+            }
+            Token::CloseParen(p) => match stack.pop() {
+                Some(mut frame) if frame.paren == *p && prefixes.is_empty() => {
+                    prefixes = std::mem::take(&mut frame.prefixes);
+                    quotes = frame.quotes;
+                    frame.close(*span)?
+                }
+                Some(frame) => {
+                    return Err(SyntaxError::UnmatchedParen {
+                        expected: Some((&frame.paren).into()),
+                        given: Some(p.into()),
+                        span: Span::new(frame.open.start, span.end),
+                    });
+                }
+                None => {
+                    return Err(SyntaxError::UnmatchedParen {
+                        expected: None,
+                        given: Some(p.into()),
+                        span: *span,
+                    })
+                }
+            },
+            Token::Dot => match stack.last_mut() {
+                Some(frame)
+                    if !frame.is_vector
+                        && frame.dot.is_none()
+                        && !frame.items.is_empty()
+                        && prefixes.is_empty() =>
+                {
+                    frame.dot = Some(frame.items.len());
+                    continue;
+                }
+                _ => return Err(SyntaxError::UnexpectedDot { span: *span }),
+            },
+            Token::DatumComment => {
+                prefixes.push((None, idx - 1));
+                continue;
+            }
+            quote => {
+                if depth >= max_depth {
+                    return Err(SyntaxError::TooDeep(max_depth));
+                }
+
+                if let Some(q) = quote_prefix(quote) {
+                    prefixes.push((Some(q), idx - 1));
+                    quotes += 1;
+                }
+                continue;
+            }
+        };
+
+        // apply quotes from the inside out, unless a datum comment swallows
+        // the expression first - any quotes before it apply to the next one
+        let mut discarded = false;
+        while let Some((prefix, _)) = prefixes.pop() {
+            if let Some(quote) = prefix {
+                quotes -= 1;
+                expr = Null.cons(expr).cons(quote);
+            } else {
+                discarded = true;
+                break;
+            }
+        }
 
-fn get_next_sexp(tokens: &[Token]) -> std::result::Result<(SExp, &[Token]), SyntaxError> {
-    let (prefixes, tokens) = dequote(tokens);
-
-    let mut quotable = match tokens.split_first() {
-        Some((Token::Atom(s), rest)) => (Atom(s.parse()?), rest),
-        Some((Token::StringLiteral(s), rest)) => (Atom(Primitive::String(s.to_string())), rest),
-        Some((Token::OpenParen(paren_type), rest)) => match rest.split_first() {
-            Some((Token::CloseParen(p), rest)) if p == paren_type => (Null, rest),
-            _ => parse_list_tokens(tokens, *paren_type).map(|(v, t)| (v.into(), t))?,
-        },
-        Some((Token::OpenHashParen(paren_type), _)) => {
-            parse_list_tokens(tokens, *paren_type).map(|(v, t)| (Atom(Primitive::Vector(v)), t))?
+        if discarded {
+            continue;
         }
-        _ => unreachable!("`get_next_sexp` should only be called with a non-empty list of tokens."),
-    };
 
-    for prefix in prefixes.into_iter().rev() {
-        quotable.0 = Null.cons(quotable.0).cons(prefix);
+        match stack.last_mut() {
+            Some(frame) => frame.items.push(expr),
+            None => return Ok((Some(expr), &tokens[idx..])),
+        }
     }
-
-    Ok(quotable)
 }
 
-impl FromStr for SExp {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result {
+impl SExp {
+    /// The maximum nesting depth of lists, vectors and quotes accepted by
+    /// [`from_str`](#method.from_str).
+    pub const MAX_DEPTH: usize = 1024;
+
+    /// Parse code, with a limit on how deeply lists, vectors and quotes may be
+    /// nested.
+    ///
+    /// # Errors
+    /// Returns a syntax error if the code is invalid or nested too deeply, or
+    /// a type error if a number marked exact with `#e` has no exact value.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::SExp;
+    ///
+    /// assert!(SExp::parse_with_max_depth("((()))", 3).is_ok());
+    /// assert!(SExp::parse_with_max_depth("((()))", 2).is_err());
+    /// ```
+    pub fn parse_with_max_depth(s: &str, max_depth: usize) -> Result {
         let token_list = lex(s)?;
         let mut tokens = &token_list[..];
 
         let mut exprs = vec![Self::sym("begin")];
         while !tokens.is_empty() {
-            let (expr, remaining) = get_next_sexp(tokens)?;
+            let (expr, remaining) = get_next_sexp(tokens, max_depth).map_err(exactness_error)?;
             tokens = remaining;
-            exprs.push(expr);
+            exprs.extend(expr);
         }
 
         // don't need `begin` expression if there's only one inside
@@ -257,3 +441,11 @@ impl FromStr for SExp {
         Ok(exprs.into())
     }
 }
+
+impl FromStr for SExp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result {
+        Self::parse_with_max_depth(s, Self::MAX_DEPTH)
+    }
+}
@@ -61,8 +61,14 @@ fn primitive_types() {
     do_parse_and_assert("#t", SExp::from(true));
     do_parse_and_assert("0", SExp::from(0));
     do_parse_and_assert("2.0", SExp::from(2));
-    do_parse_and_assert("inf", SExp::from(std::f64::INFINITY));
-    do_parse_and_assert("-inf", SExp::from(std::f64::NEG_INFINITY));
+    do_parse_and_assert("+inf.0", SExp::from(std::f64::INFINITY));
+    do_parse_and_assert("-inf.0", SExp::from(std::f64::NEG_INFINITY));
+    do_parse_and_assert("#i-inf.0", SExp::from(std::f64::NEG_INFINITY));
+    assert_eq!("+nan.0".parse::<SExp>().unwrap().to_string(), "+nan.0");
+    // the names Rust reads as special values are symbols
+    do_parse_and_assert("inf", SExp::sym("inf"));
+    do_parse_and_assert("-infinity", SExp::sym("-infinity"));
+    do_parse_and_assert("NaN", SExp::sym("NaN"));
     do_parse_and_assert("#\\c", SExp::from('c'));
     do_parse_and_assert("#\\'", SExp::from('\''));
     do_parse_and_assert(
@@ -71,6 +77,31 @@ fn primitive_types() {
     );
 }
 
+#[test]
+fn character_literals() {
+    do_parse_and_assert("#\\space", SExp::from(' '));
+    do_parse_and_assert("#\\newline", SExp::from('\n'));
+    do_parse_and_assert("#\\delete", SExp::from('\u{7f}'));
+    do_parse_and_assert("#\\x41", SExp::from('A'));
+    do_parse_and_assert("#\\x3bb;", SExp::from('λ'));
+    do_parse_and_assert("#\\x", SExp::from('x'));
+    do_parse_and_assert("#\\λ", SExp::from('λ'));
+    do_parse_and_assert(
+        "(#\\( #\\) #\\ )",
+        Null.cons(SExp::from(' '))
+            .cons(SExp::from(')'))
+            .cons(SExp::from('(')),
+    );
+    assert!("#\\xyz".parse::<SExp>().is_err());
+
+    for c in ['a', ' ', '\n', '\t', '\u{0}', '\u{1}', '(', 'λ'] {
+        let written = format!("{:?}", SExp::from(c));
+        assert_eq!(written.parse::<SExp>().unwrap(), SExp::from(c), "{written}");
+    }
+    assert_eq!(format!("{:?}", SExp::from(' ')), "#\\space");
+    assert_eq!(format!("{:?}", SExp::from('\u{1}')), "#\\x1");
+}
+
 #[test]
 fn mixed_type_list() {
     do_parse_and_assert(
@@ -184,3 +215,247 @@ mod parens {
         );
     }
 }
+
+mod depth {
+    use super::SExp;
+    use crate::{Error, SyntaxError};
+
+    #[test]
+    fn deeply_nested() {
+        let deep = "(".repeat(100_000);
+        assert!(deep.parse::<SExp>().is_err());
+
+        let deep = format!("{}{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(deep.parse::<SExp>().is_err());
+
+        let deep = format!("{}x", "'".repeat(200_000));
+        assert!(deep.parse::<SExp>().is_err());
+
+        let deep = format!("{}x{}", "(`".repeat(100_000), ")".repeat(100_000));
+        assert!(deep.parse::<SExp>().is_err());
+    }
+
+    #[test]
+    fn stray_tokens() {
+        assert!("'".parse::<SExp>().is_err());
+        assert!(")".parse::<SExp>().is_err());
+        assert!("(a ')".parse::<SExp>().is_err());
+
+        // only the dangling prefix is reported, not everything after it
+        for (code, token) in [("(a b) 'c '", "'"), ("`#; x", "`"), ("#; ,", "#;")] {
+            assert!(matches!(
+                code.parse::<SExp>(),
+                Err(Error::Syntax(SyntaxError::NotAToken(s))) if s == token
+            ));
+        }
+    }
+
+    #[test]
+    fn limit() {
+        let nested = |n| format!("{}{}", "(a ".repeat(n), ")".repeat(n));
+
+        assert!(SExp::parse_with_max_depth(&nested(5), 5).is_ok());
+        assert!(SExp::parse_with_max_depth(&nested(6), 5).is_err());
+        assert!(SExp::parse_with_max_depth("#(#(1))", 1).is_err());
+        assert!(SExp::parse_with_max_depth("'(a)", 2).is_ok());
+        assert!(SExp::parse_with_max_depth("'(a)", 1).is_err());
+        assert!(SExp::parse_with_max_depth("(a ,@'b)", 2).is_err());
+        assert!(SExp::parse_with_max_depth("#;#;#;#; a b c d", 1).is_ok());
+        assert!(nested(SExp::MAX_DEPTH).parse::<SExp>().is_ok());
+    }
+}
+
+mod spans {
+    use super::SExp;
+    use crate::{Error, Span, SyntaxError};
+
+    fn syntax_err(code: &str) -> SyntaxError {
+        match code.parse::<SExp>() {
+            Err(Error::Syntax(e)) => e,
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unmatched_paren() {
+        match syntax_err("(a (b c])") {
+            SyntaxError::UnmatchedParen {
+                expected: Some(')'),
+                given: Some(']'),
+                span,
+            } => assert_eq!(span, Span::new(3, 8)),
+            other => panic!("wrong error: {:?}", other),
+        }
+
+        match syntax_err("(a\n  (b c)") {
+            SyntaxError::UnmatchedParen {
+                expected: Some(')'),
+                given: None,
+                span,
+            } => assert_eq!(span, Span::new(0, 10)),
+            other => panic!("wrong error: {:?}", other),
+        }
+
+        match syntax_err("x ]") {
+            SyntaxError::UnmatchedParen {
+                expected: None,
+                given: Some(']'),
+                span,
+            } => assert_eq!(span, Span::new(2, 3)),
+            other => panic!("wrong error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let err = syntax_err("(display \"héllo)");
+        assert!(matches!(err, SyntaxError::UnterminatedString { .. }));
+        assert_eq!(err.span(), Some(Span::new(9, 17)));
+
+        assert!(r#"(display "a \"quoted\" word")"#.parse::<SExp>().is_ok());
+        assert!(r#""héllo""#.parse::<SExp>().is_ok());
+    }
+
+    #[test]
+    fn invalid_number() {
+        let err = "12x".parse::<crate::Num>().unwrap_err();
+        assert!(
+            matches!(err, SyntaxError::InvalidNumber { ref text, span: None } if text == "12x")
+        );
+
+        let err = syntax_err("(+ 1 1.2.3)");
+        assert!(matches!(err, SyntaxError::InvalidNumber { ref text, .. } if text == "1.2.3"));
+        assert_eq!(err.span(), Some(Span::new(5, 10)));
+    }
+}
+
+#[test]
+fn number_corpus() {
+    let numbers = [
+        ("0", 0.),
+        ("-12", -12.),
+        ("+7", 7.),
+        ("3.25", 3.25),
+        (".5", 0.5),
+        ("-.5", -0.5),
+        ("5.", 5.),
+        ("1e3", 1000.),
+        ("2.5E-1", 0.25),
+        ("99999999999999999999", 1e20),
+        ("1e308", 1e308),
+    ];
+    for (text, value) in numbers {
+        assert_eq!(text.parse::<SExp>().unwrap(), SExp::from(value), "{text}");
+    }
+
+    let malformed = [
+        "1.2.3", "--5", "+-5", "-+.5", "1e99999", "-1e99999", "1e", "1e+", "12x", "1..2", "0x1f",
+        "1_000", ".5.", "3-4", "1/2", "1+",
+    ];
+    for text in malformed {
+        assert!(
+            matches!(
+                text.parse::<SExp>(),
+                Err(crate::Error::Syntax(
+                    crate::SyntaxError::InvalidNumber { .. }
+                ))
+            ),
+            "{text}"
+        );
+    }
+
+    let symbols = ["-", "+", "...", "->x", "-x1", "e10", "x1.2.3", ".x"];
+    for text in symbols {
+        assert_eq!(text.parse::<SExp>().unwrap(), SExp::sym(text), "{text}");
+    }
+}
+
+#[test]
+fn number_prefixes() {
+    let numbers = [
+        ("#xff", 255., true),
+        ("#XFF", 255., true),
+        ("#x-1A", -26., true),
+        ("#o17", 15., true),
+        ("#b1010", 10., true),
+        ("#d99", 99., true),
+        ("#e1e3", 1000., true),
+        ("#e-2.0", -2., true),
+        ("#i3", 3., false),
+        ("#x#e10", 16., true),
+        ("#e#x10", 16., true),
+        ("#i#b101", 5., false),
+        ("#d#i2.5", 2.5, false),
+    ];
+    for (text, value, exact) in numbers {
+        let num = text.parse::<crate::Num>().unwrap();
+        assert_eq!(num.is_exact(), exact, "{text}");
+        assert_eq!(f64::from(num), value, "{text}");
+    }
+    assert_eq!("(#xa #b11)".parse::<SExp>().unwrap(), sexp![10, 3]);
+
+    let malformed = [
+        "#x", "#xfg", "#b102", "#o8", "#x1.5", "#e#e1", "#x#b1", "#e", "#einf", "#x_f",
+    ];
+    for text in malformed {
+        assert!(
+            matches!(
+                text.parse::<SExp>(),
+                Err(crate::Error::Syntax(
+                    crate::SyntaxError::InvalidNumber { .. }
+                ))
+            ),
+            "{text}"
+        );
+    }
+
+    // a number with no exact value is the same error as `(exact 1.5)`
+    for text in ["#e1.5", "(a #e#x1 #e-0.25)", "#e+inf.0"] {
+        assert!(
+            matches!(
+                text.parse::<SExp>(),
+                Err(crate::Error::Type {
+                    expected: "finite integer",
+                    ..
+                })
+            ),
+            "{text}"
+        );
+    }
+    assert!("#e1.5".parse::<crate::Num>().is_err());
+}
+
+#[test]
+fn datum_comments() {
+    do_parse_and_assert("(1 #;2 3)", sexp![1, 3]);
+    do_parse_and_assert("(1 #;(2 (3)) 4)", sexp![1, 4]);
+    do_parse_and_assert("(1 #; #;2 3 4)", sexp![1, 4]);
+    do_parse_and_assert("#;'(a b) c", SExp::sym("c"));
+    do_parse_and_assert("'#;a b", sexp![SExp::sym("quote"), SExp::sym("b")]);
+    do_parse_and_assert(
+        "#;(display 1) (display 2) #;3",
+        sexp![SExp::sym("display"), 2],
+    );
+
+    assert!("(1 #;)".parse::<SExp>().is_err());
+    assert!("1 #;".parse::<SExp>().is_err());
+}
+
+#[test]
+fn dotted_pairs() {
+    let (a, b, c) = (SExp::sym("a"), SExp::sym("b"), SExp::sym("c"));
+
+    do_parse_and_assert("(a . b)", b.clone().cons(a.clone()));
+    do_parse_and_assert("(a b . c)", c.clone().cons(b.clone()).cons(a.clone()));
+    do_parse_and_assert("(a . (b c))", sexp![a.clone(), b.clone(), c.clone()]);
+    do_parse_and_assert("'(a . b)", sexp![SExp::sym("quote"), b.cons(a)]);
+    // a lone dot in a symbol or number is not a pair
+    do_parse_and_assert("(a .b .5)", sexp![SExp::sym("a"), SExp::sym(".b"), 0.5]);
+
+    assert!("(. a)".parse::<SExp>().is_err());
+    assert!("(a .)".parse::<SExp>().is_err());
+    assert!("(a . b c)".parse::<SExp>().is_err());
+    assert!("(a . . b)".parse::<SExp>().is_err());
+    assert!("#(a . b)".parse::<SExp>().is_err());
+    assert!(". a".parse::<SExp>().is_err());
+}
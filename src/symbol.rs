@@ -0,0 +1,159 @@
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::{Primitive, SExp};
+
+thread_local! {
+    /// Every symbol name made so far on this thread, so that each is only
+    /// stored once.
+    static NAMES: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// The name of a symbol. Names are interned, so cloning and comparing them
+/// is cheap.
+///
+/// # Example
+/// ```
+/// use parsley::{SExp, Symbol};
+///
+/// let x = Symbol::new("x");
+/// assert_eq!(x, "x");
+/// assert_eq!(x, Symbol::from(String::from("x")));
+/// assert_eq!(x.len(), 1);
+/// assert_eq!(String::from(x.clone()), "x");
+/// assert_eq!(SExp::from(x), SExp::sym("x"));
+/// ```
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    /// The symbol with a given name.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        NAMES.with(|names| {
+            let mut names = names.borrow_mut();
+            if let Some(interned) = names.get(name) {
+                return Self(interned.clone());
+            }
+            let interned = Rc::<str>::from(name);
+            names.insert(interned.clone());
+            Self(interned)
+        })
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::new(&name)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(sym: &Symbol) -> Self {
+        sym.clone()
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(sym: Symbol) -> Self {
+        sym.as_str().to_string()
+    }
+}
+
+impl From<Symbol> for SExp {
+    fn from(sym: Symbol) -> Self {
+        SExp::Atom(Primitive::Symbol(sym.into()))
+    }
+}
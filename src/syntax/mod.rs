@@ -0,0 +1,525 @@
+//! Pattern-based macro transformers, as produced by `syntax-rules`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use super::Primitive::{Symbol, Vector};
+use super::SExp::{self, Atom, Null, Pair};
+use super::{Context, Env, Error, Primitive, Result, SyntaxError};
+
+mod tests;
+
+/// Special forms whose operands introduce new bindings.
+const BINDING_FORMS: [&str; 8] = [
+    "define",
+    "do",
+    "lambda",
+    "let",
+    "let*",
+    "letrec",
+    "letrec*",
+    "named-lambda",
+];
+
+/// Identifiers which are recognized by name rather than looked up, and so
+/// are never renamed when introduced by a template.
+const AUXILIARY_SYNTAX: [&str; 6] = ["else", "=>", "_", "...", "unquote", "unquote-splicing"];
+
+type Bindings = HashMap<String, Binding>;
+
+#[derive(Clone)]
+enum Binding {
+    One(SExp),
+    Many(Vec<Binding>),
+}
+
+struct Rule {
+    pattern: SExp,
+    template: SExp,
+    /// Identifiers bound by binding forms within the template.
+    binders: HashSet<String>,
+}
+
+/// A primitive value that wraps a macro transformer.
+#[derive(Clone)]
+pub struct Macro {
+    name: Option<String>,
+    ellipsis: String,
+    literals: Rc<Vec<String>>,
+    rules: Rc<Vec<Rule>>,
+    envt: Rc<Env>,
+}
+
+impl Macro {
+    /// Build a transformer from the body of a `syntax-rules` form, closing
+    /// over the environment it was defined in.
+    pub fn new(spec: SExp, envt: Rc<Env>) -> std::result::Result<Self, Error> {
+        let (first, rest) = spec.split_car()?;
+
+        // R7RS allows a custom ellipsis identifier before the literals
+        let (ellipsis, literals, rules) = match first {
+            Atom(Symbol(e)) => {
+                let (literals, rules) = rest.split_car()?;
+                (e, literals, rules)
+            }
+            literals => ("...".to_string(), literals, rest),
+        };
+
+        let literals = literals
+            .into_iter()
+            .map(|e| match e {
+                Atom(Symbol(s)) => Ok(s),
+                other => Err(Error::Type {
+                    expected: "symbol",
+                    given: other.type_of().to_string(),
+                }),
+            })
+            .collect::<std::result::Result<_, _>>()?;
+
+        let rules = rules
+            .into_iter()
+            .map(|rule| match rule.len() {
+                2 => {
+                    let (pattern, template) = rule.clone().split_car()?;
+                    if let Pair { .. } = pattern {
+                        let template = template.car()?;
+                        let mut binders = HashSet::new();
+                        collect_binders(&template, &mut binders);
+                        Ok(Rule {
+                            pattern,
+                            template,
+                            binders,
+                        })
+                    } else {
+                        Err(SyntaxError::InvalidSyntaxRule(rule).into())
+                    }
+                }
+                _ => Err(SyntaxError::InvalidSyntaxRule(rule).into()),
+            })
+            .collect::<std::result::Result<_, Error>>()?;
+
+        Ok(Self {
+            name: None,
+            ellipsis,
+            literals: Rc::new(literals),
+            rules: Rc::new(rules),
+            envt,
+        })
+    }
+
+    #[must_use]
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// The environment the macro was defined in.
+    pub(crate) fn env(&self) -> &Rc<Env> {
+        &self.envt
+    }
+
+    /// The same macro, as though it had been defined in another environment.
+    pub(crate) fn with_env(&self, envt: Rc<Env>) -> Self {
+        Self {
+            envt,
+            ..self.clone()
+        }
+    }
+
+    /// Transcribe a macro use (given the operands, without the keyword)
+    /// according to the first rule whose pattern matches.
+    pub(crate) fn expand(&self, args: SExp, ctx: &mut Context) -> Result {
+        for rule in self.rules.iter() {
+            let mut bindings = Bindings::new();
+
+            // the keyword position of the pattern is ignored
+            if let Pair { tail, .. } = &rule.pattern {
+                if self.matches(tail, &args, &mut bindings) {
+                    let mut renames = Renames {
+                        binders: &rule.binders,
+                        names: HashMap::new(),
+                    };
+                    return self.instantiate(&rule.template, &bindings, ctx, &mut renames, false);
+                }
+            }
+        }
+
+        let keyword = SExp::sym(self.name.as_deref().unwrap_or("_"));
+        Err(SyntaxError::NoMatchingRule(args.cons(keyword)).into())
+    }
+
+    fn is_ellipsis(&self, exp: &SExp) -> bool {
+        matches!(exp, Atom(Symbol(s)) if *s == self.ellipsis)
+    }
+
+    fn matches(&self, pattern: &SExp, form: &SExp, bindings: &mut Bindings) -> bool {
+        match pattern {
+            Atom(Symbol(s)) if s == "_" => true,
+            Atom(Symbol(s)) if self.literals.contains(s) => {
+                matches!(form, Atom(Symbol(f)) if f == s)
+            }
+            Atom(Symbol(s)) => {
+                bindings.insert(s.clone(), Binding::One(form.clone()));
+                true
+            }
+            Pair { .. } => self.matches_list(pattern, form, bindings),
+            Atom(Vector(pv)) => match form {
+                Atom(Vector(fv)) => self.matches_list(
+                    &pv.iter().cloned().collect::<SExp>(),
+                    &fv.iter().cloned().collect::<SExp>(),
+                    bindings,
+                ),
+                _ => false,
+            },
+            _ => pattern == form,
+        }
+    }
+
+    fn matches_list(&self, pattern: &SExp, form: &SExp, bindings: &mut Bindings) -> bool {
+        let (pats, pat_tail) = split_list(pattern);
+        let (forms, form_tail) = split_list(form);
+
+        let ellipsis_at = pats.iter().position(|p| self.is_ellipsis(p));
+
+        let (before, repeated, after) = match ellipsis_at {
+            Some(0) => return false,
+            Some(idx) => (&pats[..idx - 1], Some(pats[idx - 1]), &pats[idx + 1..]),
+            None => (&pats[..], None, &pats[0..0]),
+        };
+
+        let min = before.len() + after.len();
+        if forms.len() < min || *pat_tail == Null && *form_tail != Null {
+            return false;
+        }
+
+        let (n_repeated, rest) = match repeated {
+            // all of the list elements are consumed, so any tail pattern
+            // matches the tail of the form
+            Some(_) => (forms.len() - min, form_tail.clone()),
+            None if *pat_tail == Null && forms.len() != min => return false,
+            None => (0, rebuild(&forms[min..], form_tail)),
+        };
+
+        if !before
+            .iter()
+            .zip(&forms)
+            .all(|(p, f)| self.matches(p, f, bindings))
+        {
+            return false;
+        }
+
+        if let Some(rep) = repeated {
+            let vars = self.pattern_vars(rep);
+            let mut seqs = vec![Vec::with_capacity(n_repeated); vars.len()];
+
+            for f in &forms[before.len()..before.len() + n_repeated] {
+                let mut sub = Bindings::new();
+                if !self.matches(rep, f, &mut sub) {
+                    return false;
+                }
+
+                for (v, seq) in vars.iter().zip(seqs.iter_mut()) {
+                    if let Some(b) = sub.remove(v) {
+                        seq.push(b);
+                    }
+                }
+            }
+
+            bindings.extend(vars.into_iter().zip(seqs.into_iter().map(Binding::Many)));
+        }
+
+        after
+            .iter()
+            .zip(&forms[before.len() + n_repeated..])
+            .all(|(p, f)| self.matches(p, f, bindings))
+            && self.matches(pat_tail, &rest, bindings)
+    }
+
+    fn pattern_vars(&self, pattern: &SExp) -> Vec<String> {
+        match pattern {
+            Atom(Symbol(s)) if s == "_" || self.literals.contains(s) || *s == self.ellipsis => {
+                Vec::new()
+            }
+            Atom(Symbol(s)) => vec![s.clone()],
+            Atom(Vector(v)) => v.iter().flat_map(|e| self.pattern_vars(e)).collect(),
+            Pair { head, tail } => {
+                let mut vars = self.pattern_vars(head);
+                vars.extend(self.pattern_vars(tail));
+                vars
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn instantiate(
+        &self,
+        template: &SExp,
+        bindings: &Bindings,
+        ctx: &mut Context,
+        renames: &mut Renames,
+        quoted: bool,
+    ) -> Result {
+        match template {
+            Atom(Symbol(s)) => match bindings.get(s) {
+                Some(Binding::One(e)) => Ok(e.clone()),
+                Some(Binding::Many(_)) => Err(SyntaxError::EllipsisDepth(s.clone()).into()),
+                None => Ok(SExp::sym(&self.rename(s, ctx, renames, quoted))),
+            },
+            Atom(Vector(v)) => {
+                let list = v.iter().cloned().collect();
+                let expanded = self.instantiate(&list, bindings, ctx, renames, quoted)?;
+                Ok(Atom(Vector(expanded.into_iter().collect())))
+            }
+            Pair { .. } => {
+                let (items, tail) = split_list(template);
+
+                // `(... template)` escapes the ellipsis
+                if items.len() == 2 && *tail == Null && self.is_ellipsis(items[0]) {
+                    let escaped = Self {
+                        ellipsis: String::new(),
+                        ..self.clone()
+                    };
+                    return escaped.instantiate(items[1], bindings, ctx, renames, quoted);
+                }
+
+                let quoted = quoted || matches!(items[0], Atom(Symbol(q)) if q == "quote");
+
+                let mut out = Vec::with_capacity(items.len());
+                let mut idx = 0;
+                while idx < items.len() {
+                    let depth = items[idx + 1..]
+                        .iter()
+                        .take_while(|e| self.is_ellipsis(e))
+                        .count();
+
+                    self.instantiate_many(
+                        items[idx], bindings, depth, ctx, renames, quoted, &mut out,
+                    )?;
+                    idx += depth + 1;
+                }
+
+                let tail = self.instantiate(tail, bindings, ctx, renames, quoted)?;
+                Ok(out.into_iter().rev().fold(tail, SExp::cons))
+            }
+            other => Ok(other.clone()),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn instantiate_many(
+        &self,
+        template: &SExp,
+        bindings: &Bindings,
+        depth: usize,
+        ctx: &mut Context,
+        renames: &mut Renames,
+        quoted: bool,
+        out: &mut Vec<SExp>,
+    ) -> std::result::Result<(), Error> {
+        if depth == 0 {
+            out.push(self.instantiate(template, bindings, ctx, renames, quoted)?);
+            return Ok(());
+        }
+
+        let sequences = self
+            .pattern_vars(template)
+            .into_iter()
+            .filter_map(|v| match bindings.get(&v) {
+                Some(Binding::Many(seq)) => Some((v, seq)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let len = match sequences.first() {
+            Some((_, seq)) => seq.len(),
+            None => return Err(SyntaxError::EllipsisDepth(template.to_string()).into()),
+        };
+
+        if let Some((v, _)) = sequences.iter().find(|(_, seq)| seq.len() != len) {
+            return Err(SyntaxError::EllipsisDepth(v.clone()).into());
+        }
+
+        for idx in 0..len {
+            let mut sub = bindings.clone();
+            for (v, seq) in &sequences {
+                sub.insert(v.clone(), seq[idx].clone());
+            }
+            self.instantiate_many(template, &sub, depth - 1, ctx, renames, quoted, out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Identifiers introduced by a template are renamed if the template binds
+    /// them or they do not refer to anything visible where the macro was
+    /// defined, so that they cannot capture bindings at the site of use.
+    /// Those which do refer to something are renamed to an alias for it if
+    /// the same name means something else at the site of use, so that they
+    /// cannot be captured either.
+    fn rename(&self, sym: &str, ctx: &mut Context, renames: &mut Renames, quoted: bool) -> String {
+        if quoted || AUXILIARY_SYNTAX.contains(&sym) || sym == self.ellipsis {
+            return sym.to_string();
+        }
+        if let Some(name) = renames.names.get(sym) {
+            return name.clone();
+        }
+
+        let name = if renames.binders.contains(sym) || !ctx.is_bound_in(sym, &self.envt) {
+            ctx.fresh_symbol(sym)
+        } else {
+            let defined = ctx.binding_scope(sym, &self.envt);
+            let used = ctx.binding_scope(sym, &ctx.current_env());
+            match (&defined, &used) {
+                (None, None) => return sym.to_string(),
+                (Some(d), Some(u)) if Rc::ptr_eq(d, u) => return sym.to_string(),
+                _ => ctx.alias_binding(sym, defined.as_ref()),
+            }
+        };
+
+        renames.names.insert(sym.to_string(), name.clone());
+        name
+    }
+}
+
+/// Fresh names chosen for identifiers during a single expansion.
+struct Renames<'a> {
+    binders: &'a HashSet<String>,
+    names: HashMap<String, String>,
+}
+
+fn collect_binders(template: &SExp, binders: &mut HashSet<String>) {
+    let (items, _) = split_list(template);
+
+    if let Some(Atom(Symbol(form))) = items.first() {
+        if BINDING_FORMS.contains(&form.as_str()) {
+            let (name, rest) = match items.get(1) {
+                Some(Atom(Symbol(name))) if form == "let" => (Some(name), items.get(2)),
+                Some(Atom(Symbol(name))) => (Some(name), None),
+                other => (None, other),
+            };
+            binders.extend(name.cloned());
+
+            // signatures list their parameters directly, while binding lists
+            // name each variable at the start of a clause
+            let is_signature = form == "define" || form.ends_with("lambda");
+            for e in rest.iter().flat_map(|exp| exp.iter()) {
+                let binder = if is_signature {
+                    Some(e)
+                } else {
+                    e.iter().next()
+                };
+                if let Some(Atom(Symbol(s))) = binder {
+                    binders.insert(s.clone());
+                }
+            }
+        }
+    }
+
+    for item in items {
+        if let Pair { .. } = item {
+            collect_binders(item, binders);
+        }
+    }
+}
+
+fn split_list(exp: &SExp) -> (Vec<&SExp>, &SExp) {
+    let mut items = Vec::new();
+    let mut cursor = exp;
+
+    while let Pair { head, tail } = cursor {
+        items.push(&**head);
+        cursor = tail;
+    }
+
+    (items, cursor)
+}
+
+fn rebuild(items: &[&SExp], tail: &SExp) -> SExp {
+    items
+        .iter()
+        .rev()
+        .fold(tail.clone(), |acc, e| acc.cons((*e).clone()))
+}
+
+impl PartialEq for Macro {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.rules, &other.rules)
+    }
+}
+
+impl fmt::Debug for Macro {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl fmt::Display for Macro {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.name {
+            Some(n) => write!(f, "#<macro:{n}>"),
+            None => write!(f, "#<macro>"),
+        }
+    }
+}
+
+impl From<Macro> for SExp {
+    fn from(m: Macro) -> Self {
+        SExp::Atom(Primitive::Macro(m))
+    }
+}
+
+impl Context {
+    /// Expand a form once if it is a use of a macro, otherwise return it
+    /// unchanged. Also returns whether it was expanded.
+    fn expand_once(&mut self, expr: SExp) -> std::result::Result<(SExp, bool), Error> {
+        if let Pair { head, tail } = &expr {
+            if let Atom(Symbol(name)) = &**head {
+                if let Some(Atom(Primitive::Macro(m))) = self.get(name) {
+                    return Ok((m.expand((**tail).clone(), self)?, true));
+                }
+            }
+        }
+
+        Ok((expr, false))
+    }
+
+    /// Expand a form repeatedly until it is no longer a use of a macro,
+    /// without evaluating it. Subforms are not expanded.
+    ///
+    /// # Errors
+    /// Returns `Err` if no rule of a macro matches the form.
+    ///
+    /// # Example
+    /// ```
+    /// use parsley::prelude::*;
+    /// let mut ctx = Context::base();
+    ///
+    /// ctx.run("(define-syntax unless* (syntax-rules () ((_ c e) (if c #f e))))")
+    ///     .unwrap();
+    /// let form = "(unless* (= 1 2) 'ok)".parse::<SExp>().unwrap();
+    /// assert_eq!(ctx.expand(form).unwrap().to_string(), "(if (= 1 2) #f 'ok)");
+    /// ```
+    pub fn expand(&mut self, mut expr: SExp) -> Result {
+        loop {
+            let (expansion, expanded) = self.expand_once(expr)?;
+            if !expanded {
+                return Ok(expansion);
+            }
+            expr = expansion;
+        }
+    }
+
+    /// `(macroexpand form)`: the form with every macro at its head expanded.
+    pub(crate) fn eval_macroexpand(&mut self, expr: SExp) -> Result {
+        let form = self.eval(expr.car()?)?;
+        self.expand(form)
+    }
+
+    /// `(macroexpand-1 form)`: the form with the macro at its head expanded
+    /// once.
+    pub(crate) fn eval_macroexpand_1(&mut self, expr: SExp) -> Result {
+        let form = self.eval(expr.car()?)?;
+        Ok(self.expand_once(form)?.0)
+    }
+}
@@ -0,0 +1,210 @@
+#![cfg(test)]
+
+use super::super::Context;
+use super::SExp;
+
+fn run_all(ctx: &mut Context, code: &str) -> SExp {
+    ctx.run(code).expect("Evaluation failed")
+}
+
+fn expect(code: &str, result: &str) {
+    let mut ctx = Context::base();
+    assert_eq!(run_all(&mut ctx, code), run_all(&mut ctx, result));
+}
+
+#[test]
+fn simple_substitution() {
+    expect(
+        "(define-syntax swap!
+           (syntax-rules ()
+             ((_ a b) (let ((tmp a)) (set! a b) (set! b tmp)))))
+         (define x 1)
+         (define y 2)
+         (swap! x y)
+         (list x y)",
+        "'(2 1)",
+    );
+}
+
+#[test]
+fn literals() {
+    expect(
+        "(define-syntax arrow
+           (syntax-rules (=>)
+             ((_ a => b) (list a b))
+             ((_ a b) 'no-arrow)))
+         (list (arrow 1 => 2) (arrow 1 2))",
+        "'((1 2) no-arrow)",
+    );
+}
+
+#[test]
+fn ellipsis() {
+    expect(
+        "(define-syntax my-list
+           (syntax-rules ()
+             ((_ x ...) (list x ...))))
+         (list (my-list) (my-list 1 2 3))",
+        "'(() (1 2 3))",
+    );
+
+    // elements before and after the ellipsis
+    expect(
+        "(define-syntax ends
+           (syntax-rules ()
+             ((_ a b ... c) '(a c (b ...)))))
+         (ends 1 2 3 4 5)",
+        "'(1 5 (2 3 4))",
+    );
+}
+
+#[test]
+fn nested_ellipsis() {
+    expect(
+        "(define-syntax my-let*
+           (syntax-rules ()
+             ((_ () body ...) (let () body ...))
+             ((_ ((n v) rest ...) body ...)
+              (let ((n v)) (my-let* (rest ...) body ...)))))
+         (define-syntax flatten
+           (syntax-rules ()
+             ((_ (a b ...) ...) '(a ... b ... ...))))
+         (list (my-let* ((x 1) (y (+ x 1))) (* x y))
+               (flatten (1 2 3) (4) (5 6)))",
+        "'(2 (1 4 5 2 3 6))",
+    );
+}
+
+#[test]
+fn recursion() {
+    expect(
+        "(define-syntax my-or
+           (syntax-rules ()
+             ((_) #f)
+             ((_ e) e)
+             ((_ e r ...) (let ((t e)) (if t t (my-or r ...))))))
+         (list (my-or) (my-or #f 2) (my-or #f #f))",
+        "'(#f 2 #f)",
+    );
+}
+
+#[test]
+fn hygiene() {
+    // the `t` introduced by the template does not capture the user's `t`
+    expect(
+        "(define-syntax my-or
+           (syntax-rules ()
+             ((_ a b) (let ((t a)) (if t t b)))))
+         (define t 5)
+         (my-or #f t)",
+        "5",
+    );
+}
+
+#[test]
+fn shadowed_free_identifiers() {
+    // identifiers the template refers to keep their meaning from where the
+    // macro was defined, even if they are bound locally where it is used
+    expect(
+        "(define-syntax inc! (syntax-rules () ((_ v) (set! v (+ v 1)))))
+         (define x 1)
+         (let ((+ -)) (inc! x) x)",
+        "2",
+    );
+    expect(
+        "(define-syntax my-if (syntax-rules () ((_ c a b) (cond (c a) (else b)))))
+         (let ((cond list) (if list)) (my-if #f 1 2))",
+        "2",
+    );
+
+    // including variables, which can still be assigned
+    expect(
+        "(define counter 0)
+         (define-syntax bump! (syntax-rules () ((_) (set! counter (+ counter 1)))))
+         (let ((counter 10)) (bump!) (bump!) (list counter))",
+        "'(10)",
+    );
+    expect(
+        "(define counter 0)
+         (define-syntax bump! (syntax-rules () ((_) (set! counter (+ counter 1)))))
+         (let ((counter 10)) (bump!) (bump!))
+         counter",
+        "2",
+    );
+    expect(
+        "(let ((x 5))
+           (let-syntax ((get-x (syntax-rules () ((_) x))))
+             (let ((x 6)) (get-x))))",
+        "5",
+    );
+}
+
+#[test]
+fn no_match() {
+    let mut ctx = Context::base();
+    run_all(&mut ctx, "(define-syntax one (syntax-rules () ((_ a) a)))");
+    assert!(ctx.run("(one 1 2)").is_err());
+    assert!(ctx.run("(define-syntax two 2)").is_err());
+    assert!(ctx
+        .run("(define-syntax three (syntax-rules () (x)))")
+        .is_err());
+}
+
+#[test]
+fn let_syntax() {
+    expect(
+        "(let-syntax ((double (syntax-rules () ((_ x) (* 2 x)))))
+           (double 21))",
+        "42",
+    );
+
+    // the keyword is only bound within the body
+    let mut ctx = Context::base();
+    run_all(
+        &mut ctx,
+        "(let-syntax ((double (syntax-rules () ((_ x) (* 2 x))))) 1)",
+    );
+    assert!(ctx.run("(double 3)").is_err());
+}
+
+#[test]
+fn letrec_syntax() {
+    expect(
+        "(letrec-syntax
+             ((my-or (syntax-rules ()
+                       ((_) #f)
+                       ((_ e) e)
+                       ((_ e r ...) (let ((t e)) (if t t (my-or r ...)))))))
+           (let ((t 5))
+             (list (my-or) (my-or #f t) (my-or #f #f 3))))",
+        "'(#f 5 3)",
+    );
+}
+
+#[test]
+fn macroexpand() {
+    let mut ctx = Context::base();
+    run_all(
+        &mut ctx,
+        "(define-syntax my-or
+           (syntax-rules ()
+             ((_) #f)
+             ((_ e) e)
+             ((_ e r ...) (if e e (my-or r ...)))))
+         (define-syntax either (syntax-rules () ((_ a b) (my-or a b))))",
+    );
+
+    let expand = |ctx: &mut Context, code| run_all(ctx, code).to_string();
+    assert_eq!(
+        expand(&mut ctx, "(macroexpand-1 '(either 1 2))"),
+        "(my-or 1 2)"
+    );
+    assert_eq!(
+        expand(&mut ctx, "(macroexpand '(either 1 2))"),
+        "(if 1 1 (my-or 2))"
+    );
+    // forms which are not macro uses are returned as they are
+    assert_eq!(expand(&mut ctx, "(macroexpand '(+ 1 2))"), "(+ 1 2)");
+    assert_eq!(expand(&mut ctx, "(macroexpand-1 42)"), "42");
+    assert!(ctx.run("(macroexpand '(either 1))").is_err());
+}
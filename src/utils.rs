@@ -12,12 +12,14 @@ pub fn is_atom_char(c: char) -> bool {
 pub fn is_symbol_char(c: char) -> bool {
     c.is_alphanumeric()
         || c == '-'
+        || c == '.'
         || c == '_'
         || c == '?'
         || c == '!'
         || c == '*'
         || c == '+'
         || c == '/'
+        || c == ':'
         || c == '='
         || c == '<'
         || c == '>'
@@ -50,3 +52,23 @@ pub fn find_closing_delim(
 
     None
 }
+
+/// The number of single character insertions, deletions or substitutions
+/// needed to turn one string into another.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
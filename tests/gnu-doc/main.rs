@@ -182,8 +182,7 @@ def_test! {
     cond
         [FILE_EXPR "cond_1.ss", "greater"]
         [FILE_EXPR "cond_2.ss", "equal"]
-    // FIXME: arrow syntax for cond AND alists
-        // ["(cond ((assv 'b '((a 1) (b 2))) => cadr) (else #f))", 2]
+        ["(cond ((assv 'b '((a 1) (b 2))) => cadr) (else #f))", 2]
 }
 
 def_test! {
@@ -227,7 +226,139 @@ def_test! {
         [FILE "do_2.ss", 25]
 }
 
-// TODO: structs and macros
+def_test! {
+    define_syntax
+        [FILE_EXPR "define_syntax.ss", "(2 1)"]
+        "(define-syntax my-if
+           (syntax-rules (then else)
+             ((_ c then t else f) (cond (c t) (else f)))))"
+        ["(my-if #t then 1 else 2)", 1]
+        ["(my-if #f then 1 else 2)", 2]
+        [IS_ERR "(my-if #f 1 2)"]
+}
+
+def_test! {
+    define_record_type
+        [FILE "define_record_type.ss"]
+        ["(point? p)", true]
+        ["(point? (make-vector 2 1))", false]
+        ["(point? '(1 2))", false]
+        ["(point-x p)", 1]
+        ["(point-y p)", 2]
+        ["(type-of p)", "point"]
+        "(set-point-x! p 5)"
+        ["(point-x p)", 5]
+        "(define q p)"
+        "(set-point-x! (car (list q)) 7)"
+        ["(point-x p)", 7]
+        "(set-point-x! p 5)"
+        ["(equal? (make-point 5 2) p)", true]
+        ["(equal? (make-point 5 3) p)", false]
+        ["(equal? (make-vector 2 5) p)", false]
+        [IS_ERR "(point-x (make-vector 2 1))"]
+        [IS_ERR "(make-point 1)"]
+}
+
+def_test! {
+    dynamic_wind
+        "(define trail '())"
+        "(define (note x) (lambda () (set! trail (cons x trail))))"
+        [EXPR "(dynamic-wind (note 'in) (lambda () 'result) (note 'out))", "result"]
+        [EXPR "trail", "(out in)"]
+        // leaving through a continuation (the trail is built backwards)
+        "(set! trail '())"
+        [EXPR "(call/cc (lambda (k)
+                (dynamic-wind
+                  (note 'in)
+                  (lambda () (k 'escaped) 'finished)
+                  (note 'out))))", "escaped"]
+        [EXPR "trail", "(out in)"]
+        // leaving through an error
+        "(set! trail '())"
+        [IS_ERR "(dynamic-wind (note 'in) (lambda () (car '())) (note 'out))"]
+        [EXPR "trail", "(out in)"]
+        // nested extents are left innermost first
+        "(set! trail '())"
+        "(call/cc (lambda (k)
+           (dynamic-wind
+             (note 'a-in)
+             (lambda () (dynamic-wind (note 'b-in) (lambda () (k 0)) (note 'b-out)))
+             (note 'a-out))))"
+        [EXPR "trail", "(a-out b-out b-in a-in)"]
+}
+
+def_test! {
+    promises
+        ["(force (delay (+ 1 2)))", 3]
+        ["(let ((p (delay (+ 1 2)))) (list (force p) (force p)))", p!("(3 3)")]
+        ["(promise? (delay 1))", true]
+        ["(promise? 1)", false]
+        ["(force 5)", 5]
+        ["(force (make-promise 7))", 7]
+        // the body is evaluated at most once
+        "(define count 0)"
+        "(define p (delay (begin (set! count (+ count 1)) count)))"
+        ["(+ (force p) (force p))", 2]
+        ["count", 1]
+        // delay captures its environment
+        ["(define q (let ((x 10)) (delay x))) (force q)", 10]
+        // delay-force chains do not grow the stack
+        "(define (loop n) (delay-force (if (= n 0) (delay 'done) (loop (- n 1)))))"
+        [EXPR "(force (loop 10000))", "done"]
+}
+
+def_test! {
+    call_cc
+        ["(call/cc (lambda (k) (+ 1 (k 42))))", 42]
+        ["(+ 1 (call/cc (lambda (k) 1)))", 2]
+        ["(+ 1 (call-with-current-continuation (lambda (k) (+ 10 (k 1)))))", 2]
+        ["(procedure? (call/cc (lambda (k) k)))", true]
+        // escaping out of nested calls
+        "(define (find-first pred lst)
+           (call/cc
+             (lambda (return)
+               (map (lambda (x) (if (pred x) (return x) #f)) lst)
+               #f)))"
+        ["(find-first (lambda (x) (> x 2)) '(1 2 3 4))", 3]
+        ["(find-first (lambda (x) (> x 5)) '(1 2 3 4))", false]
+        // the evaluator is left in a consistent state afterwards
+        "(define x 'outer)"
+        "((lambda (x) (call/cc (lambda (k) (k x)))) 'inner)"
+        [EXPR "x", "outer"]
+        // escape-only: the continuation is dead once call/cc returns
+        "(define saved #f)"
+        "(call/cc (lambda (k) (set! saved k)))"
+        [IS_ERR "(saved 1)"]
+}
+
+def_test! {
+    call_ec
+        ["(call/ec (lambda (k) (+ 1 (k 42))))", 42]
+        ["(call-with-escape-continuation (lambda (k) 1))", 1]
+        // early exit from a loop
+        "(define (first-square-over n)
+           (call/ec
+             (lambda (break)
+               (do ((i 0 (+ i 1))) (#f)
+                 (if (> (* i i) n) (break i) #f)))))"
+        ["(first-square-over 50)", 8]
+        // an inner escape can jump past an outer one
+        [EXPR "(call/ec (lambda (outer) (list 'inner (call/ec (lambda (inner) (outer 'out))))))", "out"]
+        [EXPR "(call/ec (lambda (outer) (list 'inner (call/ec (lambda (inner) (inner 'in))))))", "(inner in)"]
+        "(define saved #f)"
+        "(call/ec (lambda (k) (set! saved k)))"
+        [IS_ERR "(saved 1)"]
+}
+
+#[test]
+fn record_display() -> Result<(), Error> {
+    let mut ctx = Context::base();
+    f!(ctx, "define_record_type.ss");
+    assert_eq!(s!(ctx, "p").to_string(), "#<point x=1 y=2>");
+    Ok(())
+}
+
+// TODO: structs
 
 def_test! {
     eqv
@@ -0,0 +1,96 @@
+//! Tail-call conformance: the expression in the tail position of each special
+//! form must be evaluated without growing the stack, including when forms are
+//! nested in each other's tail positions.
+
+use parsley::prelude::*;
+
+/// Every form with a tail position, with `{}` in place of the expression
+/// there.
+const TAIL_FORMS: &[(&str, &str)] = &[
+    ("if", "(if #t {} #f)"),
+    ("if (alternative)", "(if #f #f {})"),
+    ("cond", "(cond ((= n -1) #f) ((> n 0) {}))"),
+    ("cond (else)", "(cond ((= n -1) #f) (else {}))"),
+    ("case", "(case n ((-1) #f) (else {}))"),
+    ("when", "(when #t {})"),
+    ("unless", "(unless #f {})"),
+    ("and", "(and #t {})"),
+    ("or", "(or #f {})"),
+    ("begin", "(begin 0 {})"),
+    ("let", "(let ((x 1)) x {})"),
+    ("let*", "(let* ((x 1) (y x)) {})"),
+    ("letrec", "(letrec ((x 1)) {})"),
+    ("letrec*", "(letrec* ((x 1)) {})"),
+    ("named let", "(let lp ((x 1)) {})"),
+    ("let-values", "(let-values (((a b) (values 1 2))) {})"),
+    ("receive", "(receive (a . b) (values 1 2) {})"),
+    ("do", "(do ((i 0 (+ i 1))) ((= i 1) {}))"),
+    ("lambda", "((lambda () {}))"),
+    ("apply", "(apply (lambda (m) {}) (list n))"),
+];
+
+/// The recursive call which has to be in tail position.
+const CALL: &str = "(f (- n 1))";
+
+/// Call `f`, defined with the given body, `iterations` times in a row.
+fn assert_loops(name: &str, body: &str, iterations: usize) {
+    let mut ctx = Context::base();
+    let code = format!("(define (f n) (if (= n 0) 'done {body})) (f {iterations})");
+
+    match ctx.run(&code) {
+        Ok(result) => assert_eq!(result, SExp::sym("done"), "{}", name),
+        Err(err) => panic!("{}: {}", name, err),
+    }
+}
+
+#[test]
+fn single_forms() {
+    for (name, form) in TAIL_FORMS {
+        assert_loops(name, &form.replace("{}", CALL), 2_000);
+    }
+
+    assert_loops("cond (=>)", "(cond ((- n 1) => f))", 2_000);
+}
+
+#[test]
+fn nested_forms() {
+    // a non-tail call overflows the stack long before this many iterations
+    for (outer_name, outer) in TAIL_FORMS {
+        for (inner_name, inner) in TAIL_FORMS {
+            let name = format!("{inner_name} in {outer_name}");
+            assert_loops(&name, &outer.replace("{}", &inner.replace("{}", CALL)), 300);
+        }
+    }
+}
+
+#[test]
+fn forms_in_do_bodies() {
+    let mut ctx = Context::base();
+
+    let code = "(define count 0)
+                (do ((i 0 (+ i 1))) ((= i 10000) count)
+                  (case (modulo i 2)
+                    ((0) (cond ((> i 5000) (set! count (+ count 1))) (else #f)))
+                    (else (when #t (set! count (+ count 1))))))";
+    assert_eq!(ctx.run(code).unwrap(), SExp::from(7499));
+}
+
+/// Run a million iterations through every form in turn, with a procedure for
+/// each form calling the next, so that any memory held on to by an iteration
+/// adds up. This takes a while in a debug build, so it only runs in a release
+/// build (`cargo test --release`) unless asked for with `--ignored`.
+#[test]
+#[cfg_attr(debug_assertions, ignore)]
+fn million_iterations() {
+    let mut ctx = Context::base();
+    for (i, (_, form)) in TAIL_FORMS.iter().enumerate() {
+        let call = format!("(f{} (- n 1))", (i + 1) % TAIL_FORMS.len());
+        let code = format!(
+            "(define (f{i} n) (if (= n 0) 'done {}))",
+            form.replace("{}", &call)
+        );
+        ctx.run(&code).unwrap();
+    }
+
+    assert_eq!(ctx.run("(f0 1000000)").unwrap(), SExp::sym("done"));
+}